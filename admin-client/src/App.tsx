@@ -32,9 +32,31 @@ function useVotingProgram() {
   }, [connection, wallet]);
 }
 
-function derivePollPda(authority: PublicKey, title: string): PublicKey {
+function deriveCounterPda(authority: PublicKey): PublicKey {
   const [pda] = PublicKey.findProgramAddressSync(
-    [Buffer.from("poll"), authority.toBuffer(), Buffer.from(title)],
+    [Buffer.from("counter"), authority.toBuffer()],
+    PROGRAM_ID
+  );
+  return pda;
+}
+
+// Program-wide poll registry (optional; assigns sequential poll ids once created).
+const REGISTRY_PDA = PublicKey.findProgramAddressSync([Buffer.from("registry")], PROGRAM_ID)[0];
+
+// Protocol fee settings (optional; poll creation is free until `init_config` runs).
+const CONFIG_PDA = PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID)[0];
+
+function deriveIndexPda(authority: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("index"), authority.toBuffer()],
+    PROGRAM_ID
+  );
+  return pda;
+}
+
+function derivePollPda(authority: PublicKey, seq: BN): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("poll"), authority.toBuffer(), seq.toArrayLike(Buffer, "le", 8)],
     PROGRAM_ID
   );
   return pda;
@@ -61,6 +83,7 @@ function App() {
   const program = useVotingProgram();
   const { auth, db } = useMemo(() => initFirebase(), []);
   const [title, setTitle] = useState("Studentski parlament 2025");
+  const [description, setDescription] = useState("");
   const [candidatesText, setCandidatesText] = useState("Ana\nMarko\nIvana");
   const [startInMinutes, setStartInMinutes] = useState(1);
   const [durationMinutes, setDurationMinutes] = useState(60);
@@ -122,23 +145,96 @@ function App() {
       setStatus("Need at least 2 candidates.");
       return;
     }
+    if (new Set(candidates.map((c) => c.toLowerCase())).size !== candidates.length) {
+      setStatus("Candidate names must be distinct (case-insensitive).");
+      return;
+    }
     const startTs = Math.floor(Date.now() / 1000) + startInMinutes * 60;
     const endTs = startTs + durationMinutes * 60;
-    const pollPda = derivePollPda(wallet.publicKey, title);
+    const counterPda = deriveCounterPda(wallet.publicKey);
     setLoading(true);
     setStatus("Sending init_poll...");
     try {
-      // If a poll already exists for the same authority + title, surface a helpful message.
-      const existing = await program.provider.connection.getAccountInfo(pollPda);
-      if (existing) {
-        setStatus("A poll with this title already exists for this wallet. Change the title to create a new one.");
-        setLoading(false);
-        return;
-      }
+      // The next poll address comes from this wallet's poll counter (0 before the first poll).
+      const counter = await (program.account as any).pollCounter.fetchNullable(counterPda);
+      const seq: BN = counter ? counter.count : new BN(0);
+      const pollPda = derivePollPda(wallet.publicKey, seq);
+      const registry = await program.provider.connection.getAccountInfo(REGISTRY_PDA);
+      const indexPda = deriveIndexPda(wallet.publicKey);
+      const index = await program.provider.connection.getAccountInfo(indexPda);
+      const config = await (program.account as any).config.fetchNullable(CONFIG_PDA);
+      const [creatorPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("creator"), wallet.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
+      const [creatorStatsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("creator_stats"), wallet.publicKey.toBuffer()],
+        PROGRAM_ID
+      );
       await program.methods
-        .initPoll(title, candidates, new BN(startTs), new BN(endTs))
+        .initPoll(title, description, candidates, new BN(startTs), new BN(endTs), {
+          finalizeBountyLamports: new BN(0),
+          allowlistRequired: false,
+          allowlistRoot: null,
+          gateMint: null,
+          minBalance: new BN(0),
+          gateCollection: null,
+          minLamports: new BN(0),
+          weighting: { equal: {} },
+          weightRoot: null,
+          maxVoters: 0,
+          authorityCanVote: true,
+          votesPerWallet: 1,
+          allowRepeatCandidate: false,
+          registrar: null,
+          ballotType: { plurality: {} },
+          allowUnderspend: false,
+          kind: { election: {} },
+          allowAbstain: false,
+          allowDownvotes: false,
+          numWinners: 1,
+          majorityBps: 0,
+          allowWriteIns: false,
+          commitReveal: false,
+          revealDeadlineTs: new BN(0),
+          hideTallies: false,
+          quorum: new BN(0),
+          tieBreak: { none: {} },
+          registrationEndTs: new BN(0),
+          sharded: false,
+          tallyShards: 1,
+          maxCandidates: 8,
+          voteFeeLamports: new BN(0),
+          feeBeneficiary: null,
+          receiptlessVoters: 0,
+          candidateWallets: [], // Empty: the authority is recorded for every candidate.
+          rewardMint: null,
+          rewardAmount: new BN(0),
+          voterRetentionSecs: 30 * 24 * 60 * 60, // Voter records may be swept 30 days after the end.
+          candidateFeeLamports: new BN(0), // Free self-registration.
+          feeMint: null, // Set (with `feeAmount`) to charge ballots in a token instead of lamports.
+          feeAmount: new BN(0),
+          burnMint: null, // Set (with `burnAmount`) to make every voter burn that many tokens.
+          burnAmount: new BN(0),
+          certifier: null, // Set (with `certificationDeadlineTs`) to require an observer's `certifyResults`.
+          certificationDeadlineTs: new BN(0),
+        })
         .accounts({
+          counter: counterPda,
           poll: pollPda,
+          rankedTally: null, // Ranked-choice polls pass the `["ranked", poll]` PDA.
+          treasury: null, // Polls with a vote fee pass the `["treasury", poll]` PDA.
+          feeVaultAuthority: null, // Token-fee polls pass the `["fees", poll]` PDA, its ATA as `feeVault`,
+          feeVault: null, // the fee mint, its token program and the associated token program.
+          feeMint: null,
+          tokenProgram: null,
+          associatedTokenProgram: null,
+          registry: registry ? REGISTRY_PDA : null, // Register the poll once `init_registry` ran.
+          authorityIndex: index ? indexPda : null, // List it in this wallet's index, if created.
+          protocolConfig: CONFIG_PDA,
+          feeRecipient: config && !config.feeLamports.isZero() ? config.feeRecipient : null, // Pays the protocol fee.
+          creatorCredential: config?.creatorsRestricted ? creatorPda : null, // Vetted creators only, when restricted.
+          creatorStats: config && config.maxPollsPerWindow > 0 ? creatorStatsPda : null, // Counts rate-limited creations.
           authority: wallet.publicKey,
           systemProgram: SystemProgram.programId,
         })
@@ -265,6 +361,15 @@ function App() {
             <input value={title} onChange={(e) => setTitle(e.target.value)} />
           </label>
 
+          <label>
+            Description (optional)
+            <textarea
+              value={description}
+              onChange={(e) => setDescription(e.target.value)}
+              rows={3}
+            />
+          </label>
+
           <label>
             Candidates (one per line)
             <textarea
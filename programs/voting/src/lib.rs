@@ -1,144 +1,6964 @@
 use anchor_lang::prelude::*; // Anchor prelude brings in common types/macros.
+use anchor_lang::system_program; // System program CPI helpers for lamport transfers.
+use anchor_lang::Discriminator; // Account discriminators for hand-checked legacy layouts.
+use anchor_lang::solana_program::sysvar::slot_hashes; // Entropy for random tie-breaks.
 
-/// Public program id generated after `anchor keys list`.
-/// Replace this with your actual program id and keep it in sync with Anchor.toml.
+pub mod merkle; // Merkle allowlist proofs (tree builder behind the `client` feature).
+pub mod token; // Read-only SPL token account parsing for token-gated polls.
+pub mod stake; // Read-only native stake account parsing for stake-weighted polls.
+#[cfg(feature = "nft-gate")]
+pub mod metadata; // Metaplex metadata parsing for collection-gated polls.
+#[cfg(feature = "receipt-nft")]
+pub mod receipt; // Mint + metadata CPIs for participation receipts.
+
+// Public program id generated after `anchor keys list`.
+// Replace this with your actual program id and keep it in sync with Anchor.toml.
 declare_id!("DddwKhB21GsneUinJyEN7Uax3BoePhCgqcU68FTWX7bi"); // Synced to deployed program ID.
 
+/// How long after end_ts a closed poll may still be extended (24h).
+/// Past this, results people already relied on can't be reopened.
+pub const EXTEND_GRACE_SECS: i64 = 24 * 60 * 60;
+
+/// How long after end_ts a poll still holding its creation deposit counts as abandoned, so
+/// `sweep_abandoned` may forfeit the deposit (90 days).
+pub const ABANDON_SECS: i64 = 90 * 24 * 60 * 60;
+
+/// Default `voter_retention_secs`: how long Voter records stay after voting (and reveals)
+/// end before `sweep_voter` may close them (30 days).
+pub const VOTER_RETENTION_SECS: u32 = 30 * 24 * 60 * 60;
+
+/// Lamports of a swept Voter record's rent paid to whoever ran `sweep_voter`.
+pub const VOTER_SWEEP_BOUNTY: u64 = 10_000;
+
+/// Shortest and longest voting window (`end_ts - start_ts`) a poll may have: 10 minutes and
+/// 365 days. Also applies to `extend_poll`.
+pub const MIN_DURATION_SECS: i64 = 10 * 60;
+pub const MAX_DURATION_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// How far in the past a new poll's `start_ts` may lie (5 minutes), so clients whose clocks
+/// run a little ahead of the cluster can still pass "now".
+pub const START_SKEW_SECS: i64 = 5 * 60;
+
+/// Latest `start_ts` a new poll may have, relative to now (180 days).
+pub const MAX_START_DELAY_SECS: i64 = 180 * 24 * 60 * 60;
+
+/// How long after finalize a result may still be disputed with `flag_dispute` (7 days).
+pub const DISPUTE_WINDOW_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// Layout version written to new Poll accounts.
+/// v1: PDA seeded by title; v2: PDA seeded by the per-authority `PollCounter`.
+pub const POLL_VERSION: u8 = 2;
+
+/// Layout version written to new Voter records.
+pub const VOTER_VERSION: u8 = 1;
+
+/// Longest poll title in bytes (account space) and in characters, so multibyte text fits.
+pub const MAX_TITLE_BYTES: usize = 128;
+pub const MAX_TITLE_CHARS: usize = 64;
+
+/// Longest candidate name in bytes (account space) and in characters.
+pub const MAX_NAME_BYTES: usize = 64;
+pub const MAX_NAME_CHARS: usize = 32;
+
+/// Upper bound for `votes_per_wallet` (sizes `Voter::choices`).
+pub const MAX_VOTES_PER_WALLET: u8 = 8;
+
+/// Most delegation links `vote_as_delegate` follows, counting the signer's own delegation.
+pub const MAX_DELEGATION_DEPTH: usize = 4;
+
+/// Most credentials `register_voters_batch` creates in one call (keeps it within compute and
+/// transaction size limits).
+pub const MAX_BATCH_REGISTER: usize = 20;
+
+/// Highest score a `Score` ballot may give a candidate.
+pub const MAX_SCORE: u8 = 10;
+
+/// Candidate cap of a zero-copy `PollLarge`.
+pub const MAX_LARGE_CANDIDATES: usize = 64;
+
+/// Most Voter records `tabulate` counts in one call.
+pub const MAX_TABULATE_BATCH: usize = 20;
+
+/// Most counter shards per candidate on a sharded poll.
+pub const MAX_TALLY_SHARDS: u8 = 8;
+
+/// Most polls an `AuthorityIndex` lists (keeps the account under ~8 KB).
+pub const MAX_INDEXED_POLLS: usize = 256;
+
+/// Newest poll addresses the `Registry` keeps.
+pub const REGISTRY_RECENT: usize = 16;
+
+/// Bytes of the spent-leaf bitmap a receiptless poll can have, and the voters it covers.
+pub const RECEIPTLESS_BITMAP_BYTES: usize = 512;
+pub const MAX_RECEIPTLESS_VOTERS: u16 = (RECEIPTLESS_BITMAP_BYTES * 8) as u16;
+
+/// Highest `max_candidates` `expand_poll` can set (plurality elections only; 8 otherwise).
+pub const MAX_EXPANDED_CANDIDATES: u8 = 16;
+
+/// Most credentials a `ParticipationBitmap` can index (8 KB of bits, under the 10 KB `init` limit).
+pub const MAX_PARTICIPANTS: u32 = 65_536;
+
+/// The two options of every referendum: index 0 = Yes, 1 = No.
+pub const REFERENDUM_OPTIONS: [&str; 2] = ["Yes", "No"];
+
+/// Counters in `RankedTally::counts`: each of 8 candidates sits in 128 subsets.
+pub const RANKED_COUNTERS: usize = 8 << 7;
+
 #[program]
 pub mod voting {
     use super::*; // Bring outer scope into module for easy access.
 
+    /// Creates the program-wide `Registry` (`[b"registry"]`), once. From then on `init_poll` and
+    /// `init_referendum` calls that pass it give their polls sequential `poll_id`s; polls
+    /// created without it (or before it existed) have none and work as before.
+    pub fn init_registry(ctx: Context<InitRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.bump = ctx.bumps.registry; // Record bump used for PDA derivation.
+        Ok(())
+    }
+
+    /// Creates the signer's `AuthorityIndex` (`[b"index", authority]`), empty. `init_poll` and
+    /// `init_referendum` calls that pass it append the new poll, growing the account by one
+    /// entry at the authority's expense, up to `MAX_INDEXED_POLLS` (`IndexFull` after that).
+    pub fn init_authority_index(ctx: Context<InitAuthorityIndex>) -> Result<()> {
+        let index = &mut ctx.accounts.authority_index;
+        index.authority = ctx.accounts.authority.key(); // Whose polls these are.
+        index.bump = ctx.bumps.authority_index; // Record bump used for PDA derivation.
+        Ok(())
+    }
+
+    /// Creates the program-wide `Config` (`[b"config"]`), once. Only the program's upgrade
+    /// authority may call it and becomes the protocol admin. From then on every poll creation
+    /// pays `fee_lamports` to `fee_recipient`, and `init_poll` also locks a refundable
+    /// `creation_deposit_lamports` in the poll; without a config, creating polls is free.
+    pub fn init_config(
+        ctx: Context<InitConfig>,
+        fee_lamports: u64,              // Non-refundable fee per poll.
+        fee_recipient: Pubkey,          // Protocol treasury.
+        creation_deposit_lamports: u64, // Refundable deposit per `init_poll` poll.
+        deposit_min_ballots: u32,       // Ballots a finalized poll needs to release it.
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.admin = ctx.accounts.admin.key(); // Controls the fee from now on.
+        config.fee_lamports = fee_lamports;
+        config.fee_recipient = fee_recipient;
+        config.creation_deposit_lamports = creation_deposit_lamports;
+        config.deposit_min_ballots = deposit_min_ballots;
+        config.bump = ctx.bumps.config; // Record bump used for PDA derivation.
+        config.pending_admin = None; // No handover in progress.
+        config.paused = false;
+        config.creators_restricted = false; // Anyone may create polls until `set_creators_restricted`.
+        config.max_polls_per_window = 0; // Unlimited until `set_creation_limit`.
+        config.creation_window_secs = 0;
+        Ok(())
+    }
+
+    /// Changes the protocol fee, deposit and treasury (protocol admin only). Zero amounts make
+    /// poll creation free again; polls already created keep the deposit terms they got.
+    pub fn update_config(
+        ctx: Context<UpdateConfig>,
+        fee_lamports: u64,              // Non-refundable fee per poll.
+        fee_recipient: Pubkey,          // Protocol treasury.
+        creation_deposit_lamports: u64, // Refundable deposit per `init_poll` poll.
+        deposit_min_ballots: u32,       // Ballots a finalized poll needs to release it.
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.fee_lamports = fee_lamports;
+        config.fee_recipient = fee_recipient;
+        config.creation_deposit_lamports = creation_deposit_lamports;
+        config.deposit_min_ballots = deposit_min_ballots;
+        Ok(())
+    }
+
+    /// Starts handing the protocol admin role to `new_admin` (protocol admin only). Nothing
+    /// changes until `new_admin` signs `accept_admin`, so a mistyped key can't lock the config;
+    /// calling it again replaces the pending admin, and passing the current admin cancels.
+    pub fn transfer_admin(ctx: Context<UpdateConfig>, new_admin: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pending_admin = if new_admin == config.admin { None } else { Some(new_admin) };
+        Ok(())
+    }
+
+    /// Completes a handover started by `transfer_admin`: the signer must be the pending admin
+    /// and becomes the protocol admin.
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let signer = ctx.accounts.new_admin.key();
+        require!(config.pending_admin == Some(signer), VotingError::NotPendingAdmin);
+        config.admin = signer;
+        config.pending_admin = None;
+        Ok(())
+    }
+
+    /// Pauses or resumes the whole program (protocol admin only). While paused, poll creation,
+    /// ballots, candidate registration, delegation and funding fail with `ProtocolPaused`;
+    /// finalizing, refunds, withdrawals, unlocks and closing accounts keep working so no funds
+    /// are trapped.
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.config.paused = paused;
+        Ok(())
+    }
+
+    /// Turns the poll creator allowlist on or off (protocol admin only). While on, every poll
+    /// creation needs the authority's `CreatorCredential` (see `add_creator`); polls that
+    /// already exist are unaffected.
+    pub fn set_creators_restricted(ctx: Context<UpdateConfig>, restricted: bool) -> Result<()> {
+        ctx.accounts.config.creators_restricted = restricted;
+        Ok(())
+    }
+
+    /// Caps how many polls one authority may create per `window_secs` (protocol admin only);
+    /// `max_polls = 0` lifts the limit. Counts live in each authority's `CreatorStats` PDA.
+    pub fn set_creation_limit(ctx: Context<UpdateConfig>, max_polls: u32, window_secs: u32) -> Result<()> {
+        require!(max_polls == 0 || window_secs > 0, VotingError::InvalidAmount); // A limit needs a window.
+        let config = &mut ctx.accounts.config;
+        config.max_polls_per_window = max_polls;
+        config.creation_window_secs = window_secs;
+        Ok(())
+    }
+
+    /// Lets `wallet` create polls while creators are restricted (protocol admin only), by
+    /// creating its `CreatorCredential` PDA `[b"creator", wallet]`. The admin pays the rent.
+    pub fn add_creator(ctx: Context<AddCreator>, wallet: Pubkey) -> Result<()> {
+        let credential = &mut ctx.accounts.creator_credential;
+        credential.wallet = wallet;
+        credential.bump = ctx.bumps.creator_credential; // Record bump used for PDA derivation.
+        Ok(())
+    }
+
+    /// Revokes `wallet`'s `CreatorCredential` (protocol admin only); the rent goes back to the
+    /// admin. The wallet's existing polls keep working.
+    pub fn remove_creator(_ctx: Context<RemoveCreator>, _wallet: Pubkey) -> Result<()> {
+        // Credential is closed by the `close = admin` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Forfeits the creation deposit of an abandoned poll to the protocol treasury. Anyone may
+    /// call it once `ABANDON_SECS` have passed after end_ts and the deposit was never released
+    /// (the poll was not finalized with `deposit_min_ballots` ballots). The authority can then
+    /// `close_poll` for the rent.
+    pub fn sweep_abandoned(ctx: Context<SweepAbandoned>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll;
+        let deposit = poll.creation_deposit;
+        require!(deposit > 0, VotingError::NoDeposit); // Released, swept, or never charged.
+        let abandoned_at = poll.end_ts.checked_add(ABANDON_SECS).ok_or(VotingError::Overflow)?;
+        require!(clock.unix_timestamp > abandoned_at, VotingError::PollStillOpen);
+        poll.creation_deposit = 0; // Sweep only once.
+        let poll_info = poll.to_account_info(); // Program-owned: debit directly.
+        let recipient_info = ctx.accounts.fee_recipient.to_account_info();
+        **poll_info.try_borrow_mut_lamports()? = poll_info
+            .lamports()
+            .checked_sub(deposit)
+            .ok_or(VotingError::Overflow)?;
+        **recipient_info.try_borrow_mut_lamports()? = recipient_info
+            .lamports()
+            .checked_add(deposit)
+            .ok_or(VotingError::Overflow)?;
+        Ok(())
+    }
+
     /// Creates a new poll with a title, candidates, and schedule.
     /// Authority pays rent for the poll account and becomes the poll admin.
-    pub fn init_poll(
-        ctx: Context<InitPoll>,        // Accounts context for this instruction.
+    /// The poll PDA is `[b"poll", authority, seq.to_le_bytes()]` where `seq` is the authority's
+    /// `PollCounter` value before this call; titles are plain data and may repeat.
+    pub fn init_poll<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitPoll<'info>>, // Accounts context (Tally PDAs in `remaining_accounts`).
         title: String,                // Poll title string.
+        description: String,          // Longer explanation (may be empty).
         candidates: Vec<String>,      // Candidate names.
         start_ts: i64,                // Unix start timestamp.
         end_ts: i64,                  // Unix end timestamp.
+        options: PollOptions,         // Optional per-poll settings.
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        create_poll(
+            NewPoll {
+                counter: &mut accounts.counter,
+                poll: &mut accounts.poll,
+                ranked_tally: accounts.ranked_tally.as_ref(),
+                treasury: accounts.treasury.as_ref(),
+                registry: accounts.registry.as_mut(),
+                authority_index: accounts.authority_index.as_mut(),
+                protocol_config: &accounts.protocol_config,
+                fee_recipient: accounts.fee_recipient.as_ref(),
+                creator_credential: accounts.creator_credential.as_ref(),
+                creator_stats: accounts.creator_stats.as_mut(),
+                authority: &accounts.authority,
+                system_program: &accounts.system_program,
+                remaining_accounts: ctx.remaining_accounts,
+                program_id: ctx.program_id,
+                counter_bump: ctx.bumps.counter,
+                poll_bump: ctx.bumps.poll,
+                ranked_bump: ctx.bumps.ranked_tally,
+                treasury_bump: ctx.bumps.treasury,
+                creator_stats_bump: ctx.bumps.creator_stats,
+                fee_vault: FeeVaultAccounts {
+                    vault: accounts.fee_vault.as_ref(),
+                    vault_authority: accounts.fee_vault_authority.as_ref(),
+                    mint: accounts.fee_mint.as_ref(),
+                    token_program: accounts.token_program.as_ref(),
+                    associated_token_program: accounts.associated_token_program.as_ref(),
+                },
+            },
+            title,
+            description,
+            candidates,
+            start_ts,
+            end_ts,
+            options,
+        )
+    }
+
+    /// Creates a Yes/No referendum: like `init_poll` with the options "Yes" (index 0) and "No"
+    /// (index 1); it passes at finalize if yes-votes reach `pass_bps` of all votes cast
+    /// (e.g. 5000 = simple majority, 6667 = two-thirds).
+    pub fn init_referendum<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitReferendum<'info>>,
+        title: String,
+        description: String,
+        start_ts: i64,
+        end_ts: i64,
+        pass_bps: u16,            // Share of yes-votes needed to pass, in basis points.
+        options: PollOptions,     // Other settings; `kind` and `max_candidates` are overridden.
+    ) -> Result<()> {
+        let accounts = &mut *ctx.accounts;
+        create_poll(
+            NewPoll {
+                counter: &mut accounts.counter,
+                poll: &mut accounts.poll,
+                ranked_tally: accounts.ranked_tally.as_ref(),
+                treasury: accounts.treasury.as_ref(),
+                registry: accounts.registry.as_mut(),
+                authority_index: accounts.authority_index.as_mut(),
+                protocol_config: &accounts.protocol_config,
+                fee_recipient: accounts.fee_recipient.as_ref(),
+                creator_credential: accounts.creator_credential.as_ref(),
+                creator_stats: accounts.creator_stats.as_mut(),
+                authority: &accounts.authority,
+                system_program: &accounts.system_program,
+                remaining_accounts: ctx.remaining_accounts,
+                program_id: ctx.program_id,
+                counter_bump: ctx.bumps.counter,
+                poll_bump: ctx.bumps.poll,
+                ranked_bump: ctx.bumps.ranked_tally,
+                treasury_bump: ctx.bumps.treasury,
+                creator_stats_bump: ctx.bumps.creator_stats,
+                fee_vault: FeeVaultAccounts {
+                    vault: accounts.fee_vault.as_ref(),
+                    vault_authority: accounts.fee_vault_authority.as_ref(),
+                    mint: accounts.fee_mint.as_ref(),
+                    token_program: accounts.token_program.as_ref(),
+                    associated_token_program: accounts.associated_token_program.as_ref(),
+                },
+            },
+            title,
+            description,
+            REFERENDUM_OPTIONS.iter().map(|o| o.to_string()).collect(),
+            start_ts,
+            end_ts,
+            PollOptions { kind: PollKind::Referendum { pass_bps }, max_candidates: 2, ..options },
+        )
+    }
+
+    /// Starts a runoff between the top two candidates of a finalized plurality election whose
+    /// leader got less than `majority_bps` of the votes (`RunoffNotNeeded` otherwise, or when
+    /// runoffs are off). The runoff is a brand-new poll under the same authority (next counter
+    /// seq) with the parent's title, description and eligibility settings (allowlists, gates,
+    /// weighting, registrar, caps), linked via `runoff_of`. Voter PDAs are per poll, so
+    /// everyone may vote again. A tie for second place fails with `RunoffTie`; one runoff per
+    /// poll (`RunoffExists`).
+    pub fn create_runoff(ctx: Context<CreateRunoff>, start_ts: i64, end_ts: i64) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        validate_schedule(start_ts, end_ts, Clock::get()?.unix_timestamp)?; // Sane window.
+        let parent = &mut ctx.accounts.parent; // Finalized poll being re-run.
+        require!(parent.finalized, VotingError::NotFinalized); // Results must be frozen.
+        require!(
+            parent.results_certified(Clock::get()?.unix_timestamp),
+            VotingError::NotCertified
+        ); // Co-signed, or the certifier let the deadline pass.
+        require!(parent.runoff.is_none(), VotingError::RunoffExists); // Only once.
+        require!(
+            parent.ballot_type == BallotType::Plurality
+                && parent.kind == PollKind::Election
+                && !parent.allow_downvotes,
+            VotingError::WrongBallotType
+        ); // Leader share is only meaningful for plain vote counts.
+        let order = runoff_finalists(&parent.votes, parent.majority_bps)?; // Leader, runner-up.
+
+        let options = PollOptions {
+            allowlist_required: parent.allowlist_required,
+            allowlist_root: parent.allowlist_root,
+            gate_mint: parent.gate_mint,
+            min_balance: parent.min_balance,
+            gate_collection: parent.gate_collection,
+            min_lamports: parent.min_lamports,
+            weighting: parent.weighting,
+            weight_root: parent.weight_root,
+            max_voters: parent.max_voters,
+            authority_can_vote: parent.authority_can_vote,
+            registrar: parent.registrar,
+            tie_break: parent.tie_break,
+            receiptless_voters: parent.receiptless_voters,
+            ..PollOptions::default()
+        }; // Same electorate; plain single-winner ballot.
+        let counter = &mut ctx.accounts.counter; // Per-authority poll counter.
+        let seq = counter.count; // Sequence number used in the runoff's seeds.
+        counter.authority = ctx.accounts.authority.key(); // Owner of the counter.
+        counter.bump = ctx.bumps.counter; // Record bump used for PDA derivation.
+        counter.count = seq.checked_add(1).ok_or(VotingError::Overflow)?; // Next poll gets a fresh address.
+
+        let runoff = &mut ctx.accounts.runoff; // New poll account.
+        runoff.version = POLL_VERSION; // Current layout.
+        runoff.seq = seq; // Lets clients re-derive the PDA.
+        runoff.authority = ctx.accounts.authority.key(); // Same admin.
+        runoff.title = parent.title.clone();
+        runoff.description = parent.description.clone();
+        runoff.candidates = vec![
+            parent.candidates[order[0]].clone(),
+            parent.candidates[order[1]].clone(),
+        ]; // Leader first.
+        runoff.votes = vec![0; 2];
+        runoff.downvotes = vec![0; 2];
+        runoff.last_increment_ts = vec![0; 2];
+        runoff.candidate_wallets = vec![parent.candidate_wallets[order[0]], parent.candidate_wallets[order[1]]];
+        runoff.approved = vec![true; 2];
+        runoff.start_ts = start_ts;
+        runoff.end_ts = end_ts;
+        runoff.bump = ctx.bumps.runoff; // Record bump used for PDA derivation.
+        runoff.apply_options(&options); // Copied eligibility settings.
+        runoff.runoff_of = Some(parent.key()); // Link back to the first round.
+        parent.runoff = Some(runoff.key()); // Blocks a second runoff.
+
+        emit!(PollCreated {
+            poll: runoff.key(),
+            authority: runoff.authority,
+            title: runoff.title.clone(),
+            description: runoff.description.clone(),
+            start_ts,
+            end_ts,
+            candidate_count: 2,
+            poll_id: None,
+        });
+        Ok(())
+    }
+
+    /// Creates a `PollLarge`: a zero-copy poll with up to `MAX_LARGE_CANDIDATES` candidates and
+    /// plain one-wallet-one-vote plurality ballots (no gates, weighting or other options).
+    /// Shares the authority's `PollCounter` and PDA scheme with `init_poll`. A transaction
+    /// holds only so many names; `add_candidates_large` appends the rest before voting opens.
+    pub fn init_poll_large(
+        ctx: Context<InitPollLarge>,
+        title: String,           // Poll title (up to 64 bytes).
+        candidates: Vec<String>, // Initial candidate names (may be fewer than two for now).
+        start_ts: i64,           // Unix start timestamp.
+        end_ts: i64,             // Unix end timestamp.
+    ) -> Result<()> {
+        validate_title(&title)?; // Same rules as init_poll.
+        require!(title.len() <= 64, VotingError::TitleTooLong); // Fixed-size title field.
+        validate_schedule(start_ts, end_ts, Clock::get()?.unix_timestamp)?; // Sane window.
+        let config = charge_protocol_fee(
+            &ctx.accounts.protocol_config,
+            ctx.accounts.fee_recipient.as_ref(),
+            ctx.accounts.creator_credential.as_ref(),
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+        count_creation(
+            config.as_ref(),
+            ctx.accounts.creator_stats.as_deref_mut(),
+            ctx.accounts.authority.key(),
+            ctx.bumps.creator_stats,
+            Clock::get()?.unix_timestamp,
+        )?; // Per-wallet rate limit.
+        let counter = &mut ctx.accounts.counter; // Per-authority poll counter (created on first use).
+        let seq = counter.count; // Sequence number used in this poll's seeds.
+        counter.authority = ctx.accounts.authority.key(); // Owner of the counter.
+        counter.bump = ctx.bumps.counter; // Record bump used for PDA derivation.
+        counter.count = seq.checked_add(1).ok_or(VotingError::Overflow)?; // Next poll gets a fresh address.
+
+        let mut poll = ctx.accounts.poll.load_init()?; // Fresh zero-filled account.
+        poll.authority = ctx.accounts.authority.key(); // Poll admin.
+        poll.seq = seq; // Lets clients re-derive the PDA.
+        poll.start_ts = start_ts;
+        poll.end_ts = end_ts;
+        poll.title[..title.len()].copy_from_slice(title.as_bytes()); // Zero padded.
+        poll.bump = ctx.bumps.poll; // Record bump used for PDA derivation.
+        poll.push_candidates(&candidates)?;
+        emit!(PollCreated {
+            poll: ctx.accounts.poll.key(),
+            authority: poll.authority,
+            title,
+            description: String::new(),
+            start_ts,
+            end_ts,
+            candidate_count: poll.candidate_count,
+            poll_id: None,
+        });
+        Ok(())
+    }
+
+    /// Appends candidates to a `PollLarge` before voting opens (same name rules as `init_poll`).
+    pub fn add_candidates_large(ctx: Context<AddCandidatesLarge>, candidates: Vec<String>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let mut poll = ctx.accounts.poll.load_mut()?; // Zero-copy view of the poll.
+        require!(clock.unix_timestamp < poll.start_ts, VotingError::PollStarted); // No ballots exist yet.
+        poll.push_candidates(&candidates)
+    }
+
+    /// Casts a one-wallet-one-vote ballot on a `PollLarge`. The tally is updated in place
+    /// through the `AccountLoader`, so cost doesn't grow with the candidate count. The Voter
+    /// record uses the usual `[b"voter", poll, wallet]` seeds; a second ballot fails because
+    /// the record already exists.
+    pub fn vote_large(ctx: Context<VoteLarge>, candidate_idx: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let mut poll = ctx.accounts.poll.load_mut()?; // Zero-copy view of the poll.
+        let now = clock.unix_timestamp;
+        let new_count = poll.count_vote(candidate_idx, now)?; // In place, no decoding.
+
+        let voter = &mut ctx.accounts.voter; // Fresh record.
+        voter.version = VOTER_VERSION; // Current layout.
+        voter.has_voted = true;
+        voter.poll = ctx.accounts.poll.key();
+        voter.wallet = ctx.accounts.wallet.key();
+        voter.bump = ctx.bumps.voter;
+        voter.candidate_idx = candidate_idx;
+        voter.voted_at = now;
+        voter.weight = 1; // Equal weights only.
+        voter.votes_used = 1;
+        voter.choices[0] = candidate_idx;
+        emit!(VoteCast {
+            poll: voter.poll,
+            wallet: voter.wallet,
+            candidate_idx,
+            weight: 1,
+            new_count,
+            against: false,
+        });
+        Ok(())
+    }
+
+    /// Creates a split poll: a `PollConfig` with the texts and schedule, written only here,
+    /// and its `PollTally` (`[b"poll_tally", config]`) holding the counts. `vote_v2` then
+    /// write-locks only the tally, and indexers can cache the config. Same counter, PDA
+    /// scheme and text limits as `init_poll`; ballots are one-wallet-one-vote plurality
+    /// without any `PollOptions` features.
+    pub fn init_poll_v2(
+        ctx: Context<InitPollV2>,
+        title: String,           // Poll title (up to 64 bytes).
+        description: String,     // What is being decided (up to 256 bytes, may be empty).
+        candidates: Vec<String>, // Candidate names (2..=8).
+        start_ts: i64,           // Unix start timestamp.
+        end_ts: i64,             // Unix end timestamp.
     ) -> Result<()> {
         require!(candidates.len() >= 2, VotingError::NotEnoughCandidates); // Need at least two choices.
         require!(candidates.len() <= 8, VotingError::TooManyCandidates); // Cap list size for account space.
-        require!(title.len() <= 64, VotingError::TitleTooLong); // Title length bound.
-        require!(start_ts < end_ts, VotingError::BadSchedule); // Start must precede end.
-        for name in candidates.iter() {
-            require!(!name.is_empty(), VotingError::EmptyCandidateName); // No empty candidate names.
-            require!(name.len() <= 32, VotingError::CandidateNameTooLong); // Candidate length bound.
-        }
-
-        let poll = &mut ctx.accounts.poll; // Mutable handle to the poll account being created.
-        poll.authority = ctx.accounts.authority.key(); // Store authority pubkey.
-        poll.title = title; // Save title string.
-        poll.candidates = candidates; // Save candidate list.
-        poll.votes = vec![0; poll.candidates.len()]; // Initialize vote counts to zero.
-        poll.start_ts = start_ts; // Save start time.
-        poll.end_ts = end_ts; // Save end time.
-        poll.bump = ctx.bumps.poll; // Record bump used for PDA derivation.
+        validate_title(&title)?; // Length and character rules.
+        require!(description.len() <= 256, VotingError::DescriptionTooLong); // Description length bound.
+        validate_schedule(start_ts, end_ts, Clock::get()?.unix_timestamp)?; // Sane window.
+        for (i, name) in candidates.iter().enumerate() {
+            validate_candidate_name(name)?; // Non-empty, length bound.
+            require_new_candidate(candidates[..i].iter().map(String::as_str), name)?; // Distinct names.
+        }
+        let config = charge_protocol_fee(
+            &ctx.accounts.protocol_config,
+            ctx.accounts.fee_recipient.as_ref(),
+            ctx.accounts.creator_credential.as_ref(),
+            &ctx.accounts.authority,
+            &ctx.accounts.system_program,
+        )?;
+        count_creation(
+            config.as_ref(),
+            ctx.accounts.creator_stats.as_deref_mut(),
+            ctx.accounts.authority.key(),
+            ctx.bumps.creator_stats,
+            Clock::get()?.unix_timestamp,
+        )?; // Per-wallet rate limit.
+        let counter = &mut ctx.accounts.counter; // Per-authority poll counter (created on first use).
+        let seq = counter.count; // Sequence number used in this poll's seeds.
+        counter.authority = ctx.accounts.authority.key(); // Owner of the counter.
+        counter.bump = ctx.bumps.counter; // Record bump used for PDA derivation.
+        counter.count = seq.checked_add(1).ok_or(VotingError::Overflow)?; // Next poll gets a fresh address.
+
+        let mut tally = ctx.accounts.tally.load_init()?; // Fresh zero-filled account.
+        tally.config = ctx.accounts.config.key(); // Link back to the config.
+        tally.start_ts = start_ts; // Window copied so `vote_v2` never reads the config.
+        tally.end_ts = end_ts;
+        tally.candidate_count = candidates.len() as u8; // Entries of `votes` in use.
+        tally.bump = ctx.bumps.tally;
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key(); // Poll admin.
+        config.seq = seq; // Lets clients re-derive the PDA.
+        config.title = title;
+        config.description = description;
+        config.candidates = candidates;
+        config.start_ts = start_ts;
+        config.end_ts = end_ts;
+        config.bump = ctx.bumps.config;
+        emit!(PollCreated {
+            poll: config.key(),
+            authority: config.authority,
+            title: config.title.clone(),
+            description: config.description.clone(),
+            start_ts,
+            end_ts,
+            candidate_count: config.candidates.len() as u8,
+            poll_id: None,
+        });
+        Ok(())
+    }
+
+    /// Casts a one-wallet-one-vote ballot on a split poll: the cheapest ballot path. The
+    /// config isn't decoded at all, and the zero-copy tally is updated in place, so no Borsh
+    /// round trip of texts or candidate names happens. Only the tally and the new Voter
+    /// record (`[b"voter", config, wallet]`) are written.
+    pub fn vote_v2(ctx: Context<VoteV2>, candidate_idx: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let mut tally = ctx.accounts.tally.load_mut()?; // Zero-copy view of the counts.
+        let now = clock.unix_timestamp;
+        let new_count = tally.count_vote(candidate_idx, now)?; // In place, config never decoded.
+
+        let voter = &mut ctx.accounts.voter; // Fresh record.
+        voter.version = VOTER_VERSION; // Current layout.
+        voter.has_voted = true;
+        voter.poll = ctx.accounts.config.key();
+        voter.wallet = ctx.accounts.wallet.key();
+        voter.bump = ctx.bumps.voter;
+        voter.candidate_idx = candidate_idx;
+        voter.voted_at = now;
+        voter.weight = 1; // Equal weights only.
+        voter.votes_used = 1;
+        voter.choices[0] = candidate_idx;
+        emit!(VoteCast {
+            poll: voter.poll,
+            wallet: voter.wallet,
+            candidate_idx,
+            weight: 1,
+            new_count,
+            against: false,
+        });
+        Ok(())
+    }
+
+    /// Creates the `tally_shards` counter shards of one candidate on a sharded poll with more
+    /// than one shard, `[b"shard", poll, candidate_idx, shard_no]` for shard_no in
+    /// 0..tally_shards, passed in that order in `remaining_accounts`. Authority only, before
+    /// voting opens; ballots are refused until every candidate has its shards.
+    pub fn create_tally_shards<'info>(
+        ctx: Context<'_, '_, '_, 'info, CreateTallyShards<'info>>,
+        candidate_idx: u8,
+    ) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &ctx.accounts.poll; // Poll being prepared.
+        require!(poll.sharded && poll.tally_shards > 1, VotingError::NotSharded);
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        require!(poll.shard_mask & (1 << idx) == 0, VotingError::ShardsExist); // Once per candidate.
+        require!(
+            ctx.remaining_accounts.len() == poll.tally_shards as usize,
+            VotingError::AccountMismatch
+        ); // Every shard of the candidate.
+        let poll_key = poll.key();
+        for (shard, info) in ctx.remaining_accounts.iter().enumerate() {
+            let seed_idx = [candidate_idx];
+            let seed_shard = [shard as u8];
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"shard", poll_key.as_ref(), &seed_idx, &seed_shard],
+                ctx.program_id,
+            );
+            require_keys_eq!(info.key(), expected, VotingError::AccountMismatch); // `[b"shard", poll, idx, shard]`.
+            create_pda_account(
+                &ctx.accounts.authority.to_account_info(),
+                info,
+                &ctx.accounts.system_program.to_account_info(),
+                8 + Tally::SIZE,
+                ctx.program_id,
+                &[b"shard", poll_key.as_ref(), &seed_idx, &seed_shard, &[bump]],
+            )?; // Authority pays rent.
+            let tally = Tally { poll: poll_key, candidate_idx, shard: shard as u8, bump, ..Tally::default() };
+            tally.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?; // Discriminator + zero count.
+        }
+        ctx.accounts.poll.shard_mask |= 1 << idx; // Candidate ready.
+        Ok(())
+    }
+
+    /// Casts a ballot on a `sharded` poll. Only one counter of the chosen candidate and the
+    /// new Voter record are written; the Poll is read-only, so ballots don't contend for the
+    /// same account. With several shards per candidate the counter is shard
+    /// `wallet.to_bytes()[0] % tally_shards` (see `shard_for`), so even a popular candidate's
+    /// ballots spread out. One ballot per wallet, equal weights, no retraction.
+    pub fn vote_sharded(ctx: Context<VoteSharded>, candidate_idx: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &ctx.accounts.poll; // Read-only settings.
+        require!(poll.sharded, VotingError::NotSharded);
+        require!(
+            poll.shard_mask as u16 == (1u16 << poll.candidates.len()) - 1,
+            VotingError::ShardsNotReady
+        ); // Every candidate has its counters.
+        poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        poll.require_candidate(candidate_idx)?; // In range and approved.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Barred from this poll.
+        let wallet = ctx.accounts.wallet.key();
+        require!(
+            poll.authority_can_vote || wallet != poll.authority,
+            VotingError::AuthorityCannotVote
+        ); // Neutral organizer.
+        pay_vote_fee(
+            poll,
+            &ctx.accounts.wallet.to_account_info(),
+            ctx.accounts.treasury.as_ref(),
+            &ctx.accounts.system_program.to_account_info(),
+            None,
+        )?; // Anti-spam fee, if any.
+        let voter = &mut ctx.accounts.voter; // Record created on first use.
+        require!(!voter.has_voted, VotingError::AlreadyVoted); // One record per wallet.
+        voter.version = VOTER_VERSION; // Current layout.
+        voter.has_voted = true;
+        voter.poll = poll.key();
+        voter.wallet = wallet;
+        voter.bump = ctx.bumps.voter;
+        voter.candidate_idx = candidate_idx;
+        voter.voted_at = clock.unix_timestamp;
+        voter.weight = 1; // Equal weights only.
+        voter.votes_used = 1;
+        voter.choices[0] = candidate_idx;
+
+        let tally = &mut ctx.accounts.tally; // This candidate's shard.
+        require!(
+            tally.candidate_idx == candidate_idx && tally.shard == shard_for(&wallet, poll.tally_shards),
+            VotingError::AccountMismatch
+        ); // Created by this program for exactly this (poll, candidate, shard).
+        require!(!tally.folded, VotingError::AlreadyCounted); // Collected shards are final.
+        tally.votes = tally.votes.checked_add(1).ok_or(VotingError::Overflow)?;
+        tally.voters = tally.voters.checked_add(1).ok_or(VotingError::Overflow)?;
+        emit!(VoteCast {
+            poll: poll.key(),
+            wallet,
+            candidate_idx,
+            weight: 1,
+            new_count: tally.votes,
+            against: false,
+        });
+        Ok(())
+    }
+
+    /// Folds the Tally PDAs of a `sharded` poll into `votes`, `voter_count` and `turnout`
+    /// after end_ts. Anyone may call it, in batches: `remaining_accounts` are writable Tally
+    /// accounts of this poll, in any order, each folded once (`AlreadyCounted` after that).
+    /// Finalize waits until every shard is in.
+    pub fn collect_tallies<'info>(ctx: Context<'_, '_, 'info, 'info, CollectTallies<'info>>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_consistent()?; // Tallies line up with candidates.
+        require!(poll.sharded, VotingError::NotSharded);
+        require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to count.
+        require!(!poll.collected, VotingError::AlreadyCounted); // Counts are already in.
+        require!(clock.unix_timestamp > poll.end_ts, VotingError::PollStillOpen); // Ballots are final.
+        require!(
+            ctx.remaining_accounts.len() <= MAX_TABULATE_BATCH,
+            VotingError::BatchTooLarge
+        ); // Bounded work per tx.
+        for info in ctx.remaining_accounts {
+            let mut tally: Account<Tally> = Account::try_from(info)?; // Owner + discriminator.
+            require_keys_eq!(tally.poll, poll.key(), VotingError::AccountMismatch); // This poll's shard.
+            require!(!tally.folded, VotingError::AlreadyCounted); // Once per shard.
+            let idx = tally.candidate_idx as usize; // Candidate counted there.
+            poll.votes[idx] = poll.votes[idx].checked_add(tally.votes).ok_or(VotingError::Overflow)?;
+            poll.voter_count = poll.voter_count.checked_add(tally.voters).ok_or(VotingError::Overflow)?;
+            tally.folded = true;
+            tally.exit(ctx.program_id)?; // Persist the flag.
+            poll.folded_shards = poll.folded_shards.checked_add(1).ok_or(VotingError::Overflow)?;
+        }
+        poll.turnout = poll.voter_count as u64; // Equal weights: one per ballot.
+        poll.collected = poll.folded_shards as usize == poll.candidates.len() * poll.tally_shards as usize;
+        Ok(())
+    }
+
+    /// Casts a ballot on a receiptless poll without creating a Voter record. The wallet proves
+    /// it is leaf `leaf_index` of the allowlist tree (`merkle::indexed_leaf_hash`); that leaf's
+    /// bit in `spent_leaves` stops a second ballot. The ballot is folded into `ballot_hash`
+    /// (see `ballot_chain_hash`) and counted with weight 1. Nothing is kept per wallet, so it
+    /// can't be retracted or changed.
+    pub fn vote_light(ctx: Context<VoteLight>, candidate_idx: u8, leaf_index: u16, proof: Vec<[u8; 32]>) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.receiptless_voters > 0, VotingError::NotReceiptless);
+        poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        poll.require_candidate(candidate_idx)?; // In range and approved.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Barred from this poll.
+        let wallet = ctx.accounts.wallet.key();
+        require!(
+            poll.authority_can_vote || wallet != poll.authority,
+            VotingError::AuthorityCannotVote
+        ); // Neutral organizer.
+        require!(leaf_index < poll.receiptless_voters, VotingError::InvalidProof); // Covered by the bitmap.
+        let root = poll.allowlist_root.ok_or(VotingError::InvalidProof)?; // Set for every receiptless poll.
+        let leaf = merkle::indexed_leaf_hash(&wallet, leaf_index); // hash(wallet || index).
+        require!(merkle::verify(&proof, &root, leaf), VotingError::InvalidProof);
+        require!(!poll.is_full(), VotingError::PollFull); // First `max_voters` wallets only.
+        poll.spend_leaf(leaf_index)?; // Leaf used once.
+        pay_vote_fee(
+            poll,
+            &ctx.accounts.wallet.to_account_info(),
+            ctx.accounts.treasury.as_ref(),
+            &ctx.accounts.system_program.to_account_info(),
+            None,
+        )?; // Anti-spam fee, if any.
+        poll.voter_count = poll.voter_count.checked_add(1).ok_or(VotingError::Overflow)?;
+        poll.turnout = poll.turnout.checked_add(1).ok_or(VotingError::Overflow)?; // Equal weights.
+        poll.ballot_hash = ballot_chain_hash(&poll.ballot_hash, &wallet, candidate_idx); // Extend the chain.
+        poll.record_activity(clock.unix_timestamp)?; // Turnout analytics.
+        add_to_tally(poll, wallet, candidate_idx, 1, clock.unix_timestamp)
+    }
+
+    /// Creates the poll's `ParticipationBitmap` (`[b"participation", poll]`) with room for
+    /// `capacity` credentials (at most `MAX_PARTICIPANTS`), before any ballot. From then on
+    /// `register_voter` gives each credential the next slot and ballots go through
+    /// `vote_indexed`, which sets the slot's bit instead of creating a Voter PDA. Needs a
+    /// credential allowlist and plain single-candidate ballots weighted `Equal` or `Credential`.
+    pub fn init_participation(ctx: Context<InitParticipation>, capacity: u32) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(!poll.has_votes() && poll.voter_count == 0, VotingError::VotesAlreadyCast); // Nothing in Voter PDAs yet.
+        require!((1..=MAX_PARTICIPANTS).contains(&capacity), VotingError::ParticipationOptions);
+        require!(
+            poll.allowlist_required
+                && poll.ballot_type == BallotType::Plurality
+                && poll.votes_per_wallet == 1
+                && matches!(poll.weighting, VoteWeighting::Equal | VoteWeighting::Credential)
+                && !poll.commit_reveal
+                && !poll.hide_tallies
+                && !poll.sharded
+                && poll.receiptless_voters == 0
+                && poll.allowlist_root.is_none()
+                && poll.gate_mint.is_none()
+                && poll.gate_collection.is_none()
+                && poll.min_lamports == 0
+                && poll.registrar.is_none(),
+            VotingError::ParticipationOptions
+        ); // `vote_indexed` only checks the credential and bans, and keeps nothing to undo.
+        let bitmap = &mut ctx.accounts.participation;
+        bitmap.poll = poll.key(); // Poll whose ballots it tracks.
+        bitmap.bump = ctx.bumps.participation; // Record bump used for PDA derivation.
+        bitmap.capacity = capacity;
+        bitmap.bits = vec![0; (capacity as usize).div_ceil(8)]; // Nobody voted yet.
+        poll.participation = Some(bitmap.key()); // Other ballot instructions now refuse.
         Ok(())
     }
 
+    /// Casts a ballot on a poll with a `ParticipationBitmap`: sets the bit of the signer's
+    /// credential slot (`AlreadyVoted` if set) and counts the vote, without a Voter PDA, so
+    /// it costs only the transaction fee. With no record of the choice the ballot can't be
+    /// retracted or changed.
+    pub fn vote_indexed(ctx: Context<VoteIndexed>, candidate_idx: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll;
+        poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        poll.require_candidate(candidate_idx)?; // In range and approved.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Barred from this poll.
+        let wallet = ctx.accounts.wallet.key();
+        require!(
+            poll.authority_can_vote || wallet != poll.authority,
+            VotingError::AuthorityCannotVote
+        ); // Neutral organizer.
+        require!(!poll.is_full(), VotingError::PollFull); // First `max_voters` wallets only.
+        let credential = &ctx.accounts.credential;
+        let index = credential.index.ok_or(VotingError::NotIndexed)?; // Run `index_voter` first.
+        ctx.accounts.participation.mark(index)?; // Once per slot.
+        pay_vote_fee(
+            poll,
+            &ctx.accounts.wallet.to_account_info(),
+            ctx.accounts.treasury.as_ref(),
+            &ctx.accounts.system_program.to_account_info(),
+            None,
+        )?; // Anti-spam fee, if any.
+        let weight = match poll.weighting {
+            VoteWeighting::Credential => credential.weight, // Share count set by the authority.
+            _ => 1,
+        };
+        poll.voter_count = poll.voter_count.checked_add(1).ok_or(VotingError::Overflow)?;
+        poll.turnout = poll.turnout.checked_add(weight).ok_or(VotingError::Overflow)?; // Towards quorum.
+        poll.record_activity(clock.unix_timestamp)?; // Turnout analytics.
+        add_to_tally(poll, wallet, candidate_idx, weight, clock.unix_timestamp)
+    }
+
     /// Casts a single vote for a candidate index.
     /// Enforced rules:
     /// - Voting window open (start_ts <= now <= end_ts)
     /// - Candidate index in range
-    /// - One vote per wallet per poll (enforced by a unique voter PDA)
-    pub fn vote(ctx: Context<Vote>, candidate_idx: u8) -> Result<()> {
+    /// - One vote per wallet per poll (unique voter PDA; a second attempt fails with `AlreadyVoted`)
+    /// - Allowlisted polls: a credential PDA and/or a merkle `proof` of the wallet (empty if unused)
+    pub fn vote(
+        ctx: Context<Vote>,
+        candidate_idx: u8,
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(
+            ctx.accounts.poll.ballot_type == BallotType::Plurality,
+            VotingError::WrongBallotType
+        ); // Other ballot types have their own instruction.
+        require!(!ctx.accounts.poll.commit_reveal, VotingError::CommitRevealPoll); // Use `commit_vote`.
+        require!(!ctx.accounts.poll.sharded, VotingError::ShardedPoll); // Use `vote_sharded`.
+        ctx.accounts.poll.require_candidate(candidate_idx)?; // In range and approved.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+
+        // The voter PDA is created on first use; an existing record means this wallet already voted.
+        if ctx.accounts.voter.has_voted {
+            // Another ballot from the same wallet: `AlreadyVoted` unless the poll allows several.
+            return record_extra_ballot(
+                &mut ctx.accounts.poll,
+                &mut ctx.accounts.voter,
+                candidate_idx,
+                clock.unix_timestamp,
+            );
+        }
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let wallet = ctx.accounts.wallet.key();
+        record_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            wallet,
+            ctx.bumps.voter,
+            Ballot { candidate_idx, ..admission },
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Votes for a write-in candidate on a poll with `allow_write_ins`: a name matching an
+    /// existing candidate (ASCII case-insensitive) votes for it; otherwise the name is appended
+    /// to `candidates` (same validation and `max_candidates` cap as `add_candidate`, so a full
+    /// list fails with `TooManyCandidates`) and voted for. The poll account grows for the new
+    /// entry with the voter paying the extra rent. Otherwise exactly like `vote`.
+    pub fn vote_write_in(
+        ctx: Context<Vote>,
+        name: String,                         // Candidate to vote for, new or existing.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(ctx.accounts.poll.allow_write_ins, VotingError::WriteInsNotAllowed);
+        let candidate_idx = match ctx.accounts.poll.write_in_candidate(&name)? {
+            Some(idx) => idx,
+            None => {
+                make_room_for_candidate(
+                    &ctx.accounts.poll,
+                    &name,
+                    &ctx.accounts.wallet.to_account_info(),
+                    &ctx.accounts.system_program.to_account_info(),
+                )?; // Voter pays for the new entry.
+                let poll = &mut ctx.accounts.poll;
+                poll.candidates.push(name); // Append candidate.
+                poll.votes.push(0); // Keep tallies aligned.
+                poll.downvotes.push(0);
+                poll.last_increment_ts.push(0);
+                poll.candidate_wallets.push(ctx.accounts.wallet.key()); // Written in by this voter.
+                poll.approved.push(true); // Write-ins are the point of the poll.
+                emit!(CandidatesChanged {
+                    poll: poll.key(),
+                    candidates: poll.candidates.clone(),
+                });
+                (poll.candidates.len() - 1) as u8
+            }
+        };
+        vote(ctx, candidate_idx, proof, balance_proof) // Eligibility, weight, tally.
+    }
+
+    /// Commits a secret ballot on a `commit_reveal` poll during the voting window: stores
+    /// `commitment = vote_commitment(candidate_idx, salt, wallet)` on the Voter record without
+    /// touching the tallies. Eligibility and weight are settled now, like `vote`; the ballot
+    /// only counts once revealed with `reveal_vote`. `retract_vote` withdraws it.
+    pub fn commit_vote(
+        ctx: Context<Vote>,
+        commitment: [u8; 32],                 // sha256(candidate_idx || salt || wallet).
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(ctx.accounts.poll.commit_reveal, VotingError::NotCommitReveal);
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One commitment per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let poll = &mut ctx.accounts.poll;
+        let voter = &mut ctx.accounts.voter;
+        open_voter_record(poll, voter, ctx.accounts.wallet.key(), ctx.bumps.voter, &admission, clock.unix_timestamp, false)?; // Counts once revealed.
+        voter.commitment = commitment; // Checked by `reveal_vote`.
+        Ok(())
+    }
+
+    /// Reveals a committed ballot after end_ts and before `reveal_deadline_ts`: the candidate
+    /// and salt must hash to the stored commitment (`CommitmentMismatch`), then the recorded
+    /// weight is added to the candidate. Unrevealed commitments never count.
+    pub fn reveal_vote(ctx: Context<RevealVote>, candidate_idx: u8, salt: [u8; 32]) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_consistent()?; // Tallies line up with candidates.
+        require!(poll.commit_reveal, VotingError::NotCommitReveal);
+        require!(!poll.cancelled, VotingError::PollCancelled); // Cancelled polls take no votes.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // Results are frozen.
+        require!(!poll.paused, VotingError::PollPaused); // Kill switch engaged.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Banned ballots are frozen.
+        let voter = &mut ctx.accounts.voter; // Committed record.
+        check_reveal(poll, voter, candidate_idx, &salt, clock.unix_timestamp)?;
+        voter.revealed = true;
+        voter.candidate_idx = candidate_idx; // Remember choice for audits.
+        voter.votes_used = 1; // Now a regular ballot (retraction is closed anyway).
+        voter.choices[0] = candidate_idx;
+        poll.turnout = poll.turnout.checked_add(voter.weight).ok_or(VotingError::Overflow)?; // Now a cast ballot.
+        add_to_tally(poll, voter.wallet, candidate_idx, voter.weight, clock.unix_timestamp)
+    }
+
+    /// Casts an approval ballot on an `Approval` poll: every selected candidate gets the voter's
+    /// weight (1 in `Equal` mode). Selections must be non-empty, unique and in range. The Voter
+    /// record stores one 0/1 flag per candidate.
+    pub fn vote_approval(
+        ctx: Context<Vote>,
+        selections: Vec<u8>,                  // Approved candidate indices.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(
+            ctx.accounts.poll.ballot_type == BallotType::Approval,
+            VotingError::WrongBallotType
+        );
+        let ballot = approval_ballot(&selections, ctx.accounts.poll.candidates.len())?; // Flags per candidate.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        record_points_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: selections[0], ..admission },
+            ballot,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Casts a vote against a candidate on a poll with `allow_downvotes`. Same rules as `vote`
+    /// (one ballot per wallet, for or against); the weight goes to `downvotes` and the winner is
+    /// decided by `votes - downvotes`. The Voter record remembers the direction.
+    pub fn vote_against(
+        ctx: Context<Vote>,
+        candidate_idx: u8,
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(ctx.accounts.poll.allow_downvotes, VotingError::DownvotesNotAllowed);
+        ctx.accounts.poll.require_candidate(candidate_idx)?; // In range and approved.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let wallet = ctx.accounts.wallet.key();
+        let poll = &mut ctx.accounts.poll;
+        let voter = &mut ctx.accounts.voter;
+        let ballot = Ballot { candidate_idx, ..admission };
+        open_voter_record(poll, voter, wallet, ctx.bumps.voter, &ballot, clock.unix_timestamp, true)?;
+        voter.against = true; // Retract/change work on `downvotes`.
+        if poll.hide_tallies {
+            return Ok(()); // Counted by `tabulate` after the close.
+        }
+        let idx = candidate_idx as usize;
+        poll.downvotes[idx] = poll.downvotes[idx]
+            .checked_add(ballot.weight)
+            .ok_or(VotingError::Overflow)?;
+        poll.last_increment_ts[idx] = clock.unix_timestamp; // Net score changed.
+        emit!(VoteCast {
+            poll: poll.key(),
+            wallet,
+            candidate_idx,
+            weight: ballot.weight,
+            new_count: poll.downvotes[idx],
+            against: true,
+        });
+        Ok(())
+    }
+
+    /// Formally abstains on a poll with `allow_abstain`: creates the Voter record (so the wallet
+    /// can't vote afterwards) and counts it in `abstentions`, leaving `votes` untouched.
+    /// Eligibility rules are the same as for a ballot; `retract_vote` withdraws the abstention.
+    pub fn vote_abstain(
+        ctx: Context<Vote>,
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(ctx.accounts.poll.allow_abstain, VotingError::AbstainNotAllowed);
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One record per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Identity claim etc.
+        let poll = &mut ctx.accounts.poll;
+        let voter = &mut ctx.accounts.voter;
+        open_voter_record(poll, voter, ctx.accounts.wallet.key(), ctx.bumps.voter, &admission, clock.unix_timestamp, true)?;
+        voter.abstained = true; // Blocks candidate ballots from this wallet.
+        voter.votes_used = 0; // No candidate chosen.
+        poll.abstentions = poll.abstentions.checked_add(1).ok_or(VotingError::Overflow)?; // Counts towards turnout.
+        Ok(())
+    }
+
+    /// Casts a Borda ballot on a `Borda` poll: a ranking of every candidate (a permutation of
+    /// all indices, else `BadBallotLength` / `DuplicateChoice`). The best-ranked candidate gets
+    /// `n-1` points, the next `n-2`, down to 0, times the voter's weight, so `votes` holds points.
+    /// The ranking is stored on the Voter record.
+    pub fn vote_borda(
+        ctx: Context<Vote>,
+        ranking: Vec<u8>,                     // Every candidate index, most preferred first.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(
+            ctx.accounts.poll.ballot_type == BallotType::Borda,
+            VotingError::WrongBallotType
+        );
+        let candidates = ctx.accounts.poll.candidates.len();
+        require!(ranking.len() == candidates, VotingError::BadBallotLength); // Rank everyone.
+        let ballot = ranked_ballot(&ranking, candidates)?; // Unique and in range: a permutation.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        record_points_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: ranking[0], ..admission },
+            ballot,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Casts a score ballot on a `Score` poll: exactly one score per candidate
+    /// (`BadBallotLength`), each 0..=`MAX_SCORE` (`ScoreOutOfRange`). Each score (times the
+    /// voter's weight) is added to that candidate's tally; the scores are stored on the Voter.
+    pub fn vote_score(
+        ctx: Context<Vote>,
+        scores: Vec<u8>,                      // Score per candidate, aligned with `candidates`.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(
+            ctx.accounts.poll.ballot_type == BallotType::Score,
+            VotingError::WrongBallotType
+        );
+        let ballot = score_ballot(&scores, ctx.accounts.poll.candidates.len())?; // Points per candidate.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let favourite = ballot
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| **v)
+            .map_or(0, |(idx, _)| idx as u8); // Best-scored candidate, for `candidate_idx`.
+        record_points_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: favourite, ..admission },
+            ballot,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Casts a cumulative ballot on a `Cumulative { budget }` poll: points per candidate
+    /// (`BadBallotLength` unless one per candidate) summing to exactly `budget`, or at most
+    /// `budget` when the poll allows under-spending (`OverBudget`, `BudgetNotSpent`). Points
+    /// (times the voter's weight) are added to the tallies; the allocation is stored on the Voter.
+    pub fn vote_cumulative(
+        ctx: Context<Vote>,
+        allocation: Vec<u16>,                 // Points per candidate, aligned with `candidates`.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
         let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        let BallotType::Cumulative { budget } = ctx.accounts.poll.ballot_type else {
+            return err!(VotingError::WrongBallotType);
+        };
+        let poll = &ctx.accounts.poll;
+        cumulative_ballot(&allocation, poll.candidates.len(), budget, poll.allow_underspend)?;
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let favourite = allocation
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| **v)
+            .map_or(0, |(idx, _)| idx as u8); // Candidate with the most points, for `candidate_idx`.
+        record_points_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: favourite, ..admission },
+            allocation,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Casts a split ballot on a `Split` poll: basis points per candidate (`BadBallotLength`
+    /// unless one per candidate) summing to exactly 10_000 (`BadAllocation`). Each share (times
+    /// the voter's weight) is added to the tallies; the allocation is stored on the Voter.
+    pub fn vote_split(
+        ctx: Context<Vote>,
+        allocation_bps: Vec<u16>,             // Share per candidate in basis points, aligned with `candidates`.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
         require!(
-            clock.unix_timestamp >= ctx.accounts.poll.start_ts,
-            VotingError::TooEarly
+            ctx.accounts.poll.ballot_type == BallotType::Split,
+            VotingError::WrongBallotType
         );
+        split_ballot(&allocation_bps, ctx.accounts.poll.candidates.len())?; // One share each, 100% in all.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let favourite = allocation_bps
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, v)| **v)
+            .map_or(0, |(idx, _)| idx as u8); // Largest share, for `candidate_idx`.
+        record_points_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: favourite, ..admission },
+            allocation_bps,
+            clock.unix_timestamp,
+        )
+    }
+
+    /// Casts a ranked ballot on a `RankedChoice` poll: candidate indices in order of preference,
+    /// unique and in range; unranked candidates come last. The ranking is stored on the Voter
+    /// record and folded into the poll's `RankedTally` for `tally_irv`; `votes` only counts
+    /// first preferences.
+    pub fn vote_ranked(
+        ctx: Context<Vote>,
+        ranking: Vec<u8>,                     // Candidate indices, most preferred first.
+        proof: Vec<[u8; 32]>,                 // Allowlist proof (empty if not merkle-gated).
+        balance_proof: Option<BalanceProof>,  // Snapshot balance proof (`Snapshot` weighting only).
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
         require!(
-            clock.unix_timestamp <= ctx.accounts.poll.end_ts,
-            VotingError::Closed
+            ctx.accounts.poll.ballot_type == BallotType::RankedChoice,
+            VotingError::WrongBallotType
         );
+        let candidates = ctx.accounts.poll.candidates.len();
+        let ballot = ranked_ballot(&ranking, candidates)?; // Unique, in range, non-empty.
+        ctx.accounts.check_eligibility(&proof)?; // Bans, allowlists and gates.
+        ctx.accounts.pay_vote_fee()?; // Anti-spam fee, if any.
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // One ballot per wallet.
+        let admission = ctx.accounts.admit(balance_proof.as_ref(), clock.epoch, ctx.program_id)?; // Weight (and escrow).
+        let tally = ctx.accounts.ranked_tally.as_mut().ok_or(VotingError::AccountMismatch)?;
+        apply_ranking(&mut tally.counts, &ballot, candidates, admission.weight, true)?; // Every round's counters.
+        record_ballot(
+            &mut ctx.accounts.poll,
+            &mut ctx.accounts.voter,
+            ctx.accounts.wallet.key(),
+            ctx.bumps.voter,
+            Ballot { candidate_idx: ranking[0], ..admission },
+            clock.unix_timestamp,
+        )?; // First preference goes to `votes`.
+        ctx.accounts.voter.ballot = ballot; // Full ranking for audits and retraction.
+        Ok(())
+    }
+
+    /// Casts a ballot on behalf of the wallet that delegated to the signer. The Voter PDA is the
+    /// *delegator's*, so they can't also vote directly (and a direct vote first makes this fail
+    /// with `AlreadyVoted`). Eligibility and weight are the delegator's; polls gated on their
+    /// tokens, NFTs or SOL balance, or weighted by live holdings, reject delegated votes with
+    /// `DelegationUnsupported` since those checks would need the delegator's signature/accounts.
+    ///
+    /// Chains (A -> B -> signer) are followed through `remaining_accounts`, one group per
+    /// upstream link, nearest first: `[delegation, voter PDA (writable, not yet created),
+    /// ban PDA]`, plus the upstream delegator's credential when the poll is allowlisted or uses
+    /// `Credential` weighting. Every wallet in the chain gets its own Voter record with its own
+    /// weight. At most `MAX_DELEGATION_DEPTH` links (`DelegationTooDeep`); a wallet appearing
+    /// twice fails with `DelegationCycle`. Merkle-allowlisted and snapshot-weighted polls only
+    /// take single-link delegations, since each upstream wallet would need its own proof.
+    pub fn vote_as_delegate<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VoteAsDelegate<'info>>,
+        candidate_idx: u8,
+        proof: Vec<[u8; 32]>,                 // Delegator's allowlist proof (empty if unused).
+        balance_proof: Option<BalanceProof>,  // Delegator's snapshot balance proof.
+    ) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
 
         let poll = &mut ctx.accounts.poll; // Poll account to mutate.
-        let idx = candidate_idx as usize; // Cast to usize for indexing.
-        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        require!(poll.ballot_type == BallotType::Plurality, VotingError::WrongBallotType); // Single-candidate ballots only.
+        require!(!poll.commit_reveal, VotingError::CommitRevealPoll); // Delegates can't commit for others.
+        require!(!poll.sharded, VotingError::ShardedPoll); // Ballots go through `vote_sharded`.
+        require!(poll.supports_delegation(), VotingError::DelegationUnsupported);
+        require!(poll.receiptless_voters == 0, VotingError::ReceiptlessPoll); // Ballots go through `vote_light`.
+        require!(poll.participation.is_none(), VotingError::ParticipationPoll); // Ballots go through `vote_indexed`.
+        poll.require_candidate(candidate_idx)?; // In range and approved.
 
-        // Mark the voter PDA; creation fails if PDA already exists, preventing double-voting.
-        let voter = &mut ctx.accounts.voter; // PDA unique to (poll, wallet).
-        voter.has_voted = true; // Flag that this wallet voted.
-        voter.poll = poll.key(); // Store poll reference.
-        voter.wallet = ctx.accounts.wallet.key(); // Store voter wallet.
-        voter.bump = ctx.bumps.voter; // Save bump for PDA recreation.
-
-        // Increment selected candidate count with overflow protection.
-        poll.votes[idx] = poll
-            .votes[idx]
-            .checked_add(1)
-            .ok_or(VotingError::Overflow)?;
+        require!(
+            clock.unix_timestamp <= ctx.accounts.delegation.expires_ts,
+            VotingError::DelegationExpired
+        ); // Lapsed delegations don't work (the delegator can still vote directly).
+        let delegator = ctx.accounts.delegation.delegator; // Wallet whose ballot this is.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Delegator is barred.
+        if poll.allowlist_required {
+            // Seeds/has_one on the optional account tie it to (poll, delegator).
+            require!(ctx.accounts.credential.is_some(), VotingError::NotEligible);
+        }
+        if let Some(root) = poll.allowlist_root {
+            let leaf = merkle::leaf_hash(&delegator); // hash(delegator_pubkey).
+            require!(merkle::verify(&proof, &root, leaf), VotingError::InvalidProof);
+        }
+
+        require!(!ctx.accounts.voter.has_voted, VotingError::AlreadyVoted); // Delegator already voted.
+        let (observed_balance, weight) = vote_weight(
+            poll,
+            &delegator,
+            None,
+            None,
+            ctx.accounts.credential.as_deref(),
+            balance_proof.as_ref(),
+            clock.epoch,
+        )?; // Only account-free weightings reach here.
+        record_ballot(
+            poll,
+            &mut ctx.accounts.voter,
+            delegator,
+            ctx.bumps.voter,
+            Ballot { candidate_idx, weight, observed_balance, locked_amount: 0, burned: 0 },
+            clock.unix_timestamp,
+        )?;
+        pay_vote_fee(
+            poll,
+            &ctx.accounts.delegate.to_account_info(),
+            ctx.accounts.treasury.as_ref(),
+            &ctx.accounts.system_program.to_account_info(),
+            None,
+        )?; // The delegate pays the fee of every ballot it casts.
+
+        // Upstream links: wallets that delegated to `delegator`, and so on.
+        let needs_credential = poll.allowlist_required || poll.weighting == VoteWeighting::Credential;
+        let stride = if needs_credential { 4 } else { 3 }; // Accounts per upstream link.
+        let upstream = ctx.remaining_accounts;
+        require!(upstream.chunks_exact(stride).remainder().is_empty(), VotingError::AccountMismatch); // Whole groups only.
+        if upstream.is_empty() {
+            return Ok(());
+        }
+        require!(
+            upstream.len() / stride < MAX_DELEGATION_DEPTH,
+            VotingError::DelegationTooDeep
+        ); // Signer's own link counts too.
+        require!(
+            poll.allowlist_root.is_none() && !matches!(poll.weighting, VoteWeighting::Snapshot { .. }),
+            VotingError::DelegationUnsupported
+        ); // Per-wallet proofs aren't carried for upstream links.
+
+        let poll_key = poll.key();
+        let mut seen = vec![ctx.accounts.delegate.key(), delegator]; // Wallets already in the chain.
+        let mut head = delegator; // Wallet the next link must delegate to.
+        for group in upstream.chunks(stride) {
+            let link: Account<Delegation> = Account::try_from(&group[0])?; // Owner + discriminator.
+            require_keys_eq!(link.poll, poll_key, VotingError::AccountMismatch); // Same poll.
+            let expected = Pubkey::create_program_address(
+                &[b"delegate", poll_key.as_ref(), link.delegator.as_ref(), &[link.bump]],
+                ctx.program_id,
+            )
+            .map_err(|_| VotingError::AccountMismatch)?;
+            require_keys_eq!(group[0].key(), expected, VotingError::AccountMismatch); // Canonical PDA.
+            require_keys_eq!(link.to, head, VotingError::AccountMismatch); // Unbroken chain.
+            require!(!seen.contains(&link.delegator), VotingError::DelegationCycle);
+            require!(
+                clock.unix_timestamp <= link.expires_ts,
+                VotingError::DelegationExpired
+            ); // Every link must be live.
+
+            let (ban_key, _) = Pubkey::find_program_address(
+                &[b"ban", poll_key.as_ref(), link.delegator.as_ref()],
+                ctx.program_id,
+            );
+            require_keys_eq!(group[2].key(), ban_key, VotingError::AccountMismatch); // Upstream wallet's ban PDA.
+            require!(group[2].data_is_empty(), VotingError::VoterBanned); // Barred wallets can't ride a chain.
+
+            let credential = if needs_credential {
+                let credential: Account<VoterCredential> = Account::try_from(&group[3])?;
+                require_keys_eq!(credential.poll, poll_key, VotingError::NotEligible); // This poll's.
+                require_keys_eq!(credential.wallet, link.delegator, VotingError::NotEligible); // Upstream wallet's.
+                Some(credential)
+            } else {
+                None
+            };
+            let (observed_balance, weight) = vote_weight(
+                poll,
+                &link.delegator,
+                None,
+                None,
+                credential.as_deref(),
+                None,
+                clock.epoch,
+            )?;
+
+            let voter_info = &group[1]; // Upstream delegator's voter PDA.
+            let (voter_key, bump) = Pubkey::find_program_address(
+                &[b"voter", poll_key.as_ref(), link.delegator.as_ref()],
+                ctx.program_id,
+            ); // Same seeds as `vote`.
+            require_keys_eq!(voter_info.key(), voter_key, VotingError::AccountMismatch);
+            require!(voter_info.owner == &system_program::ID, VotingError::AlreadyVoted); // Not voted yet.
+            create_pda_account(
+                &ctx.accounts.delegate.to_account_info(),
+                voter_info,
+                &ctx.accounts.system_program.to_account_info(),
+                8 + Voter::INIT_SPACE, // Discriminator + size of Voter.
+                ctx.program_id,
+                &[b"voter", poll_key.as_ref(), link.delegator.as_ref(), &[bump]],
+            )?; // Delegate pays, as for the direct link.
+            let mut voter: Account<Voter> = Account::try_from_unchecked(voter_info)?; // Zeroed record.
+            record_ballot(
+                poll,
+                &mut voter,
+                link.delegator,
+                bump,
+                Ballot { candidate_idx, weight, observed_balance, locked_amount: 0, burned: 0 },
+                clock.unix_timestamp,
+            )?;
+            voter.exit(ctx.program_id)?; // Write discriminator + fields.
+            pay_vote_fee(
+                poll,
+                &ctx.accounts.delegate.to_account_info(),
+                ctx.accounts.treasury.as_ref(),
+                &ctx.accounts.system_program.to_account_info(),
+                None,
+            )?;
+
+            seen.push(link.delegator);
+            head = link.delegator;
+        }
         Ok(())
     }
-}
 
-/// Accounts needed to initialize a poll.
-#[derive(Accounts)]
-#[instruction(title: String)]
-pub struct InitPoll<'info> {
-    #[account(
-        init,
-        payer = authority, // Authority funds account creation.
-        space = 8 + Poll::MAX_SIZE, // Discriminator + max size for Poll.
-        seeds = [b"poll", authority.key.as_ref(), title.as_bytes()], // PDA seeds.
-        bump // PDA bump supplied by Anchor.
-    )]
-    pub poll: Account<'info, Poll>, // Poll account to create.
-    #[account(mut)]
-    pub authority: Signer<'info>, // Wallet paying for the poll account.
-    pub system_program: Program<'info, System>, // Required for account creation.
-}
+    /// Delegates the signer's vote in this poll to `to`, who may then call `vote_as_delegate`
+    /// until `expires_ts`. Fails with `AlreadyVoted` if the signer has already voted. The
+    /// delegator pays the rent.
+    pub fn delegate(ctx: Context<Delegate>, to: Pubkey, expires_ts: i64) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        require_keys_neq!(to, ctx.accounts.delegator.key(), VotingError::InvalidDelegate); // No self-delegation.
+        let clock = Clock::get()?; // Read current cluster time.
+        require!(expires_ts > clock.unix_timestamp, VotingError::InvalidExpiry); // Must be usable.
+        require!(
+            ctx.accounts.delegator_voter.data_is_empty(),
+            VotingError::AlreadyVoted
+        ); // A cast ballot can't be handed over.
+        let delegation = &mut ctx.accounts.delegation; // New delegation PDA.
+        delegation.poll = ctx.accounts.poll.key(); // Poll this delegation is for.
+        delegation.delegator = ctx.accounts.delegator.key(); // Wallet handing over its vote.
+        delegation.to = to; // Wallet allowed to vote for it.
+        delegation.bump = ctx.bumps.delegation; // Save bump for PDA recreation.
+        delegation.expires_ts = expires_ts; // Delegate loses the vote after this.
+        Ok(())
+    }
 
-/// Accounts needed to cast a vote.
-#[derive(Accounts)]
-pub struct Vote<'info> {
-    #[account(mut, has_one = authority)] // Must point to the correct authority; poll is mutable for vote counts.
-    pub poll: Account<'info, Poll>, // Poll being voted on.
-    /// CHECK: Read-only authority pubkey stored on the poll; no additional data is read or written.
-    pub authority: AccountInfo<'info>, // Authority pubkey stored in the poll.
-    #[account(
-        init,
-        payer = wallet, // Voter pays rent for their own record.
-        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // PDA unique per (poll, wallet).
-        bump,
-        space = 8 + Voter::SIZE // Discriminator + size of Voter.
-    )]
-    pub voter: Account<'info, Voter>, // Voter record PDA to mark participation.
-    #[account(mut)]
-    pub wallet: Signer<'info>, // Wallet casting the vote; signs and funds the PDA.
-    pub system_program: Program<'info, System>, // System program for account creation.
+    /// Pushes a delegation's expiry later (also revives a lapsed one). Delegator only.
+    pub fn extend_delegation(ctx: Context<ExtendDelegation>, new_expires_ts: i64) -> Result<()> {
+        let delegation = &mut ctx.accounts.delegation; // Delegation to extend.
+        require!(
+            new_expires_ts > delegation.expires_ts,
+            VotingError::InvalidExpiry
+        ); // Only ever later.
+        delegation.expires_ts = new_expires_ts; // Save the new expiry.
+        Ok(())
+    }
+
+    /// Revokes the signer's delegation; rent goes back to the delegator. A ballot the delegate
+    /// already cast stays (the delegator can still `change_vote` / `retract_vote` it).
+    pub fn undelegate(_ctx: Context<Undelegate>) -> Result<()> {
+        // Delegation is closed by the `close = delegator` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Closes a finished poll and returns its rent to the authority.
+    /// Only allowed once the voting window has passed (now > end_ts) or the poll was cancelled;
+    /// works with zero votes too, once any creation deposit was released or swept and any
+    /// prize paid out or refunded. Fee polls also pass their treasury: it must hold no more
+    /// than its rent (fees go to the beneficiary first), which goes back to the authority.
+    pub fn close_poll(ctx: Context<ClosePoll>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &ctx.accounts.poll;
+        poll.require_closable(clock.unix_timestamp)?;
+        if poll.has_treasury() {
+            let treasury = ctx.accounts.treasury.as_ref().ok_or(VotingError::TreasuryRequired)?;
+            require!(
+                treasury.lamports() <= Rent::get()?.minimum_balance(0),
+                VotingError::TreasuryNotWithdrawn
+            ); // Fees belong to the beneficiary.
+            treasury_transfer(
+                poll,
+                treasury,
+                &ctx.accounts.authority.to_account_info(),
+                &ctx.accounts.system_program,
+                treasury.lamports(),
+            )?; // The rent the authority seeded; the PDA is gone afterwards.
+        }
+        if let Some(mint) = poll.fee_mint {
+            let vault = ctx.accounts.fee_vault.as_ref().ok_or(VotingError::FeeVaultRequired)?;
+            let owner = ctx.accounts.fee_vault_authority.as_ref().ok_or(VotingError::FeeVaultRequired)?;
+            let token_program = ctx.accounts.token_program.as_ref().ok_or(VotingError::FeeVaultRequired)?;
+            let poll_key = poll.key();
+            let (holding, bump) = require_vault(b"fees", &poll_key, mint, vault, ctx.program_id)?;
+            require!(holding.amount == 0, VotingError::TreasuryNotWithdrawn); // Fees belong to the beneficiary.
+            token::close_account(
+                token_program,
+                vault,
+                &ctx.accounts.authority.to_account_info(),
+                owner,
+                &[&[b"fees", poll_key.as_ref(), &[bump]]],
+            )?; // Vault rent back to the authority.
+        }
+        // Lamports are returned by the `close = authority` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Pays collected fees from a finalized (or cancelled) fee poll's treasury to its
+    /// `fee_beneficiary`. Anyone may call it; the lamports only ever go to the beneficiary.
+    /// `amount` defaults to everything above the treasury's rent-exempt minimum and any
+    /// candidate fees owed back, and can't exceed it (`InsufficientTreasury`); the rent is
+    /// returned by `close_poll`.
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: Option<u64>) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        require!(poll.has_treasury(), VotingError::NoTreasury); // Fee polls only.
+        require!(poll.finalized || poll.cancelled, VotingError::NotFinalized); // No more ballots.
+        let treasury = &ctx.accounts.treasury;
+        let available = treasury
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(0))
+            .saturating_sub(poll.candidate_refunds_owed); // Fees collected, minus refunds.
+        let amount = amount.unwrap_or(available);
+        require!(amount <= available, VotingError::InsufficientTreasury); // The rent seed stays.
+        if amount > 0 {
+            treasury_transfer(
+                poll,
+                treasury,
+                &ctx.accounts.fee_beneficiary.to_account_info(),
+                &ctx.accounts.system_program,
+                amount,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Token-fee counterpart of `withdraw_treasury`: moves collected `fee_mint` tokens from the
+    /// poll's fee vault to the `fee_beneficiary`'s associated token account once the poll is
+    /// finalized or cancelled. Anyone may call it. `amount = None` withdraws the whole balance.
+    pub fn withdraw_fee_tokens(ctx: Context<WithdrawFeeTokens>, amount: Option<u64>) -> Result<()> {
+        let poll = &ctx.accounts.poll;
+        let mint = poll.fee_mint.ok_or(VotingError::NoTreasury)?; // Token-fee polls only.
+        require!(poll.finalized || poll.cancelled, VotingError::NotFinalized); // No more ballots.
+        let poll_key = poll.key();
+        let vault = ctx.accounts.fee_vault.to_account_info();
+        let (holding, bump) = require_vault(b"fees", &poll_key, mint, &vault, ctx.program_id)?;
+        let ata = token::associated_token_address(&poll.fee_beneficiary, &mint, ctx.accounts.token_program.key);
+        require_keys_eq!(ctx.accounts.destination.key(), ata, VotingError::InvalidTokenAccount); // Beneficiary's ATA.
+        let amount = amount.unwrap_or(holding.amount);
+        require!(amount <= holding.amount, VotingError::InsufficientTreasury);
+        if amount > 0 {
+            token::transfer(
+                &ctx.accounts.token_program,
+                &vault,
+                &ctx.accounts.destination,
+                &ctx.accounts.fee_vault_authority,
+                amount,
+                &[&[b"fees", poll_key.as_ref(), &[bump]]],
+            )?; // Fees PDA signs for the vault.
+        }
+        Ok(())
+    }
+
+    /// Returns a rejected candidate's registration fee from the treasury and closes its
+    /// `CandidateFee` record, both to the registering wallet. Anyone may call it, so the
+    /// authority can settle refunds before `close_poll`.
+    pub fn refund_candidate_fee(ctx: Context<RefundCandidateFee>) -> Result<()> {
+        let amount = ctx.accounts.candidate_fee_record.amount;
+        require!(ctx.accounts.candidate_fee_record.rejected, VotingError::FeeNotRefundable); // Only rejections.
+        treasury_transfer(
+            &ctx.accounts.poll,
+            &ctx.accounts.treasury,
+            &ctx.accounts.wallet.to_account_info(),
+            &ctx.accounts.system_program,
+            amount,
+        )?;
+        let poll = &mut ctx.accounts.poll;
+        poll.candidate_refunds_owed = poll.candidate_refunds_owed.saturating_sub(amount);
+        // The record's rent is returned by the `close = wallet` constraint.
+        Ok(())
+    }
+
+    /// Adds `amount` lamports to the poll's prize pool (`[b"prize", poll]`), paid to the
+    /// winning candidate's wallet by `payout_prize`. Anyone may fund a poll until it is
+    /// finalized; each funder's total is kept in a `Contribution` PDA for refunds.
+    pub fn fund_prize(ctx: Context<FundPrize>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let poll = &mut ctx.accounts.poll;
+        require!(amount > 0, VotingError::InvalidAmount);
+        require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to win.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // Winner already known.
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: ctx.accounts.funder.to_account_info(),
+                    to: ctx.accounts.prize_pool.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
+        poll.prize_total = poll.prize_total.checked_add(amount).ok_or(VotingError::Overflow)?;
+        ctx.accounts.prize_pool.poll = poll.key();
+        ctx.accounts.prize_pool.bump = ctx.bumps.prize_pool;
+        let contribution = &mut ctx.accounts.contribution;
+        contribution.poll = poll.key();
+        contribution.funder = ctx.accounts.funder.key();
+        contribution.amount = contribution.amount.checked_add(amount).ok_or(VotingError::Overflow)?;
+        contribution.bump = ctx.bumps.contribution;
+        Ok(())
+    }
+
+    /// Sends the whole prize pool, rent included, to `candidate_wallets[winner_idx]` of a
+    /// finalized poll that met its quorum. Anyone may call it. A tie blocks the payout
+    /// (`PrizeTied`); set a `tie_break` to have ties resolved at finalize, otherwise funders
+    /// get their contributions back with `refund_contribution`. Polls with a `certifier`
+    /// wait for `certify_results` or `certification_deadline_ts`. Uncertified results also
+    /// wait out `DISPUTE_WINDOW_SECS` after finalize, so a dispute can't be raced.
+    pub fn payout_prize(ctx: Context<PayoutPrize>) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp; // For the certification and dispute windows.
+        let poll = &mut ctx.accounts.poll;
+        let winner = poll.require_payable(now)?;
+        require_keys_eq!(
+            ctx.accounts.winner.key(),
+            poll.candidate_wallets[winner],
+            VotingError::AccountMismatch
+        ); // Only the winning candidate's wallet.
+        poll.prize_total = 0; // `close_poll` may run now.
+        // Lamports are moved by the `close = winner` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Marks a finalized result as contested, so `payout_prize` and downstream consumers hold
+    /// off until the protocol admin rules with `resolve_dispute`. The poll authority or the
+    /// protocol admin may call it within `DISPUTE_WINDOW_SECS` after finalize; `reason_code`
+    /// is free-form for off-chain tooling.
+    pub fn flag_dispute(ctx: Context<FlagDispute>, reason_code: u8) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let signer = ctx.accounts.signer.key();
+        let is_admin = ctx.accounts.protocol_config.as_ref().is_some_and(|c| c.admin == signer);
+        let poll = &mut ctx.accounts.poll;
+        require!(signer == poll.authority || is_admin, VotingError::NotDisputeParty);
+        require!(poll.finalized, VotingError::NotFinalized); // Only results can be contested.
+        require!(!poll.voided, VotingError::ResultVoided); // Already ruled on.
+        require!(!poll.disputed, VotingError::ResultDisputed); // One dispute at a time.
+        let window_end = poll.finalized_at.checked_add(DISPUTE_WINDOW_SECS).ok_or(VotingError::Overflow)?;
+        require!(clock.unix_timestamp <= window_end, VotingError::DisputeWindowClosed);
+        poll.disputed = true;
+        poll.dispute_reason = reason_code;
+        emit!(DisputeFlagged { poll: poll.key(), by: signer, reason_code });
+        Ok(())
+    }
+
+    /// Co-signs a finalized result. Only the poll's `certifier` may call it, once; until then
+    /// (or until `certification_deadline_ts` passes) `payout_prize` and `create_runoff` fail
+    /// with `NotCertified`.
+    pub fn certify_results(ctx: Context<CertifyResults>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let certifier = poll.certifier.ok_or(VotingError::NoCertifier)?; // Only certified polls.
+        require_keys_eq!(ctx.accounts.certifier.key(), certifier, VotingError::NotCertifier);
+        require!(poll.finalized, VotingError::NotFinalized); // Nothing to sign yet.
+        require!(!poll.certified, VotingError::AlreadyCertified); // Once.
+        poll.certified = true;
+        emit!(ResultsCertified { poll: poll.key(), certifier });
+        Ok(())
+    }
+
+    /// Rules on a disputed result (protocol admin only). `uphold = true` clears the flag and
+    /// the result stands; `false` voids it for good (`voided`), so the prize is refunded to
+    /// its funders instead of paid out.
+    pub fn resolve_dispute(ctx: Context<ResolveDispute>, uphold: bool) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.disputed, VotingError::NotDisputed); // Nothing to rule on.
+        poll.disputed = false;
+        poll.voided = !uphold;
+        emit!(DisputeResolved { poll: poll.key(), upheld: uphold });
+        Ok(())
+    }
+
+    /// Refunds a funder's `Contribution` from the prize pool and closes the record, so it
+    /// can't be refunded twice. Only when no one can win the prize: the poll was cancelled,
+    /// or finalized short of its quorum, tied, or without votes.
+    pub fn refund_contribution(ctx: Context<RefundContribution>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.prize_refundable(), VotingError::PrizeNotRefundable); // Otherwise the winner gets it.
+        let amount = ctx.accounts.contribution.amount;
+        poll.prize_total = poll.prize_total.checked_sub(amount).ok_or(VotingError::Overflow)?;
+        let pool_info = ctx.accounts.prize_pool.to_account_info(); // Program-owned: debit directly.
+        let funder_info = ctx.accounts.funder.to_account_info();
+        **pool_info.try_borrow_mut_lamports()? = pool_info
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(VotingError::Overflow)?;
+        **funder_info.try_borrow_mut_lamports()? = funder_info
+            .lamports()
+            .checked_add(amount)
+            .ok_or(VotingError::Overflow)?;
+        // The record's rent is returned by the `close = funder` constraint.
+        Ok(())
+    }
+
+    /// Upgrades a v1 poll (title-seeded PDA, no `version` byte) to the current layout. The
+    /// account grows to `INIT_SPACE` (authority pays the rent difference) and keeps its
+    /// title, candidates, tallies, window and status; every newer setting gets its default.
+    /// The address doesn't change, so `seq` is 0. Ballots on v1 polls were one per wallet at
+    /// weight 1, so the tallies also give `voter_count` and `turnout`.
+    pub fn migrate_poll(ctx: Context<MigratePoll>) -> Result<()> {
+        let info = ctx.accounts.poll.to_account_info();
+        require_keys_eq!(*info.owner, crate::ID, VotingError::UnsupportedVersion); // Our account.
+        let old = PollV1::read(&info.try_borrow_data()?)?; // Discriminator + v1 fields.
+        require_keys_eq!(
+            old.authority,
+            ctx.accounts.authority.key(),
+            anchor_lang::error::ErrorCode::ConstraintHasOne
+        ); // Only the stored authority may migrate.
+        let v1_address = Pubkey::create_program_address(
+            &[b"poll", old.authority.as_ref(), old.title.as_bytes(), &[old.bump]],
+            ctx.program_id,
+        )
+        .map_err(|_| VotingError::UnsupportedVersion)?;
+        require_keys_eq!(info.key(), v1_address, VotingError::UnsupportedVersion); // Only v1 polls live at title seeds.
+
+        grow_account(
+            &info,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+            8 + Poll::INIT_SPACE,
+        )?; // Room for the current layout; authority covers the extra rent.
+
+        let poll = old.upgrade(&info.key())?; // Current layout, classic settings.
+        poll.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?; // Discriminator + new layout.
+        Ok(())
+    }
+
+    /// Closes a voter record and returns its rent to the wallet that voted.
+    /// Only allowed after the poll ends so the record can't be deleted and recreated to vote twice.
+    pub fn close_voter(ctx: Context<CloseVoter>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        require!(
+            clock.unix_timestamp > ctx.accounts.poll.end_ts,
+            VotingError::PollStillOpen
+        );
+        require!(ctx.accounts.voter.locked_amount == 0, VotingError::TokensLocked); // Unlock first.
+        // Lamports are returned by the `close = wallet` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Creates the poll's participation receipt mint `[b"receipt_mint", poll]`: 0 decimals,
+    /// minted only by the PDA `[b"receipt", poll]`, with Metaplex metadata named after the
+    /// poll title and `uri_base` followed by the poll address as URI. Authority only, once.
+    /// Programs built without `receipt-nft` fail with `FeatureNotEnabled`.
+    pub fn init_receipt_mint(ctx: Context<InitReceiptMint>, uri_base: String) -> Result<()> {
+        require!(ctx.accounts.poll.receipt_mint.is_none(), VotingError::ReceiptMintExists); // Once.
+        require_keys_eq!(
+            ctx.accounts.token_program.key(),
+            token::spl_token::ID,
+            VotingError::InvalidTokenAccount
+        ); // Token Metadata expects classic SPL Token mints.
+        create_receipt_mint(ctx.accounts, ctx.bumps.receipt_mint, ctx.bumps.receipt_authority, &uri_base)?;
+        ctx.accounts.poll.receipt_mint = Some(ctx.accounts.receipt_mint.key());
+        Ok(())
+    }
+
+    /// Mints one receipt token to the voter's associated token account (created if missing),
+    /// once per Voter record (`receipt_claimed`). The voter signs and pays; only after end_ts,
+    /// so the record can't be retracted and recreated for a second receipt.
+    pub fn claim_receipt(ctx: Context<ClaimReceipt>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &ctx.accounts.poll;
+        let mint = poll.receipt_mint.ok_or(VotingError::NoReceipts)?; // `init_receipt_mint` ran.
+        require_keys_eq!(ctx.accounts.receipt_mint.key(), mint, VotingError::AccountMismatch);
+        require!(clock.unix_timestamp > poll.end_ts, VotingError::PollStillOpen); // Ballots are final.
+        require!(!ctx.accounts.voter.receipt_claimed, VotingError::ReceiptAlreadyClaimed); // Once per voter.
+        let ata = token::associated_token_address(&ctx.accounts.wallet.key(), &mint, &token::spl_token::ID);
+        require_keys_eq!(ctx.accounts.destination.key(), ata, VotingError::InvalidTokenAccount); // Voter's ATA.
+        mint_receipt(ctx.accounts, ctx.bumps.receipt_authority)?;
+        ctx.accounts.voter.receipt_claimed = true;
+        Ok(())
+    }
+
+    /// Permissionless cleanup: closes a stale Voter record, paying `VOTER_SWEEP_BOUNTY` of its
+    /// rent to the cranker and the rest to the wallet that voted. Allowed once the poll's
+    /// `voter_retention_secs` have passed after voting ends (after `reveal_deadline_ts` for
+    /// commit-reveal polls). If the poll was already closed, `VOTER_RETENTION_SECS` after the
+    /// record's `voted_at` instead. Records with escrowed tokens must be unlocked first.
+    pub fn sweep_voter(ctx: Context<SweepVoter>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let voter = &ctx.accounts.voter;
+        require!(voter.locked_amount == 0, VotingError::TokensLocked); // Unlock first.
+        let info = ctx.accounts.poll.to_account_info();
+        let stale_after = if info.owner == ctx.program_id {
+            let poll = Poll::try_deserialize(&mut &info.try_borrow_data()?[..])?; // Not closed yet.
+            require!(poll.version == POLL_VERSION, VotingError::UnsupportedVersion);
+            let phase_end = if poll.commit_reveal {
+                poll.reveal_deadline_ts.max(poll.end_ts) // Reveals still need the record.
+            } else {
+                poll.end_ts
+            };
+            phase_end.checked_add(poll.voter_retention_secs as i64)
+        } else {
+            require!(info.data_is_empty(), VotingError::AccountMismatch); // Closed by `close_poll`.
+            voter.voted_at.checked_add(VOTER_RETENTION_SECS as i64)
+        }
+        .ok_or(VotingError::Overflow)?;
+        require!(clock.unix_timestamp > stale_after, VotingError::PollStillOpen); // Retention period.
+
+        let voter_info = voter.to_account_info(); // Program-owned: debit directly.
+        let cranker_info = ctx.accounts.cranker.to_account_info();
+        let bounty = VOTER_SWEEP_BOUNTY.min(voter_info.lamports());
+        **voter_info.try_borrow_mut_lamports()? -= bounty;
+        **cranker_info.try_borrow_mut_lamports()? = cranker_info
+            .lamports()
+            .checked_add(bounty)
+            .ok_or(VotingError::Overflow)?;
+        // The rest is returned by the `close = wallet` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Cancels a poll that has not received any votes yet (e.g. wrong candidates or dates).
+    /// The account stays on chain so later votes fail with `PollCancelled`; the authority can
+    /// reclaim the rent right away with `close_poll`.
+    pub fn cancel_poll(ctx: Context<CancelPoll>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_cancellable(clock.unix_timestamp)?;
+        poll.cancelled = true; // Block any further voting.
+        Ok(())
+    }
+
+    /// Freezes the results of an ended poll and stores the winner on chain.
+    /// Callable by anyone after end_ts; the signing `cranker` (normally the fee payer) gets the
+    /// `finalize_bounty` deposited at init, if any. Only the first call succeeds, so the bounty
+    /// is paid once. A tie for first place is stored as `winner_idx = None` with
+    /// `is_tie = true`; a poll with no votes has no winner and no tie.
+    pub fn finalize_poll(ctx: Context<FinalizePoll>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let slot_hashes = ctx.accounts.slot_hashes.as_ref().map(|a| a.to_account_info());
+        finalize_results(&mut ctx.accounts.poll, None, slot_hashes.as_ref(), clock.unix_timestamp)?; // Checks, stores, emits.
+        pay_finalize_bounty(&mut ctx.accounts.poll, &ctx.accounts.cranker)
+    }
+
+    /// Permissionless crank: finalizes someone else's expired poll and pays the cranker the
+    /// `finalize_bounty` deposited at init (if any). Same as `finalize_poll`.
+    pub fn crank_finalize(ctx: Context<CrankFinalize>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let slot_hashes = ctx.accounts.slot_hashes.as_ref().map(|a| a.to_account_info());
+        finalize_results(&mut ctx.accounts.poll, None, slot_hashes.as_ref(), clock.unix_timestamp)?; // Checks, stores, emits.
+        pay_finalize_bounty(&mut ctx.accounts.poll, &ctx.accounts.cranker)
+    }
+
+    /// Finalizes a ranked-choice poll by instant runoff over its `RankedTally`: each round the
+    /// candidate(s) with the fewest ballots ranking them highest among those still standing are
+    /// eliminated, until one candidate has a strict majority of the non-exhausted ballots.
+    /// Candidates tied for last go out together; if that would eliminate everyone, the result
+    /// is a tie. `PollFinalized.totals` carries the last round's counts. Same timing rules and
+    /// bounty as `crank_finalize`.
+    pub fn tally_irv(ctx: Context<TallyIrv>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        finalize_results(
+            &mut ctx.accounts.poll,
+            Some(ctx.accounts.ranked_tally.counts.as_slice()),
+            None, // Ranked choice has no tie-break.
+            clock.unix_timestamp,
+        )?; // Checks, runs the rounds, stores, emits.
+        pay_finalize_bounty(&mut ctx.accounts.poll, &ctx.accounts.cranker)
+    }
+
+    /// Counts the ballots of a hidden-tally poll after end_ts. Anyone may call it, in batches:
+    /// `remaining_accounts` are writable Voter records of this poll, each added to `votes`
+    /// (or `downvotes`) once and flagged `counted`, so a record can't be counted twice
+    /// (`AlreadyCounted`). Abstentions are just marked. Finalize works once every record
+    /// (`voter_count`) has been tabulated.
+    pub fn tabulate<'info>(ctx: Context<'_, '_, 'info, 'info, Tabulate<'info>>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_consistent()?; // Tallies line up with candidates.
+        require!(poll.hide_tallies, VotingError::TalliesNotHidden);
+        require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to count.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // Results are frozen.
+        require!(clock.unix_timestamp > poll.end_ts, VotingError::PollStillOpen); // Ballots are final.
+        require!(
+            ctx.remaining_accounts.len() <= MAX_TABULATE_BATCH,
+            VotingError::BatchTooLarge
+        ); // Bounded work per tx.
+        for info in ctx.remaining_accounts {
+            let mut voter: Account<Voter> = Account::try_from(info)?; // Owner + discriminator.
+            require_keys_eq!(voter.poll, poll.key(), VotingError::AccountMismatch); // This poll's record.
+            require!(!voter.counted, VotingError::AlreadyCounted); // Once per record.
+            let tally = if voter.against { &mut poll.downvotes } else { &mut poll.votes };
+            for &choice in &voter.choices[..voter.votes_used as usize] {
+                let idx = choice as usize; // Candidate chosen.
+                require!(idx < tally.len(), VotingError::BadCandidate); // Stored index must still be valid.
+                tally[idx] = tally[idx].checked_add(voter.weight).ok_or(VotingError::Overflow)?;
+            } // Abstentions have no choices.
+            voter.counted = true;
+            voter.exit(ctx.program_id)?; // Persist the flag.
+            poll.tabulated = poll.tabulated.checked_add(1).ok_or(VotingError::Overflow)?;
+        }
+        Ok(())
+    }
+
+    /// Starts handing poll administration to a new wallet (e.g. an ops key handing over to a
+    /// DAO). Nothing changes until `new_authority` signs `accept_authority`, so a mistyped key
+    /// can't strand the poll; calling it again replaces the pending key, and passing the
+    /// current authority cancels.
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        poll.pending_authority = if new_authority == poll.authority { None } else { Some(new_authority) };
+        Ok(())
+    }
+
+    /// Completes a handover started by `propose_authority`: the signer must be the pending key
+    /// and becomes the poll authority. Only the stored field changes: the poll PDA stays at the
+    /// address derived from the original authority, and `has_one = authority` checks follow
+    /// the new value.
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let signer = ctx.accounts.new_authority.key();
+        require!(poll.pending_authority == Some(signer), VotingError::NotPendingAuthority);
+        poll.authority = signer; // Swap stored authority; address unchanged.
+        poll.pending_authority = None;
+        Ok(())
+    }
+
+    /// Drops a pending handover (current authority only).
+    pub fn cancel_authority_transfer(ctx: Context<ProposeAuthority>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        require!(poll.pending_authority.is_some(), VotingError::NoPendingAuthority); // Nothing to cancel.
+        poll.pending_authority = None;
+        Ok(())
+    }
+
+    /// Pushes out end_ts (e.g. when turnout is low).
+    /// Only before finalization and at most `EXTEND_GRACE_SECS` after the current end_ts.
+    pub fn extend_poll(ctx: Context<ExtendPoll>, new_end_ts: i64) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to extend.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // Results are frozen.
+        require!(new_end_ts > poll.end_ts, VotingError::BadSchedule); // Extensions only move forward.
+        require!(new_end_ts > clock.unix_timestamp, VotingError::EndInPast); // Actually reopens.
+        let grace_end = poll
+            .end_ts
+            .checked_add(EXTEND_GRACE_SECS)
+            .ok_or(VotingError::Overflow)?; // Last moment an ended poll can be reopened.
+        require!(clock.unix_timestamp <= grace_end, VotingError::ExtensionTooLate);
+
+        let old_end_ts = poll.end_ts; // Remember for the event.
+        poll.move_end(new_end_ts)?; // Save new end time, deadlines follow.
+        emit!(PollExtended {
+            poll: poll.key(),
+            old_end_ts,
+            new_end_ts,
+        });
+        Ok(())
+    }
+
+    /// Kill switch: blocks voting until `resume_poll` is called.
+    pub fn pause_poll(ctx: Context<PausePoll>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to pause.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // Results are frozen.
+        require!(!poll.paused, VotingError::PollPaused); // Already paused.
+        poll.paused = true; // Stop accepting votes.
+        Ok(())
+    }
+
+    /// Lifts a pause. `extend_by_secs` optionally adds the lost time back to end_ts, with the
+    /// same deadline shifts and duration cap as `extend_poll`.
+    pub fn resume_poll(ctx: Context<PausePoll>, extend_by_secs: Option<u32>) -> Result<()> {
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        require!(poll.paused, VotingError::NotPaused); // Only resume a paused poll.
+        require!(!poll.finalized, VotingError::AlreadyFinalized); // `end_ts` is in `results_hash`.
+        if let Some(secs) = extend_by_secs.filter(|&secs| secs > 0) {
+            let old_end_ts = poll.end_ts; // Remember for the event.
+            let new_end_ts = old_end_ts.checked_add(i64::from(secs)).ok_or(VotingError::Overflow)?;
+            poll.move_end(new_end_ts)?; // Give voters the paused time back.
+            emit!(PollExtended {
+                poll: poll.key(),
+                old_end_ts,
+                new_end_ts,
+            });
+        }
+        poll.paused = false; // Accept votes again.
+        Ok(())
+    }
+
+    /// Fixes the poll title before voting opens (no votes yet), same 64-byte limit as init.
+    /// The title is not part of the PDA seeds, so the poll address does not change. Like the
+    /// other text edits, this grows the account to `Poll::max_space` (authority pays).
+    pub fn update_title(ctx: Context<EditPoll>, new_title: String) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        validate_title(&new_title)?; // Same rules as init.
+        poll.title = new_title; // Save new title; address unaffected.
+        Ok(())
+    }
+
+    /// Sets or clears the merkle root of eligible wallets before voting opens.
+    /// Large electorates can use this instead of one credential PDA per voter.
+    pub fn set_allowlist_root(ctx: Context<UpdatePoll>, root: Option<[u8; 32]>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Eligibility is fixed once voting opens.
+        poll.require_allowlist_root(&root)?; // Only where ballots check it.
+        poll.allowlist_root = root; // Save (or clear) the root.
+        Ok(())
+    }
+
+    /// Publishes (or clears) the merkle root of `(wallet, balance)` pairs taken at the poll's
+    /// snapshot slot. Fixed once voting opens, like the allowlist root.
+    pub fn set_weight_root(ctx: Context<UpdatePoll>, root: Option<[u8; 32]>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Weights are fixed once voting opens.
+        poll.weight_root = root; // Save (or clear) the root.
+        Ok(())
+    }
+
+    /// Edits the poll description before voting opens (no votes yet), same 256-byte limit as init.
+    pub fn update_description(ctx: Context<EditPoll>, new_description: String) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        require!(new_description.len() <= 256, VotingError::DescriptionTooLong); // Description length bound.
+        poll.description = new_description; // Save new description.
+        Ok(())
+    }
+
+    /// Adds a late entrant before voting opens, up to `max_candidates` (the account already has
+    /// room for it).
+    pub fn add_candidate(ctx: Context<AddCandidate>, name: String) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        require!(poll.kind == PollKind::Election, VotingError::ReferendumOptions); // Yes/No stay fixed.
+        require!(!poll.sharded, VotingError::ShardedPoll); // One Tally per candidate, made at init.
+        require!(poll.candidates.len() < poll.max_candidates as usize, VotingError::TooManyCandidates); // Cap chosen at init.
+        validate_candidate_name(&name)?; // Same rules as init_poll.
+        require_new_candidate(poll.candidates.iter().map(String::as_str), &name)?; // Distinct names.
+        make_room_for_candidate(
+            poll,
+            &name,
+            &ctx.accounts.authority.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?; // Only write-in polls start without room.
+        poll.candidates.push(name); // Append candidate.
+        poll.votes.push(0); // Keep tallies aligned.
+        poll.downvotes.push(0);
+        poll.last_increment_ts.push(0);
+        poll.candidate_wallets.push(ctx.accounts.authority.key()); // Entered by the authority.
+        poll.approved.push(true);
+        emit!(CandidatesChanged {
+            poll: poll.key(),
+            candidates: poll.candidates.clone(),
+        });
+        Ok(())
+    }
+
+    /// Raises `max_candidates` before voting opens, growing the account to `Poll::space` for
+    /// the new cap (authority pays the rent difference), so `add_candidate` can go past the
+    /// size chosen at init without a new poll address. Up to 8 for any non-sharded election,
+    /// up to `MAX_EXPANDED_CANDIDATES` for plurality ballots (other ballot types keep
+    /// per-candidate data sized for 8).
+    pub fn expand_poll(ctx: Context<ExpandPoll>, new_max_candidates: u8) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to grow.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        require!(poll.kind == PollKind::Election, VotingError::ReferendumOptions); // Yes/No stay fixed.
+        require!(!poll.sharded, VotingError::ShardedPoll); // One Tally per candidate, made at init.
+        require!(
+            new_max_candidates > poll.max_candidates && new_max_candidates <= MAX_EXPANDED_CANDIDATES,
+            VotingError::InvalidMaxCandidates
+        ); // Only grows.
+        require!(
+            new_max_candidates <= 8 || poll.ballot_type == BallotType::Plurality,
+            VotingError::WrongBallotType
+        ); // Voter ballots and ranked counters hold 8 candidates.
+        let len = 8 + Poll::space(
+            &poll.title,
+            &poll.description,
+            &poll.candidates,
+            new_max_candidates,
+            poll.receiptless_voters,
+        );
+        let info = poll.to_account_info();
+        if len > info.data_len() {
+            grow_account(
+                &info,
+                &ctx.accounts.authority.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+                len,
+            )?; // Room for the new entries; authority pays.
+        }
+        poll.max_candidates = new_max_candidates;
+        Ok(())
+    }
+
+    /// Lets any wallet enter itself as a candidate until `registration_end_ts`. The name is
+    /// validated like `add_candidate` and counts towards the same `max_candidates` cap; their
+    /// wallet is stored in `candidate_wallets`. One entry per wallet. The entry stays pending (`approved = false`)
+    /// until the authority approves or rejects it. Polls with a `candidate_fee` also take the
+    /// fee into the treasury and record it in a `CandidateFee` PDA for `refund_candidate_fee`.
+    pub fn register_candidate(ctx: Context<RegisterCandidate>, name: String) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_registration_open(clock.unix_timestamp)?;
+        let wallet = ctx.accounts.candidate.key(); // Registering wallet.
+        require!(!poll.candidate_wallets.contains(&wallet), VotingError::AlreadyRegistered); // One entry each.
+        require!(poll.candidates.len() < poll.max_candidates as usize, VotingError::TooManyCandidates); // Cap chosen at init.
+        validate_candidate_name(&name)?; // Same rules as init_poll.
+        require_new_candidate(poll.candidates.iter().map(String::as_str), &name)?; // Distinct names.
+        make_room_for_candidate(
+            poll,
+            &name,
+            &ctx.accounts.candidate.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?; // Only write-in polls start without room.
+        if poll.candidate_fee > 0 {
+            let treasury = ctx.accounts.treasury.as_ref().ok_or(VotingError::TreasuryRequired)?;
+            let record = ctx.accounts.candidate_fee_record.as_mut().ok_or(VotingError::CandidateFeeRecordRequired)?;
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: ctx.accounts.candidate.to_account_info(),
+                        to: treasury.to_account_info(),
+                    },
+                ),
+                poll.candidate_fee,
+            )?;
+            record.poll = poll.key();
+            record.wallet = wallet;
+            record.amount = poll.candidate_fee;
+            record.rejected = false;
+            record.bump = ctx.bumps.candidate_fee_record.ok_or(VotingError::CandidateFeeRecordRequired)?;
+        }
+        poll.candidates.push(name); // Append candidate.
+        poll.votes.push(0); // Keep tallies aligned.
+        poll.downvotes.push(0);
+        poll.last_increment_ts.push(0);
+        poll.candidate_wallets.push(wallet);
+        poll.approved.push(false); // Pending until `approve_candidate`.
+        emit!(CandidatesChanged {
+            poll: poll.key(),
+            candidates: poll.candidates.clone(),
+        });
+        Ok(())
+    }
+
+    /// Approves a pending candidate during the registration phase, so ballots may name it.
+    pub fn approve_candidate(ctx: Context<UpdatePoll>, candidate_idx: u8) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_registration_open(clock.unix_timestamp)?;
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        poll.approved[idx] = true;
+        Ok(())
+    }
+
+    /// Rejects a candidate during the registration phase. The entry is removed (later indices
+    /// shift down by one, like `remove_candidate`), so its slot can be reused. On polls with a
+    /// `candidate_fee`, rejecting a pending candidate takes its `CandidateFee` record and makes
+    /// the fee refundable; approved candidates forfeit it.
+    pub fn reject_candidate(ctx: Context<RejectCandidate>, candidate_idx: u8) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_registration_open(clock.unix_timestamp)?;
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        if poll.candidate_fee > 0 && !poll.approved[idx] {
+            let record = ctx.accounts.candidate_fee_record.as_mut().ok_or(VotingError::CandidateFeeRecordRequired)?;
+            require_keys_eq!(record.poll, poll.key(), VotingError::AccountMismatch);
+            require_keys_eq!(record.wallet, poll.candidate_wallets[idx], VotingError::AccountMismatch); // This entry's fee.
+            require!(!record.rejected, VotingError::AccountMismatch);
+            record.rejected = true;
+            poll.candidate_refunds_owed = poll.candidate_refunds_owed.saturating_add(record.amount); // Stays in the treasury.
+        }
+        poll.remove_candidate_at(idx); // Voting checks there are enough left.
+        emit!(CandidatesChanged {
+            poll: poll.key(),
+            candidates: poll.candidates.clone(),
+        });
+        Ok(())
+    }
+
+    /// Drops a withdrawn candidate before voting opens. Later indices shift down by one, so
+    /// the full new list is emitted for clients to re-sync.
+    pub fn remove_candidate(ctx: Context<UpdatePoll>, candidate_idx: u8) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        require!(!poll.sharded, VotingError::ShardedPoll); // Tally indices are fixed at init.
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        require!(poll.candidates.len() > 2, VotingError::NotEnoughCandidates); // Keep at least two choices.
+        require!(
+            poll.candidates.len() - 1 > poll.num_winners as usize,
+            VotingError::NotEnoughCandidates
+        ); // More candidates than seats.
+        poll.remove_candidate_at(idx); // Drop candidate and everything aligned with it.
+        emit!(CandidatesChanged {
+            poll: poll.key(),
+            candidates: poll.candidates.clone(),
+        });
+        Ok(())
+    }
+
+    /// Fixes a misspelled candidate name before voting opens (never once a vote exists).
+    pub fn rename_candidate(
+        ctx: Context<EditPoll>,
+        candidate_idx: u8,
+        new_name: String,
+    ) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        poll.require_setup_phase(clock.unix_timestamp)?; // Only before voting opens.
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < poll.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        require!(poll.kind == PollKind::Election, VotingError::ReferendumOptions); // Yes/No stay fixed.
+        validate_candidate_name(&new_name)?; // Same rules as init_poll.
+        let others = poll.candidates.iter().enumerate().filter(|(i, _)| *i != idx); // Renaming to itself is fine.
+        require_new_candidate(others.map(|(_, c)| c.as_str()), &new_name)?; // Distinct names.
+        poll.candidates[idx] = new_name; // Replace name in place; index unchanged.
+        emit!(CandidatesChanged {
+            poll: poll.key(),
+            candidates: poll.candidates.clone(),
+        });
+        Ok(())
+    }
+
+    /// Issues a voting credential for `wallet` on an allowlisted poll. Authority pays the rent.
+    /// `weight` is what the wallet's ballot counts in `Credential` weighting (must be > 0).
+    /// Polls with a `ParticipationBitmap` need it passed here; the credential gets its next slot.
+    pub fn register_voter(ctx: Context<RegisterVoter>, wallet: Pubkey, weight: u64) -> Result<()> {
+        require!(weight > 0, VotingError::InvalidWeight); // Zero-weight members can't vote anyway.
+        require!(
+            ctx.accounts.poll.participation.is_none() || ctx.accounts.participation.is_some(),
+            VotingError::AccountMismatch
+        ); // Every credential of a bitmap poll gets a slot.
+        let credential = &mut ctx.accounts.credential; // New credential PDA.
+        credential.poll = ctx.accounts.poll.key(); // Poll this credential is valid for.
+        credential.wallet = wallet; // Eligible wallet.
+        credential.bump = ctx.bumps.credential; // Save bump for PDA recreation.
+        credential.weight = weight; // Share count for weighted polls.
+        credential.index = match ctx.accounts.participation.as_mut() {
+            Some(bitmap) => Some(bitmap.assign()?), // Sequential slot.
+            None => None,
+        };
+        Ok(())
+    }
+
+    /// Gives a credential issued before the poll's `ParticipationBitmap` existed its slot.
+    pub fn index_voter(ctx: Context<IndexVoter>, _wallet: Pubkey) -> Result<()> {
+        let credential = &mut ctx.accounts.credential;
+        require!(credential.index.is_none(), VotingError::AlreadyIndexed); // One slot per credential.
+        credential.index = Some(ctx.accounts.participation.assign()?);
+        Ok(())
+    }
+
+    /// Changes a member's credential weight. Ballots already cast keep the weight recorded at
+    /// vote time; only later votes (including a re-vote after `retract_vote`) use the new one.
+    pub fn update_weight(ctx: Context<UpdateWeight>, _wallet: Pubkey, weight: u64) -> Result<()> {
+        require!(weight > 0, VotingError::InvalidWeight); // Revoke instead of zeroing.
+        ctx.accounts.credential.weight = weight; // Tally is untouched.
+        Ok(())
+    }
+
+    /// Issues credentials for several wallets at once. `remaining_accounts` must hold the
+    /// credential PDA of each wallet, in the same order, writable and not yet created; the
+    /// authority pays rent for all of them. Any mismatch fails the whole batch. Slots in a
+    /// `ParticipationBitmap` are handed out in the same order.
+    pub fn register_voters_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RegisterVotersBatch<'info>>,
+        wallets: Vec<Pubkey>,
+        weights: Vec<u64>, // Credential weight per wallet, same order.
+    ) -> Result<()> {
+        require!(wallets.len() <= MAX_BATCH_REGISTER, VotingError::BatchTooLarge); // Bounded work per tx.
+        require!(
+            ctx.remaining_accounts.len() == wallets.len() && weights.len() == wallets.len(),
+            VotingError::AccountMismatch
+        ); // One credential account and weight per wallet.
+        require!(weights.iter().all(|w| *w > 0), VotingError::InvalidWeight); // Same rule as `register_voter`.
+        require!(
+            ctx.accounts.poll.participation.is_none() || ctx.accounts.participation.is_some(),
+            VotingError::AccountMismatch
+        ); // Every credential of a bitmap poll gets a slot.
+
+        let poll_key = ctx.accounts.poll.key(); // Poll the credentials are for.
+        for ((wallet, weight), info) in wallets.iter().zip(weights).zip(ctx.remaining_accounts.iter()) {
+            let (expected, bump) = Pubkey::find_program_address(
+                &[b"cred", poll_key.as_ref(), wallet.as_ref()],
+                ctx.program_id,
+            ); // Same seeds as `register_voter`.
+            require_keys_eq!(info.key(), expected, VotingError::AccountMismatch); // Right PDA.
+            require!(info.is_writable, VotingError::AccountMismatch); // Must be creatable.
+            require!(info.owner == &system_program::ID, VotingError::AccountMismatch); // Not already registered.
+
+            create_pda_account(
+                &ctx.accounts.authority.to_account_info(),
+                info,
+                &ctx.accounts.system_program.to_account_info(),
+                8 + VoterCredential::SIZE, // Discriminator + size of VoterCredential.
+                ctx.program_id,
+                &[b"cred", poll_key.as_ref(), wallet.as_ref(), &[bump]],
+            )?; // Authority funds each credential.
+
+            let credential = VoterCredential {
+                poll: poll_key,  // Poll this credential is valid for.
+                wallet: *wallet, // Eligible wallet.
+                bump,            // Save bump for PDA recreation.
+                weight,          // Share count for weighted polls.
+                index: match ctx.accounts.participation.as_mut() {
+                    Some(bitmap) => Some(bitmap.assign()?), // Sequential slot.
+                    None => None,
+                },
+            };
+            credential.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?; // Discriminator + fields.
+        }
+        Ok(())
+    }
+
+    /// Records that the poll's registrar verified `wallet` as the person behind `identity_hash`
+    /// (e.g. a hash of a KYC record). Only the registrar may sign; it pays the rent.
+    pub fn attest(ctx: Context<Attest>, wallet: Pubkey, identity_hash: [u8; 32]) -> Result<()> {
+        let attestation = &mut ctx.accounts.attestation; // New attestation PDA.
+        attestation.poll = ctx.accounts.poll.key(); // Poll the attestation is for.
+        attestation.wallet = wallet; // Attested wallet.
+        attestation.identity_hash = identity_hash; // Person behind it, as the registrar knows them.
+        attestation.bump = ctx.bumps.attestation; // Save bump for PDA recreation.
+        Ok(())
+    }
+
+    /// Withdraws an attestation before the wallet has voted; rent goes back to the registrar.
+    pub fn revoke_attestation(ctx: Context<RevokeAttestation>, _wallet: Pubkey) -> Result<()> {
+        require!(ctx.accounts.voter.data_is_empty(), VotingError::AlreadyVoted); // Ballot already counted.
+        // Attestation is closed by the `close = registrar` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Bars `wallet` from voting in this poll by creating its `Banned` PDA (authority pays).
+    /// A ballot already cast stays counted but can no longer be changed or retracted.
+    pub fn ban_voter(ctx: Context<BanVoter>, wallet: Pubkey) -> Result<()> {
+        let banned = &mut ctx.accounts.banned; // New ban PDA.
+        banned.poll = ctx.accounts.poll.key(); // Poll the ban applies to.
+        banned.wallet = wallet; // Barred wallet.
+        banned.bump = ctx.bumps.banned; // Save bump for PDA recreation.
+        Ok(())
+    }
+
+    /// Lifts a ban; rent goes back to the authority.
+    pub fn unban_voter(_ctx: Context<UnbanVoter>, _wallet: Pubkey) -> Result<()> {
+        // Ban is closed by the `close = authority` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Revokes a wallet's credential; rent goes back to the authority.
+    /// A ballot already cast stays counted. Credentials with a bitmap slot need the bitmap and
+    /// can only be revoked before they vote: a new credential gets a fresh slot, so revoking a
+    /// spent one would let the wallet vote twice.
+    pub fn revoke_voter(ctx: Context<RevokeVoter>, _wallet: Pubkey) -> Result<()> {
+        if let Some(index) = ctx.accounts.credential.index {
+            let bitmap = ctx.accounts.participation.as_ref().ok_or(VotingError::AccountMismatch)?;
+            require!(!bitmap.is_set(index), VotingError::AlreadyVoted); // Slot already spent.
+        }
+        // Credential is closed by the `close = authority` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Withdraws a ballot while the poll is still open: the recorded candidate's tally is
+    /// decremented and the Voter PDA is closed, so the wallet may vote again later.
+    pub fn retract_vote(ctx: Context<RetractVote>) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(ctx.accounts.voter.locked_amount == 0, VotingError::TokensLocked); // Escrowed ballots stay.
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Banned ballots are frozen.
+        require!(!ctx.accounts.poll.sharded, VotingError::ShardedPoll); // Sharded ballots are final.
+        require!(!ctx.accounts.voter.counted, VotingError::AlreadyCounted); // Already in the tabulated tally.
+
+        let voter = &ctx.accounts.voter; // Record being withdrawn.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        match poll.ballot_type {
+            _ if voter.abstained => {
+                poll.abstentions = poll.abstentions.checked_sub(1).ok_or(VotingError::Overflow)?; // Undo the abstention.
+            }
+            BallotType::Plurality if poll.hide_tallies => {} // Never added: nothing to undo.
+            BallotType::Plurality => {
+                let tally = if voter.against { &mut poll.downvotes } else { &mut poll.votes };
+                for &choice in &voter.choices[..voter.votes_used as usize] {
+                    let idx = choice as usize; // Candidate chosen earlier.
+                    require!(idx < tally.len(), VotingError::BadCandidate); // Stored index must still be valid.
+                    tally[idx] = tally[idx]
+                        .checked_sub(voter.weight)
+                        .ok_or(VotingError::Overflow)?; // Undo each earlier increment.
+                }
+                for &choice in &voter.choices[..voter.votes_used as usize] {
+                    poll.last_increment_ts[choice as usize] = clock.unix_timestamp; // Counts changed again.
+                }
+            }
+            BallotType::Approval
+            | BallotType::Borda
+            | BallotType::Score
+            | BallotType::Cumulative { .. }
+            | BallotType::Split => {
+                for (idx, p) in ballot_points(poll.ballot_type, &voter.ballot).into_iter().enumerate() {
+                    require!(idx < poll.votes.len(), VotingError::BadCandidate); // Stored ballot must still fit.
+                    let removed = voter.weight.checked_mul(p as u64).ok_or(VotingError::Overflow)?;
+                    poll.votes[idx] = poll.votes[idx].checked_sub(removed).ok_or(VotingError::Overflow)?; // Undo each candidate's points.
+                }
+            }
+            BallotType::RankedChoice => {
+                let first = *voter.ballot.first().ok_or(VotingError::BadCandidate)? as usize; // First preference.
+                require!(first < poll.votes.len(), VotingError::BadCandidate); // Stored index must still be valid.
+                poll.votes[first] = poll
+                    .votes[first]
+                    .checked_sub(voter.weight)
+                    .ok_or(VotingError::Overflow)?; // Undo the first-preference count.
+                let tally = ctx.accounts.ranked_tally.as_mut().ok_or(VotingError::AccountMismatch)?;
+                apply_ranking(&mut tally.counts, &voter.ballot, poll.candidates.len(), voter.weight, false)?;
+            }
+        }
+        if voter.votes_used > 0 || voter.abstained {
+            poll.turnout = poll.turnout.checked_sub(voter.weight).ok_or(VotingError::Overflow)?; // Unrevealed commitments weren't counted.
+        }
+        poll.voter_count = poll.voter_count.checked_sub(1).ok_or(VotingError::Overflow)?; // Frees a `max_voters` slot.
+        // Voter PDA is closed by the `close = wallet` constraint after the handler runs.
+        Ok(())
+    }
+
+    /// Atomically moves an existing ballot to another candidate while the poll is open.
+    pub fn change_vote(ctx: Context<ChangeVote>, new_candidate_idx: u8) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let clock = Clock::get()?; // Read current cluster time.
+        ctx.accounts.poll.require_voting_open(clock.unix_timestamp)?; // Window open, not frozen.
+        require!(!ctx.accounts.poll.sharded, VotingError::ShardedPoll); // Sharded ballots are final.
+
+        require!(ctx.accounts.ban.data_is_empty(), VotingError::VoterBanned); // Banned ballots are frozen.
+        require!(
+            ctx.accounts.poll.ballot_type == BallotType::Plurality,
+            VotingError::WrongBallotType
+        ); // Other ballots are retracted and cast again.
+        require!(!ctx.accounts.poll.commit_reveal, VotingError::CommitRevealPoll); // Retract the commitment instead.
+        require!(!ctx.accounts.voter.counted, VotingError::AlreadyCounted); // Already in the tabulated tally.
+        let voter = &mut ctx.accounts.voter; // Existing record for (poll, wallet).
+        require!(!voter.abstained, VotingError::Abstained); // Retract the abstention instead.
+        require!(voter.votes_used == 1, VotingError::MultipleBallots); // Retract and re-vote instead.
+        require!(
+            voter.candidate_idx != new_candidate_idx,
+            VotingError::SameCandidate
+        );
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        let old_idx = voter.candidate_idx as usize; // Candidate chosen earlier.
+        let new_idx = new_candidate_idx as usize; // Cast to usize for indexing.
+        poll.require_candidate(new_candidate_idx)?; // In range and approved.
+        require!(old_idx < poll.votes.len(), VotingError::BadCandidate); // Stored index must still be valid.
+
+        // The weight recorded at vote time moves as-is; balances aren't re-read. A vote against
+        // stays a vote against. Hidden tallies only change the record.
+        if !poll.hide_tallies {
+            let tally = if voter.against { &mut poll.downvotes } else { &mut poll.votes };
+            tally[old_idx] = tally[old_idx]
+                .checked_sub(voter.weight)
+                .ok_or(VotingError::Overflow)?; // Take the ballot off the old candidate.
+            tally[new_idx] = tally[new_idx]
+                .checked_add(voter.weight)
+                .ok_or(VotingError::Overflow)?; // Credit the new candidate.
+            poll.last_increment_ts[old_idx] = clock.unix_timestamp; // Both counts changed.
+            poll.last_increment_ts[new_idx] = clock.unix_timestamp;
+        }
+        voter.candidate_idx = new_candidate_idx; // Record the new choice.
+        voter.choices[0] = new_candidate_idx;
+        voter.voted_at = clock.unix_timestamp; // When the current choice was recorded.
+        Ok(())
+    }
+
+    /// Returns a voter's escrowed tokens once the poll has ended (or right away if cancelled).
+    /// Anyone may call it; the tokens always go to a token account owned by the voter wallet.
+    /// The tally is left as is.
+    pub fn unlock_tokens(ctx: Context<UnlockTokens>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll; // Poll account to mutate.
+        require!(
+            poll.cancelled || clock.unix_timestamp > poll.end_ts,
+            VotingError::PollStillOpen
+        );
+        let VoteWeighting::Escrow { mint } = poll.weighting else {
+            return err!(VotingError::NothingToUnlock); // Only escrow polls lock tokens.
+        };
+        let voter = &mut ctx.accounts.voter; // Record holding the locked amount.
+        let amount = voter.locked_amount; // Exactly what was locked.
+        require!(amount > 0, VotingError::NothingToUnlock); // Blocks a second unlock.
+
+        let poll_key = poll.key();
+        let vault = ctx.accounts.escrow_vault.to_account_info();
+        let (_, bump) = require_vault(b"escrow", &poll_key, mint, &vault, ctx.program_id)?; // Vault of this poll.
+        let destination = token::unpack_token_account(&ctx.accounts.destination)?; // Must be a token account.
+        require_keys_eq!(destination.mint, mint, VotingError::InvalidTokenAccount); // Same token.
+        require_keys_eq!(destination.owner, voter.wallet, VotingError::InvalidTokenAccount); // Back to the voter.
+
+        token::transfer(
+            &ctx.accounts.token_program,
+            &vault,
+            &ctx.accounts.destination,
+            &ctx.accounts.escrow_authority,
+            amount,
+            &[&[b"escrow", poll_key.as_ref(), &[bump]]],
+        )?; // Escrow PDA signs for the vault.
+        voter.locked_amount = 0; // Nothing left to return.
+        poll.escrow_locked = poll
+            .escrow_locked
+            .checked_sub(amount)
+            .ok_or(VotingError::Overflow)?;
+        Ok(())
+    }
+
+    /// Moves `amount` reward tokens from the authority's token account into the poll's reward
+    /// vault: a `reward_mint` token account owned by the PDA `[b"rewards", poll]` (e.g. its
+    /// associated token account, created off-chain).
+    pub fn fund_rewards(ctx: Context<FundRewards>, amount: u64) -> Result<()> {
+        require_not_paused(&ctx.accounts.protocol_config)?; // Protocol-wide kill switch.
+        let poll = &ctx.accounts.poll;
+        let mint = poll.reward_mint.ok_or(VotingError::NoRewards)?; // Reward polls only.
+        require!(amount > 0, VotingError::InvalidAmount);
+        let vault = ctx.accounts.reward_vault.to_account_info();
+        require_vault(b"rewards", &poll.key(), mint, &vault, ctx.program_id)?; // Vault of this poll.
+        token::transfer(
+            &ctx.accounts.token_program,
+            &ctx.accounts.source,
+            &vault,
+            &ctx.accounts.authority,
+            amount,
+            &[],
+        )?; // Authority signs for its own account; the token program checks the mint.
+        let poll = &mut ctx.accounts.poll;
+        poll.rewards_outstanding = poll.rewards_outstanding.checked_add(amount).ok_or(VotingError::Overflow)?; // Owed until claimed or withdrawn.
+        Ok(())
+    }
+
+    /// Pays `reward_amount` tokens from the reward vault to a voter's associated token account,
+    /// once per Voter record (`reward_claimed`). Anyone may call it once the poll has ended, so
+    /// the ballot can no longer be retracted and recast; commit-reveal ballots must have been
+    /// revealed. Fails with `RewardsExhausted`, claiming nothing, when the vault is short.
+    pub fn claim_reward(ctx: Context<ClaimReward>) -> Result<()> {
+        let clock = Clock::get()?; // Read current cluster time.
+        let poll = &mut ctx.accounts.poll;
+        let mint = poll.reward_mint.ok_or(VotingError::NoRewards)?; // Reward polls only.
+        let voter = &mut ctx.accounts.voter;
+        let poll_key = poll.key();
+        let vault = ctx.accounts.reward_vault.to_account_info();
+        let (holding, bump) = require_vault(b"rewards", &poll_key, mint, &vault, ctx.program_id)?;
+        check_claim(poll, voter, holding.amount, clock.unix_timestamp)?; // Once, after the close, while funded.
+        let ata = token::associated_token_address(&voter.wallet, &mint, ctx.accounts.token_program.key);
+        require_keys_eq!(ctx.accounts.destination.key(), ata, VotingError::InvalidTokenAccount); // Voter's ATA.
+
+        token::transfer(
+            &ctx.accounts.token_program,
+            &vault,
+            &ctx.accounts.destination,
+            &ctx.accounts.rewards_authority,
+            poll.reward_amount,
+            &[&[b"rewards", poll_key.as_ref(), &[bump]]],
+        )?; // Rewards PDA signs for the vault.
+        voter.reward_claimed = true;
+        poll.rewards_outstanding = poll.rewards_outstanding.saturating_sub(poll.reward_amount); // Paid out.
+        Ok(())
+    }
+
+    /// Returns unclaimed reward tokens to a token account of the authority once the poll is
+    /// finalized (authority only). Later claims fail with `RewardsExhausted`.
+    pub fn withdraw_rewards(ctx: Context<WithdrawRewards>, amount: u64) -> Result<()> {
+        let poll = &mut ctx.accounts.poll;
+        let mint = poll.reward_mint.ok_or(VotingError::NoRewards)?; // Reward polls only.
+        require!(poll.finalized || poll.cancelled, VotingError::NotFinalized); // Claim window over.
+        let poll_key = poll.key();
+        let vault = ctx.accounts.reward_vault.to_account_info();
+        let (_, bump) = require_vault(b"rewards", &poll_key, mint, &vault, ctx.program_id)?;
+        token::transfer(
+            &ctx.accounts.token_program,
+            &vault,
+            &ctx.accounts.destination,
+            &ctx.accounts.rewards_authority,
+            amount,
+            &[&[b"rewards", poll_key.as_ref(), &[bump]]],
+        )?; // The token program checks the balance and mint.
+        poll.rewards_outstanding = poll.rewards_outstanding.saturating_sub(amount); // Withdrawing everything zeroes it.
+        Ok(())
+    }
+}
+
+/// Turns approval `selections` into one 0/1 flag per candidate: non-empty, unique, in range.
+fn approval_ballot(selections: &[u8], candidates: usize) -> Result<Vec<u16>> {
+    require!(!selections.is_empty(), VotingError::EmptyBallot);
+    let mut flags = vec![0u16; candidates];
+    for &sel in selections {
+        let idx = sel as usize;
+        require!(idx < candidates, VotingError::BadCandidate); // Validate index in range.
+        require!(flags[idx] == 0, VotingError::DuplicateChoice); // Each candidate once.
+        flags[idx] = 1;
+    }
+    Ok(flags)
+}
+
+/// Turns `scores` into points per candidate: one per candidate (`BadBallotLength`), each
+/// 0..=`MAX_SCORE` (`ScoreOutOfRange`).
+fn score_ballot(scores: &[u8], candidates: usize) -> Result<Vec<u16>> {
+    require!(scores.len() == candidates, VotingError::BadBallotLength); // One score per candidate.
+    require!(scores.iter().all(|&v| v <= MAX_SCORE), VotingError::ScoreOutOfRange);
+    Ok(scores.iter().map(|&v| v as u16).collect())
+}
+
+/// Checks a cumulative `allocation`: one entry per candidate (`BadBallotLength`), at most
+/// `budget` points (`OverBudget`), exactly `budget` unless `allow_underspend`
+/// (`BudgetNotSpent`), and not empty (`EmptyBallot`).
+fn cumulative_ballot(allocation: &[u16], candidates: usize, budget: u16, allow_underspend: bool) -> Result<()> {
+    require!(allocation.len() == candidates, VotingError::BadBallotLength); // One entry per candidate.
+    let spent: u32 = allocation.iter().map(|&v| v as u32).sum(); // Can't overflow for 8 entries.
+    require!(spent <= budget as u32, VotingError::OverBudget);
+    require!(spent == budget as u32 || allow_underspend, VotingError::BudgetNotSpent);
+    require!(spent > 0, VotingError::EmptyBallot); // Some points must go somewhere.
+    Ok(())
+}
+
+/// Checks a split allocation: one share per candidate (`BadBallotLength`) summing to exactly
+/// 10_000 basis points (`BadAllocation`).
+fn split_ballot(allocation_bps: &[u16], candidates: usize) -> Result<()> {
+    require!(allocation_bps.len() == candidates, VotingError::BadBallotLength); // One share per candidate.
+    let total: u32 = allocation_bps.iter().map(|&v| v as u32).sum(); // Can't overflow for 8 entries.
+    require!(total == 10_000, VotingError::BadAllocation); // Exactly 100%.
+    Ok(())
+}
+
+/// Accounts and bumps `init_poll` and `init_referendum` share (their `space` differs).
+struct NewPoll<'a, 'info> {
+    counter: &'a mut Account<'info, PollCounter>,      // Supplies the seed for the new poll.
+    poll: &'a mut Account<'info, Poll>,                // Freshly created poll account.
+    ranked_tally: Option<&'a UncheckedAccount<'info>>, // Created for ranked-choice polls.
+    treasury: Option<&'a UncheckedAccount<'info>>,     // Funded for polls with a vote fee.
+    registry: Option<&'a mut Account<'info, Registry>>, // Assigns `poll_id` when passed.
+    authority_index: Option<&'a mut Account<'info, AuthorityIndex>>, // Lists the poll when passed.
+    protocol_config: &'a UncheckedAccount<'info>,      // `[b"config"]`, charged when it exists.
+    fee_recipient: Option<&'a UncheckedAccount<'info>>, // Receives the protocol fee.
+    creator_credential: Option<&'a UncheckedAccount<'info>>, // Authority's `CreatorCredential`, if any.
+    creator_stats: Option<&'a mut Account<'info, CreatorStats>>, // Counts creations when rate-limited.
+    authority: &'a Signer<'info>,                      // Pays for everything.
+    system_program: &'a Program<'info, System>,        // For account creation.
+    remaining_accounts: &'a [AccountInfo<'info>],      // Tally PDAs of sharded polls.
+    program_id: &'a Pubkey,                            // Owner of the side accounts.
+    counter_bump: u8,                                  // Bump of `counter`.
+    poll_bump: u8,                                     // Bump of `poll`.
+    ranked_bump: Option<u8>,                           // Bump of `ranked_tally`, if passed.
+    treasury_bump: Option<u8>,                         // Bump of `treasury`, if passed.
+    creator_stats_bump: Option<u8>,                    // Bump of `creator_stats`, if passed.
+    fee_vault: FeeVaultAccounts<'a, 'info>,            // Token-fee polls only.
+}
+
+/// Accounts `create_poll` opens a token-fee poll's vault with; all `None` for other polls.
+struct FeeVaultAccounts<'a, 'info> {
+    vault: Option<&'a UncheckedAccount<'info>>,           // ATA of `vault_authority` for `fee_mint`.
+    vault_authority: Option<&'a UncheckedAccount<'info>>, // `[b"fees", poll]`.
+    mint: Option<&'a UncheckedAccount<'info>>,            // `options.fee_mint`.
+    token_program: Option<&'a UncheckedAccount<'info>>,   // Owner of `mint`.
+    associated_token_program: Option<&'a UncheckedAccount<'info>>, // Creates `vault`.
+}
+
+/// Validates and writes a new poll (see `init_poll`), creating its side accounts.
+fn create_poll(
+    mut a: NewPoll,
+    title: String,
+    description: String,
+    candidates: Vec<String>,
+    start_ts: i64,
+    end_ts: i64,
+    options: PollOptions,
+) -> Result<()> {
+    let config = charge_protocol_fee(
+        a.protocol_config,
+        a.fee_recipient,
+        a.creator_credential,
+        a.authority,
+        a.system_program,
+    )?;
+    count_creation(
+        config.as_ref(),
+        a.creator_stats.as_deref_mut().map(|s| &mut **s),
+        a.authority.key(),
+        a.creator_stats_bump,
+        Clock::get()?.unix_timestamp,
+    )?; // Per-wallet rate limit.
+    let registration = options.registration_end_ts > 0; // Candidates may sign up themselves.
+    require!(
+        candidates.len() >= 2 || registration,
+        VotingError::NotEnoughCandidates
+    ); // Need at least two choices (by the time voting opens, with registration).
+    require!(candidates.len() <= 8, VotingError::TooManyCandidates); // Cap list size for account space.
+    validate_title(&title)?; // Length and character rules (not a seed, so >32 bytes is fine).
+    require!(description.len() <= 256, VotingError::DescriptionTooLong); // Description length bound.
+    validate_schedule(start_ts, end_ts, Clock::get()?.unix_timestamp)?; // Sane window.
+    #[cfg(not(feature = "nft-gate"))]
+    require!(options.gate_collection.is_none(), VotingError::FeatureNotEnabled); // Built without NFT gating.
+    require!(
+        (1..=MAX_VOTES_PER_WALLET).contains(&options.votes_per_wallet),
+        VotingError::InvalidVotesPerWallet
+    ); // 1 = classic single ballot.
+    require!(
+        options.ballot_type == BallotType::Plurality || options.votes_per_wallet == 1,
+        VotingError::InvalidVotesPerWallet
+    ); // Several ballots per wallet only make sense for plurality.
+    if let BallotType::Cumulative { budget } = options.ballot_type {
+        require!(budget > 0, VotingError::InvalidBudget); // Something to distribute.
+    }
+    require!(
+        (candidates.len()..=8).contains(&(options.max_candidates as usize)),
+        VotingError::InvalidMaxCandidates
+    ); // Room for the initial list, within the account cap.
+    let max_candidates = if registration { options.max_candidates as usize } else { candidates.len() }; // List size reachable before voting.
+    require!(
+        options.num_winners >= 1 && (options.num_winners as usize) < max_candidates,
+        VotingError::InvalidNumWinners
+    ); // Someone must lose.
+    if registration {
+        require!(options.registration_end_ts < end_ts, VotingError::BadSchedule); // Voting follows it.
+        require!(options.kind == PollKind::Election, VotingError::ReferendumOptions); // Yes/No stay fixed.
+    }
+    require!(
+        options.num_winners == 1
+            || (options.ballot_type != BallotType::RankedChoice && options.kind == PollKind::Election),
+        VotingError::InvalidNumWinners
+    ); // IRV and referendums decide a single outcome.
+    require!(
+        !options.allow_write_ins
+            || (options.ballot_type == BallotType::Plurality && options.kind == PollKind::Election),
+        VotingError::WrongBallotType
+    ); // Write-ins are single-candidate ballots in an open field.
+    if options.certifier.is_some() {
+        require!(
+            options.certification_deadline_ts > end_ts.max(options.reveal_deadline_ts),
+            VotingError::BadSchedule
+        ); // The certifier gets time after results are in.
+    }
+    if options.commit_reveal {
+        require!(options.reveal_deadline_ts > end_ts, VotingError::BadSchedule); // Reveals follow voting.
+        require!(
+            options.ballot_type == BallotType::Plurality
+                && options.votes_per_wallet == 1
+                && !options.allow_downvotes
+                && !options.allow_write_ins,
+            VotingError::WrongBallotType
+        ); // One hidden candidate choice per wallet.
+    }
+    require!(
+        !options.hide_tallies || (options.ballot_type == BallotType::Plurality && !options.commit_reveal),
+        VotingError::WrongBallotType
+    ); // Tabulation sums plurality choices; commit-reveal hides them anyway.
+    if options.allow_downvotes {
+        require!(options.ballot_type == BallotType::Plurality, VotingError::WrongBallotType); // Single-candidate ballots.
+        require!(options.votes_per_wallet == 1, VotingError::InvalidVotesPerWallet); // One direction per wallet.
+    }
+    require!(options.majority_bps <= 10_000, VotingError::InvalidThreshold); // 0 disables runoffs.
+    if options.sharded {
+        require!(
+            options.ballot_type == BallotType::Plurality
+                && options.votes_per_wallet == 1
+                && !options.commit_reveal
+                && !options.hide_tallies
+                && !options.allow_downvotes
+                && !options.allow_write_ins
+                && !options.allow_abstain
+                && options.tie_break != TieBreak::EarliestLead
+                && options.registration_end_ts == 0,
+            VotingError::ShardedOptions
+        ); // A ballot touches one candidate's Tally and nothing else.
+        require!(
+            !options.allowlist_required
+                && options.allowlist_root.is_none()
+                && options.gate_mint.is_none()
+                && options.gate_collection.is_none()
+                && options.min_lamports == 0
+                && options.registrar.is_none()
+                && options.weighting == VoteWeighting::Equal
+                && options.max_voters == 0,
+            VotingError::ShardedOptions
+        ); // Open, equal-weight electorate: `vote_sharded` only checks bans.
+        require!(
+            (1..=MAX_TALLY_SHARDS).contains(&options.tally_shards),
+            VotingError::InvalidShardCount
+        ); // A handful of counters per candidate.
+    } else {
+        require!(options.tally_shards == 1, VotingError::InvalidShardCount); // Only sharded polls split counters.
+    }
+    if options.receiptless_voters > 0 {
+        require!(
+            options.receiptless_voters <= MAX_RECEIPTLESS_VOTERS && options.allowlist_root.is_some(),
+            VotingError::ReceiptlessOptions
+        ); // Leaf indices of a bounded allowlist tree.
+        require!(
+            options.ballot_type == BallotType::Plurality
+                && options.votes_per_wallet == 1
+                && options.weighting == VoteWeighting::Equal
+                && !options.commit_reveal
+                && !options.hide_tallies
+                && !options.sharded
+                && !options.allowlist_required
+                && options.gate_mint.is_none()
+                && options.gate_collection.is_none()
+                && options.min_lamports == 0
+                && options.registrar.is_none(),
+            VotingError::ReceiptlessOptions
+        ); // `vote_light` only checks the proof and bans, and keeps nothing to undo.
+    }
+    require!(
+        options.tie_break == TieBreak::None
+            || (options.ballot_type != BallotType::RankedChoice && options.num_winners == 1),
+        VotingError::WrongBallotType
+    ); // Breaks a tie for the single first place; IRV ties stay ties.
+    require!(
+        options.tie_break != TieBreak::EarliestLead
+            || (options.ballot_type == BallotType::Plurality && !options.hide_tallies),
+        VotingError::WrongBallotType
+    ); // Needs per-ballot timing; tabulation order is arbitrary.
+    if let PollKind::Referendum { pass_bps } = options.kind {
+        require!((1..=10_000).contains(&pass_bps), VotingError::InvalidThreshold); // A share of the votes.
+        require!(
+            candidates == REFERENDUM_OPTIONS && options.ballot_type == BallotType::Plurality,
+            VotingError::ReferendumOptions
+        ); // Index 0 = Yes, 1 = No, one choice per ballot.
+    }
+    for (i, name) in candidates.iter().enumerate() {
+        validate_candidate_name(name)?; // Non-empty, length bound.
+        require_new_candidate(candidates[..i].iter().map(String::as_str), name)?; // Distinct names.
+    }
+    require!(
+        Poll::space(&title, &description, &candidates, options.reserved_candidates(candidates.len()), options.receiptless_voters)
+            <= Poll::INIT_SPACE,
+        VotingError::PollTooLarge
+    ); // The account was sized from these arguments.
+
+    let counter = &mut a.counter; // Per-authority poll counter (created on first use).
+    let seq = counter.count; // Sequence number used in this poll's seeds.
+    counter.authority = a.authority.key(); // Owner of the counter.
+    counter.bump = a.counter_bump; // Record bump used for PDA derivation.
+    counter.count = seq.checked_add(1).ok_or(VotingError::Overflow)?; // Next poll gets a fresh address.
+
+    let poll = &mut a.poll; // Mutable handle to the poll account being created.
+    poll.version = POLL_VERSION; // Current layout.
+    poll.seq = seq; // Lets clients re-derive the PDA.
+    poll.authority = a.authority.key(); // Store authority pubkey.
+    poll.title = title; // Save title string.
+    poll.description = description; // Save description string.
+    poll.candidates = candidates; // Save candidate list.
+    poll.votes = vec![0; poll.candidates.len()]; // Initialize vote counts to zero.
+    poll.downvotes = vec![0; poll.candidates.len()]; // Votes against, aligned with `votes`.
+    poll.last_increment_ts = vec![0; poll.candidates.len()]; // Also aligned with `votes`.
+    require!(
+        options.reward_mint.is_none() || options.reward_amount > 0,
+        VotingError::InvalidAmount
+    ); // A reward must pay something.
+    require!(
+        options.fee_mint.is_none() || options.vote_fee_lamports == 0,
+        VotingError::FeeConflict
+    ); // One fee currency per poll.
+    require!(
+        options.fee_mint.is_some() == (options.fee_amount > 0),
+        VotingError::InvalidAmount
+    ); // A token fee needs both its mint and an amount.
+    require!(
+        options.burn_mint.is_some() == (options.burn_amount > 0),
+        VotingError::InvalidAmount
+    ); // Burn-to-vote needs both its mint and an amount.
+    if options.candidate_wallets.is_empty() {
+        poll.candidate_wallets = vec![a.authority.key(); poll.candidates.len()]; // Seeded by the authority.
+    } else {
+        require!(
+            options.candidate_wallets.len() == poll.candidates.len(),
+            VotingError::CandidateWalletsMismatch
+        ); // One wallet per seeded candidate.
+        poll.candidate_wallets = options.candidate_wallets.clone(); // Prize recipients.
+    }
+    poll.approved = vec![true; poll.candidates.len()]; // Only self-registrations need approval.
+    poll.start_ts = start_ts; // Save start time.
+    poll.end_ts = end_ts; // Save end time.
+    poll.bump = a.poll_bump; // Record bump used for PDA derivation.
+    poll.apply_options(&options); // Gates, weighting, ballot type, ...
+    poll.fee_beneficiary = options.fee_beneficiary.unwrap_or(a.authority.key()); // Who gets the fees.
+    if let Some(registry) = a.registry.as_mut() {
+        poll.poll_id = Some(registry.register(poll.key())?); // Next sequential id.
+    }
+    if let Some(index) = a.authority_index.as_mut() {
+        require!(index.polls.len() < MAX_INDEXED_POLLS, VotingError::IndexFull);
+        grow_account(
+            &index.to_account_info(),
+            &a.authority.to_account_info(),
+            &a.system_program.to_account_info(),
+            8 + AuthorityIndex::space(index.polls.len() + 1),
+        )?; // One more entry; authority pays.
+        index.polls.push(a.poll.key());
+    }
+
+    if let Some(config) = config.filter(|c| c.creation_deposit_lamports > 0) {
+        // Locked in the poll on top of its rent until finalized or swept.
+        system_program::transfer(
+            CpiContext::new(
+                a.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: a.authority.to_account_info(),
+                    to: a.poll.to_account_info(),
+                },
+            ),
+            config.creation_deposit_lamports,
+        )?;
+        a.poll.creation_deposit = config.creation_deposit_lamports;
+        a.poll.deposit_min_ballots = config.deposit_min_ballots; // Terms fixed at creation.
+    }
+
+    if options.finalize_bounty_lamports > 0 {
+        // Park the bounty in the poll account on top of its rent.
+        system_program::transfer(
+            CpiContext::new(
+                a.system_program.to_account_info(),
+                system_program::Transfer {
+                    from: a.authority.to_account_info(),
+                    to: a.poll.to_account_info(),
+                },
+            ),
+            options.finalize_bounty_lamports,
+        )?;
+    }
+
+    if options.vote_fee_lamports > 0 || options.candidate_fee_lamports > 0 {
+        // A plain system account; fees below its rent-exempt minimum couldn't create it.
+        let treasury = a.treasury.ok_or(VotingError::AccountMismatch)?;
+        let top_up = Rent::get()?.minimum_balance(0).saturating_sub(treasury.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    a.system_program.to_account_info(),
+                    system_program::Transfer {
+                        from: a.authority.to_account_info(),
+                        to: treasury.to_account_info(),
+                    },
+                ),
+                top_up,
+            )?;
+        }
+        a.poll.treasury_bump = a.treasury_bump.ok_or(VotingError::AccountMismatch)?;
+    }
+
+    if let Some(mint) = options.fee_mint {
+        // Token fees collect in the ATA of the poll's `[b"fees", poll]` PDA.
+        let f = &a.fee_vault;
+        let (vault, owner, mint_info, token_program, ata_program) =
+            match (f.vault, f.vault_authority, f.mint, f.token_program, f.associated_token_program) {
+                (Some(v), Some(o), Some(m), Some(t), Some(p)) => (v, o, m, t, p),
+                _ => return err!(VotingError::FeeVaultRequired),
+            };
+        require_keys_eq!(mint_info.key(), mint, VotingError::AccountMismatch);
+        require!(
+            token::is_token_program(token_program.key) && mint_info.owner == token_program.key,
+            VotingError::InvalidTokenAccount
+        ); // The mint's own token program.
+        require_keys_eq!(
+            vault.key(),
+            token::associated_token_address(owner.key, &mint, token_program.key),
+            VotingError::AccountMismatch
+        );
+        token::create_associated_token_account(
+            ata_program,
+            &a.authority.to_account_info(),
+            vault,
+            owner,
+            mint_info,
+            &a.system_program.to_account_info(),
+            token_program,
+        )?; // Authority pays rent.
+        a.poll.fee_vault = vault.key();
+    }
+
+    if options.ballot_type == BallotType::RankedChoice {
+        // Instant-runoff counters live in their own PDA (too big for the poll account).
+        let info = a.ranked_tally.as_ref().ok_or(VotingError::AccountMismatch)?;
+        let poll_key = a.poll.key();
+        let bump = a.ranked_bump.ok_or(VotingError::AccountMismatch)?;
+        create_pda_account(
+            &a.authority.to_account_info(),
+            &info.to_account_info(),
+            &a.system_program.to_account_info(),
+            8 + RankedTally::SIZE,
+            a.program_id,
+            &[b"ranked", poll_key.as_ref(), &[bump]],
+        )?; // Authority pays rent.
+        let tally = RankedTally { poll: poll_key, bump, counts: vec![0; RANKED_COUNTERS] };
+        tally.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?; // Discriminator + zeroed counters.
+    }
+
+    if options.sharded && options.tally_shards == 1 {
+        // One Tally PDA per candidate, passed in `remaining_accounts` in candidate order.
+        // With more shards per candidate, `create_tally_shards` makes them instead.
+        let poll_key = a.poll.key();
+        require!(
+            a.remaining_accounts.len() == a.poll.candidates.len(),
+            VotingError::AccountMismatch
+        );
+        for (idx, info) in a.remaining_accounts.iter().enumerate() {
+            let seed_idx = [idx as u8];
+            let (expected, bump) =
+                Pubkey::find_program_address(&[b"tally", poll_key.as_ref(), &seed_idx], a.program_id);
+            require_keys_eq!(info.key(), expected, VotingError::AccountMismatch); // `[b"tally", poll, idx]`.
+            create_pda_account(
+                &a.authority.to_account_info(),
+                info,
+                &a.system_program.to_account_info(),
+                8 + Tally::SIZE,
+                a.program_id,
+                &[b"tally", poll_key.as_ref(), &seed_idx, &[bump]],
+            )?; // Authority pays rent.
+            let tally = Tally { poll: poll_key, candidate_idx: idx as u8, bump, ..Tally::default() };
+            tally.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?; // Discriminator + zero count.
+        }
+        a.poll.shard_mask = ((1u16 << a.remaining_accounts.len()) - 1) as u8; // All ready.
+    }
+
+    let poll = &a.poll; // Re-borrow after the CPI.
+    emit!(PollCreated {
+        poll: poll.key(),
+        authority: poll.authority,
+        title: poll.title.clone(),
+        description: poll.description.clone(),
+        start_ts: poll.start_ts,
+        end_ts: poll.end_ts,
+        candidate_count: poll.candidates.len() as u8,
+        poll_id: poll.poll_id,
+    });
+    Ok(())
+}
+
+/// Candidate names must be non-blank, at most `MAX_NAME_BYTES` bytes (sized into
+/// `Poll::INIT_SPACE`) and `MAX_NAME_CHARS` characters, without control or invisible characters.
+pub fn validate_candidate_name(name: &str) -> Result<()> {
+    require!(!name.trim().is_empty(), VotingError::EmptyCandidateName); // No empty or blank candidate names.
+    require!(
+        name.len() <= MAX_NAME_BYTES && name.chars().count() <= MAX_NAME_CHARS,
+        VotingError::CandidateNameTooLong
+    ); // Account space and display length.
+    require!(is_printable(name), VotingError::InvalidCharacters);
+    Ok(())
+}
+
+/// Checks a new voting window: start before end (`BadSchedule`), a start within
+/// `now - START_SKEW_SECS..=now + MAX_START_DELAY_SECS` (`StartInPast` / `StartTooFar`), end
+/// still in the future (`EndInPast`) and a length within `MIN_DURATION_SECS..=MAX_DURATION_SECS`.
+fn validate_schedule(start_ts: i64, end_ts: i64, now: i64) -> Result<()> {
+    require!(start_ts < end_ts, VotingError::BadSchedule); // Start must precede end.
+    require!(start_ts >= now.saturating_sub(START_SKEW_SECS), VotingError::StartInPast); // Clock drift only.
+    require!(start_ts <= now.saturating_add(MAX_START_DELAY_SECS), VotingError::StartTooFar); // No rent parked for years.
+    require!(end_ts > now, VotingError::EndInPast); // Not expired on arrival.
+    let duration = end_ts.checked_sub(start_ts).ok_or(VotingError::Overflow)?;
+    require!(duration >= MIN_DURATION_SECS, VotingError::DurationTooShort);
+    require!(duration <= MAX_DURATION_SECS, VotingError::DurationTooLong);
+    Ok(())
+}
+
+/// Titles follow the same rules as names with `MAX_TITLE_BYTES` / `MAX_TITLE_CHARS`.
+pub fn validate_title(title: &str) -> Result<()> {
+    require!(!title.trim().is_empty(), VotingError::EmptyTitle); // Something to show.
+    require!(
+        title.len() <= MAX_TITLE_BYTES && title.chars().count() <= MAX_TITLE_CHARS,
+        VotingError::TitleTooLong
+    ); // Account space and display length.
+    require!(is_printable(title), VotingError::InvalidCharacters);
+    Ok(())
+}
+
+/// False if `text` has control characters (newlines, tabs, ...) or invisible formatting
+/// characters that hide or reorder text (zero-width spaces and joiners, bidi controls, BOM).
+fn is_printable(text: &str) -> bool {
+    !text.chars().any(|c| {
+        c.is_control()
+            || matches!(c, '\u{200B}'..='\u{200F}' | '\u{2028}'..='\u{202E}' | '\u{2060}'..='\u{206F}' | '\u{FEFF}')
+    })
+}
+
+/// True when two names denote the same candidate: equal once surrounding whitespace is
+/// trimmed, ignoring ASCII case ("Alice" = " alice"). Every entry point uses this.
+pub fn same_candidate(a: &str, b: &str) -> bool {
+    a.trim().eq_ignore_ascii_case(b.trim())
+}
+
+/// Fails with `DuplicateCandidate` if `name` is `same_candidate` as any of `existing`.
+fn require_new_candidate<'a>(mut existing: impl Iterator<Item = &'a str>, name: &str) -> Result<()> {
+    require!(!existing.any(|c| same_candidate(c, name)), VotingError::DuplicateCandidate);
+    Ok(())
+}
+
+/// Text of a zero-padded fixed-size name (`PollLarge::names`).
+fn padded_str(slot: &[u8; 32]) -> &str {
+    let len = slot.iter().position(|b| *b == 0).unwrap_or(slot.len()); // Up to the padding.
+    std::str::from_utf8(&slot[..len]).unwrap_or_default() // Written from a String.
+}
+
+/// Shared by `finalize_poll`, `crank_finalize` and `tally_irv`: validates the poll can be
+/// finalized, stores the winner, and emits `PollFinalized`. Ranked-choice polls need their
+/// `RankedTally` counters (`ranked`), other polls are decided by `votes`. `Random` tie-breaks
+/// need the `SlotHashes` sysvar (`slot_hashes`).
+fn finalize_results(
+    poll: &mut Account<Poll>,
+    ranked: Option<&[u64]>,
+    slot_hashes: Option<&AccountInfo>,
+    now: i64,
+) -> Result<()> {
+    poll.require_consistent()?; // Tallies line up with candidates.
+    require!(!poll.cancelled, VotingError::PollCancelled); // Nothing to finalize.
+    require!(!poll.finalized, VotingError::AlreadyFinalized); // Finalize only once.
+    require!(!poll.paused, VotingError::PollPaused); // Resume (and maybe extend) first.
+    require!(!poll.sharded || poll.collected, VotingError::TalliesNotCollected); // Run `collect_tallies`.
+    if poll.commit_reveal {
+        require!(now > poll.reveal_deadline_ts, VotingError::PollStillOpen); // Reveals must be in.
+    } else if poll.hide_tallies {
+        require!(now > poll.end_ts, VotingError::PollStillOpen); // No early finalize on a full cap.
+        require!(poll.tabulated == poll.voter_count, VotingError::TabulationIncomplete); // Every record counted.
+    } else {
+        require!(now > poll.end_ts || poll.is_full(), VotingError::PollStillOpen); // Window over or cap hit.
+    }
+
+    let (winner_idx, is_tie, totals) = if poll.ballot_type == BallotType::RankedChoice {
+        let counts = ranked.ok_or(VotingError::UseTallyIrv)?; // Needs the runoff counters.
+        instant_runoff(counts, poll.candidates.len())
+    } else if poll.allow_downvotes {
+        let (winner_idx, is_tie) = compute_net_winner(&poll.votes, &poll.downvotes); // Best net score.
+        (winner_idx, is_tie, poll.votes.clone())
+    } else {
+        let (winner_idx, is_tie) = compute_winner(&poll.votes); // Scan tallies for the leader.
+        (winner_idx, is_tie, poll.votes.clone())
+    };
+    let (mut winner_idx, mut is_tie) = (winner_idx, is_tie);
+    if is_tie && poll.tie_break != TieBreak::None {
+        let recent_hash = slot_hashes.map(latest_slot_hash).transpose()?; // Entropy for `Random`.
+        winner_idx = break_tie(poll, poll.key(), recent_hash.as_ref())?; // Policy pick among the leaders.
+        is_tie = winner_idx.is_none(); // EarliestLead can still tie within the same second.
+    }
+    poll.winner_idx = winner_idx; // Store winner (None on tie / no votes).
+    poll.is_tie = is_tie; // Flag ties explicitly.
+    let (winners, seat_tie) = if poll.ballot_type == BallotType::RankedChoice {
+        (winner_idx.into_iter().collect(), is_tie) // Single seat (enforced at init).
+    } else if poll.tie_break != TieBreak::None && winner_idx.is_some() {
+        (winner_idx.into_iter().collect(), false) // Single seat (enforced at init), tie broken.
+    } else if !poll.has_votes() {
+        (Vec::new(), false) // Nobody voted: no seats filled.
+    } else {
+        let scores: Vec<i128> = poll
+            .votes
+            .iter()
+            .zip(&poll.downvotes)
+            .map(|(&up, &down)| up as i128 - down as i128)
+            .collect(); // Net score; downvotes are all zero unless enabled.
+        top_candidates(&scores, poll.num_winners as usize)
+    };
+    poll.winners = winners.clone(); // Seated candidates, best first.
+    poll.seat_tie = seat_tie; // Tie at the last seat: `winners` holds every tied candidate.
+    if let PollKind::Referendum { pass_bps } = poll.kind {
+        poll.passed = referendum_passed(poll.votes[0], poll.votes[1], pass_bps); // Index 0 = Yes.
+    }
+    poll.quorum_met = poll.meets_quorum(); // Winner fields stay, flagged invalid if not.
+    poll.finalized = true; // Freeze results.
+    poll.finalized_at = now; // Opens the dispute window.
+    poll.results_hash = poll.outcome_hash(&poll.key());
+    if poll.voter_count >= poll.deposit_min_ballots {
+        poll.creation_deposit = 0; // Released: goes back to the authority with the rent at close.
+    }
+
+    emit!(PollFinalized {
+        poll: poll.key(),
+        winner_idx,
+        is_tie,
+        totals,
+        ballot_type: poll.ballot_type,
+        passed: poll.passed,
+        downvotes: poll.downvotes.clone(),
+        winners,
+        seat_tie,
+        voter_count: poll.voter_count,
+        turnout: poll.turnout,
+        quorum_met: poll.quorum_met,
+        tie_break: poll.tie_break,
+        results_hash: poll.results_hash,
+    });
+    Ok(())
+}
+
+/// Picks one of the candidates tied for first under the poll's `tie_break` policy.
+/// `EarliestLead`: the one whose count last changed earliest, i.e. that reached its final
+/// count first (`None` if that's tied too). `Random`: `sha256(poll || recent_hash)` modulo
+/// the number of leaders, where `recent_hash` is the latest `SlotHashes` entry
+/// (`SlotHashesRequired` without it).
+fn break_tie(poll: &Poll, key: Pubkey, recent_hash: Option<&[u8; 32]>) -> Result<Option<u8>> {
+    let scores: Vec<i128> = poll
+        .votes
+        .iter()
+        .zip(&poll.downvotes)
+        .map(|(&up, &down)| up as i128 - down as i128)
+        .collect(); // Net score; downvotes are all zero unless enabled.
+    let Some(max) = scores.iter().copied().max() else {
+        return Ok(None); // No candidates.
+    };
+    let leaders: Vec<usize> = (0..scores.len()).filter(|&c| scores[c] == max).collect();
+    match poll.tie_break {
+        TieBreak::None => Ok(None),
+        TieBreak::EarliestLead => {
+            let first = leaders.iter().map(|&c| poll.last_increment_ts[c]).min(); // Earliest settled count.
+            let mut earliest = leaders.iter().filter(|&&c| Some(poll.last_increment_ts[c]) == first);
+            match (earliest.next(), earliest.next()) {
+                (Some(&c), None) => Ok(Some(c as u8)),
+                _ => Ok(None), // Same second: still a tie.
+            }
+        }
+        TieBreak::Random => {
+            let recent_hash = recent_hash.ok_or(VotingError::SlotHashesRequired)?;
+            let seed = anchor_lang::solana_program::hash::hashv(&[key.as_ref(), recent_hash]).to_bytes();
+            let r = u64::from_le_bytes(seed[..8].try_into().unwrap());
+            Ok(Some(leaders[(r % leaders.len() as u64) as usize] as u8))
+        }
+    }
+}
+
+/// Hash of the most recent slot in the `SlotHashes` sysvar: entry count (u64), then
+/// `(slot: u64, hash: [u8; 32])` pairs, newest first.
+fn latest_slot_hash(info: &AccountInfo) -> Result<[u8; 32]> {
+    require_keys_eq!(*info.key, slot_hashes::ID, VotingError::SlotHashesRequired);
+    let data = info.try_borrow_data()?;
+    require!(data.len() >= 48, VotingError::SlotHashesRequired); // At least one entry.
+    Ok(data[16..48].try_into().unwrap())
+}
+
+/// The `seats` best candidates by score, best first (equal scores in candidate order). When the
+/// last seat is tied, every candidate tied for it is included, so the list is longer than
+/// `seats`, and the flag is set.
+fn top_candidates(scores: &[i128], seats: usize) -> (Vec<u8>, bool) {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by_key(|&c| std::cmp::Reverse(scores[c])); // Stable: ties keep index order.
+    let cutoff = scores[order[seats - 1]]; // Score of the last seat.
+    let tie = order.get(seats).is_some_and(|&next| scores[next] == cutoff); // Next one equals it.
+    let winners = order
+        .into_iter()
+        .filter(|&c| scores[c] >= cutoff)
+        .map(|c| c as u8)
+        .collect();
+    (winners, tie)
+}
+
+/// Moves the `finalize_bounty` deposited at init from the poll to whoever finalized it.
+fn pay_finalize_bounty(poll: &mut Account<Poll>, cranker: &Signer) -> Result<()> {
+    let bounty = poll.finalize_bounty; // Lamports set aside at init.
+    if bounty > 0 {
+        poll.finalize_bounty = 0; // Pay out only once.
+        let poll_info = poll.to_account_info(); // Program-owned: debit directly.
+        let cranker_info = cranker.to_account_info();
+        **poll_info.try_borrow_mut_lamports()? = poll_info
+            .lamports()
+            .checked_sub(bounty)
+            .ok_or(VotingError::Overflow)?;
+        **cranker_info.try_borrow_mut_lamports()? = cranker_info
+            .lamports()
+            .checked_add(bounty)
+            .ok_or(VotingError::Overflow)?;
+    }
+    Ok(())
+}
+
+/// Index in `RankedTally::counts` of candidate `c`'s counter for the subset `set` (bitmask of
+/// standing candidates, `c` a member): subsets come in increasing order, one counter per member.
+fn ranked_slot(set: u16, c: usize) -> usize {
+    let before: usize = (0..set).map(|t| t.count_ones() as usize).sum(); // Counters of smaller subsets.
+    before + (set & ((1 << c) - 1)).count_ones() as usize // Members below `c` in this subset.
+}
+
+/// Adds (or with `add = false` removes) a ranked ballot of `weight` to every subset's counters:
+/// the ballot counts for its highest-ranked member of each subset, and for none if it ranks
+/// no member (exhausted).
+fn apply_ranking(
+    counts: &mut [u64],
+    ranking: &[u16],
+    candidates: usize,
+    weight: u64,
+    add: bool,
+) -> Result<()> {
+    let all: u16 = (1 << candidates) - 1; // Subsets of the poll's candidates only.
+    let mut offset = 0; // Counters of all smaller subsets.
+    for set in 0u16..256 {
+        if set & !all == 0 {
+            if let Some(&top) = ranking.iter().find(|&&c| set & (1 << c) != 0) {
+                let slot = offset + (set & ((1 << top) - 1)).count_ones() as usize;
+                counts[slot] = if add {
+                    counts[slot].checked_add(weight)
+                } else {
+                    counts[slot].checked_sub(weight)
+                }
+                .ok_or(VotingError::Overflow)?;
+            }
+        }
+        offset += set.count_ones() as usize;
+    }
+    Ok(())
+}
+
+/// Runs instant-runoff rounds over `RankedTally` counters; returns (winner, is_tie, last
+/// round counts per candidate). No ballots means no winner and no tie.
+fn instant_runoff(counts: &[u64], candidates: usize) -> (Option<u8>, bool, Vec<u64>) {
+    let mut standing: u16 = (1 << candidates) - 1; // Everyone starts in the race.
+    loop {
+        let round: Vec<u64> = (0..candidates)
+            .map(|c| if standing & (1 << c) != 0 { counts[ranked_slot(standing, c)] } else { 0 })
+            .collect(); // Ballots ranking each standing candidate highest.
+        let total: u128 = round.iter().map(|&v| v as u128).sum(); // Non-exhausted ballots.
+        if total == 0 {
+            return (None, false, round); // Nobody voted.
+        }
+        let (leader, &top) = round.iter().enumerate().max_by_key(|(_, v)| **v).unwrap();
+        if top as u128 * 2 > total {
+            return (Some(leader as u8), false, round); // Strict majority.
+        }
+        let lowest = (0..candidates)
+            .filter(|&c| standing & (1 << c) != 0)
+            .map(|c| round[c])
+            .min()
+            .unwrap(); // Fewest among those standing.
+        let next = (0..candidates)
+            .filter(|&c| round[c] == lowest)
+            .fold(standing, |set, c| set & !(1 << c)); // Eliminate everyone tied for last.
+        if next == 0 {
+            return (None, true, round); // All remaining candidates tied.
+        }
+        standing = next;
+    }
+}
+
+/// Counter shard a wallet's ballots land in on a sharded poll: `wallet[0] % shards`.
+pub fn shard_for(wallet: &Pubkey, shards: u8) -> u8 {
+    wallet.to_bytes()[0] % shards.max(1)
+}
+
+/// Next `ballot_hash` of a receiptless poll: `sha256(prev || wallet || candidate_idx)`, starting
+/// from all zeros. Replaying the poll's `VoteCast` events must reproduce the stored value.
+pub fn ballot_chain_hash(prev: &[u8; 32], wallet: &Pubkey, candidate_idx: u8) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[prev, wallet.as_ref(), &[candidate_idx]]).to_bytes()
+}
+
+/// Commitment stored by `commit_vote`: `sha256(candidate_idx || salt || wallet)`. Binding the
+/// wallet stops anyone from copying another voter's commitment.
+pub fn vote_commitment(candidate_idx: u8, salt: &[u8; 32], wallet: &Pubkey) -> [u8; 32] {
+    anchor_lang::solana_program::hash::hashv(&[&[candidate_idx], salt, wallet.as_ref()]).to_bytes()
+}
+
+/// Commitment to a finalized outcome, stored as `results_hash`: sha256 over `poll`, then the
+/// candidate count (u32 LE), each name as u32 LE byte length + UTF-8 bytes, each of `votes`
+/// as u64 LE, `voter_count` as u32 LE and `end_ts` as i64 LE. Light clients recompute it
+/// from the fetched Poll account.
+pub fn results_hash(
+    poll: &Pubkey,
+    candidates: &[String],
+    votes: &[u64],
+    voter_count: u32,
+    end_ts: i64,
+) -> [u8; 32] {
+    let mut data = Vec::with_capacity(32 + 4 + candidates.len() * 44 + 12);
+    data.extend_from_slice(poll.as_ref());
+    data.extend_from_slice(&(candidates.len() as u32).to_le_bytes());
+    for name in candidates {
+        data.extend_from_slice(&(name.len() as u32).to_le_bytes()); // Borsh string.
+        data.extend_from_slice(name.as_bytes());
+    }
+    for count in votes {
+        data.extend_from_slice(&count.to_le_bytes());
+    }
+    data.extend_from_slice(&voter_count.to_le_bytes());
+    data.extend_from_slice(&end_ts.to_le_bytes());
+    anchor_lang::solana_program::hash::hash(&data).to_bytes()
+}
+
+/// Validates a ranking: non-empty, in range, no candidate twice.
+fn ranked_ballot(ranking: &[u8], candidates: usize) -> Result<Vec<u16>> {
+    require!(!ranking.is_empty(), VotingError::EmptyBallot);
+    let mut seen = 0u16; // Bitmask of candidates ranked so far.
+    for &c in ranking {
+        require!((c as usize) < candidates, VotingError::BadCandidate); // Validate index in range.
+        require!(seen & (1 << c) == 0, VotingError::DuplicateChoice); // Each candidate once.
+        seen |= 1 << c;
+    }
+    Ok(ranking.iter().map(|&c| c as u16).collect())
+}
+
+/// Token accounts a `Vote` ballot pays a `fee_mint` fee with.
+struct TokenFee<'a, 'info> {
+    source: Option<&'a UncheckedAccount<'info>>,        // Payer's associated token account.
+    vault: Option<&'a UncheckedAccount<'info>>,         // `poll.fee_vault` (address-checked).
+    token_program: Option<&'a UncheckedAccount<'info>>, // Owner of `fee_mint`.
+}
+
+/// Moves `poll.vote_fee` from `payer` to the poll's treasury PDA; free polls skip the
+/// transfer (and need no treasury account). Token-fee polls instead move `fee_amount` from
+/// the payer's ATA into `poll.fee_vault`; only `Vote` instructions pass `token_fee`, so the
+/// others fail with `TokenFeeUnsupported` on those polls, and with `BurnUnsupported` on
+/// burn-to-vote polls (only `Vote::admit` burns).
+fn pay_vote_fee<'info>(
+    poll: &Poll,
+    payer: &AccountInfo<'info>,
+    treasury: Option<&UncheckedAccount<'info>>,
+    system_program: &AccountInfo<'info>,
+    token_fee: Option<TokenFee<'_, 'info>>,
+) -> Result<()> {
+    require!(token_fee.is_some() || poll.burn_mint.is_none(), VotingError::BurnUnsupported);
+    if let Some(mint) = poll.fee_mint {
+        let accounts = token_fee.ok_or(VotingError::TokenFeeUnsupported)?;
+        let source = accounts.source.ok_or(VotingError::NoFeeTokenAccount)?;
+        let vault = accounts.vault.ok_or(VotingError::FeeVaultRequired)?;
+        let token_program = accounts.token_program.ok_or(VotingError::InvalidTokenAccount)?;
+        require_keys_eq!(
+            source.key(),
+            token::associated_token_address(payer.key, &mint, token_program.key),
+            VotingError::InvalidTokenAccount
+        ); // The payer's ATA, so the mint matches too.
+        require!(!source.data_is_empty(), VotingError::NoFeeTokenAccount); // ATA never created.
+        let holding = token::unpack_token_account(source)?;
+        require!(holding.amount >= poll.fee_amount, VotingError::InsufficientFeeTokens);
+        return token::transfer(token_program, source, vault, payer, poll.fee_amount, &[]); // Payer signs.
+    }
+    if poll.vote_fee == 0 {
+        return Ok(());
+    }
+    let treasury = treasury.ok_or(VotingError::TreasuryRequired)?; // Seeds checked by the context.
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            system_program::Transfer { from: payer.clone(), to: treasury.to_account_info() },
+        ),
+        poll.vote_fee,
+    )
+}
+
+/// Pays the protocol creation fee from `authority` when the `Config` PDA exists and charges
+/// one; clusters without a config (or with a zero fee) create polls for free. While the
+/// config restricts creators, `authority` also needs its `CreatorCredential`. Returns the
+/// config, if any, for the creation deposit.
+fn charge_protocol_fee<'info>(
+    protocol_config: &UncheckedAccount<'info>,
+    fee_recipient: Option<&UncheckedAccount<'info>>,
+    creator_credential: Option<&UncheckedAccount<'info>>,
+    authority: &Signer<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<Option<Config>> {
+    if protocol_config.owner != &crate::ID {
+        return Ok(None); // `init_config` never ran here.
+    }
+    let config = Config::try_deserialize(&mut &protocol_config.try_borrow_data()?[..])?;
+    require!(!config.paused, VotingError::ProtocolPaused); // No new polls while paused.
+    if config.creators_restricted {
+        // Seeds tie the account to `[b"creator", authority]`; it only exists once added.
+        let vetted = creator_credential.is_some_and(|c| c.owner == &crate::ID && !c.data_is_empty());
+        require!(vetted, VotingError::CreatorNotAllowed);
+    }
+    if config.fee_lamports == 0 {
+        return Ok(Some(config));
+    }
+    let recipient = fee_recipient.ok_or(VotingError::FeeRecipientRequired)?;
+    require_keys_eq!(recipient.key(), config.fee_recipient, VotingError::AccountMismatch);
+    system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            system_program::Transfer {
+                from: authority.to_account_info(),
+                to: recipient.to_account_info(),
+            },
+        ),
+        config.fee_lamports,
+    )?;
+    Ok(Some(config))
+}
+
+/// Counts one poll creation against the protocol's per-wallet limit, when the config sets
+/// one (`set_creation_limit`). The window rolls over by itself: the first creation after
+/// `creation_window_secs` starts a new one. Fails with `CreationRateLimited` once the
+/// authority made `max_polls_per_window` polls in the current window.
+fn count_creation(
+    config: Option<&Config>,
+    stats: Option<&mut CreatorStats>,
+    authority: Pubkey,
+    bump: Option<u8>,
+    now: i64,
+) -> Result<()> {
+    let config = match config {
+        Some(c) if c.max_polls_per_window > 0 => c,
+        _ => return Ok(()), // No limit on this cluster.
+    };
+    let stats = stats.ok_or(VotingError::CreatorStatsRequired)?;
+    stats.authority = authority;
+    stats.bump = bump.ok_or(VotingError::CreatorStatsRequired)?;
+    let window_end = stats.window_start_ts.saturating_add(config.creation_window_secs as i64);
+    if stats.polls_created_in_window == 0 || now >= window_end {
+        stats.window_start_ts = now; // Fresh window from this creation on.
+        stats.polls_created_in_window = 0;
+    }
+    require!(
+        stats.polls_created_in_window < config.max_polls_per_window,
+        VotingError::CreationRateLimited
+    );
+    stats.polls_created_in_window += 1;
+    Ok(())
+}
+
+/// Fails with `ProtocolPaused` while the protocol admin has paused the program (see
+/// `set_paused`); clusters without a `Config` are never paused.
+fn require_not_paused(protocol_config: &UncheckedAccount) -> Result<()> {
+    if protocol_config.owner != &crate::ID {
+        return Ok(()); // `init_config` never ran here.
+    }
+    let config = Config::try_deserialize(&mut &protocol_config.try_borrow_data()?[..])?;
+    require!(!config.paused, VotingError::ProtocolPaused);
+    Ok(())
+}
+
+/// Moves `amount` lamports out of `poll`'s treasury PDA, signing with its seeds.
+fn treasury_transfer<'info>(
+    poll: &Account<'info, Poll>,
+    treasury: &UncheckedAccount<'info>,
+    to: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    let poll_key = poll.key();
+    system_program::transfer(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Transfer { from: treasury.to_account_info(), to: to.clone() },
+            &[&[b"treasury", poll_key.as_ref(), &[poll.treasury_bump]]],
+        ),
+        amount,
+    )
+}
+
+/// Reallocates `account` to `len` zero-filled bytes, with `payer` topping it up to the new
+/// rent-exempt minimum first.
+fn grow_account<'info>(
+    account: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    len: usize,
+) -> Result<()> {
+    let top_up = Rent::get()?.minimum_balance(len).saturating_sub(account.lamports());
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer.clone(), to: account.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    account.realloc(len, true)?;
+    Ok(())
+}
+
+/// Grows `poll` so one more candidate named `name` fits, with `payer` covering the extra
+/// rent. A no-op while room reserved at init is left, so only write-in polls (which reserve
+/// none, see `PollOptions::reserved_candidates`) actually grow.
+fn make_room_for_candidate<'info>(
+    poll: &Account<'info, Poll>,
+    name: &str,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let mut names: Vec<&str> = poll.candidates.iter().map(String::as_str).collect();
+    names.push(name);
+    let len = 8 + Poll::space(&poll.title, &poll.description, &names, names.len() as u8, poll.receiptless_voters);
+    let info = poll.to_account_info();
+    if len > info.data_len() {
+        grow_account(&info, payer, system_program, len)?;
+    }
+    Ok(())
+}
+
+/// Creates a program-owned PDA at `target` funded by `payer`, like Anchor's `init`: an address
+/// somebody pre-funded is topped up, allocated and assigned instead of failing.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let rent = Rent::get()?.minimum_balance(space); // Rent-exempt minimum.
+    let current = target.lamports();
+    if current == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                system_program::CreateAccount { from: payer.clone(), to: target.clone() },
+                &[seeds],
+            ),
+            rent,
+            space as u64,
+            owner,
+        );
+    }
+    let top_up = rent.saturating_sub(current); // Pre-funded: only pay the difference.
+    if top_up > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer { from: payer.clone(), to: target.clone() },
+            ),
+            top_up,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Allocate { account_to_allocate: target.clone() },
+            &[seeds],
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            system_program::Assign { account_to_assign: target.clone() },
+            &[seeds],
+        ),
+        owner,
+    )
+}
+
+/// Claims the `[b"identity", poll, identity_hash]` PDA for the attested wallet, so a second
+/// wallet attested to the same person can't vote. The same wallet may re-claim it (re-vote
+/// after `retract_vote`); the record is kept for the life of the poll.
+fn claim_identity<'info>(
+    attestation: &Attestation,
+    identity: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    program_id: &Pubkey,
+) -> Result<()> {
+    let (expected, bump) = Pubkey::find_program_address(
+        &[b"identity", attestation.poll.as_ref(), attestation.identity_hash.as_ref()],
+        program_id,
+    );
+    require_keys_eq!(identity.key(), expected, VotingError::AccountMismatch); // This identity's PDA.
+    if identity.owner == program_id {
+        let claim = IdentityClaim::try_deserialize(&mut &identity.try_borrow_data()?[..])?; // Claimed earlier.
+        require_keys_eq!(claim.wallet, wallet.key(), VotingError::IdentityAlreadyVoted);
+        return Ok(());
+    }
+    create_pda_account(
+        wallet,
+        identity,
+        system_program,
+        8 + IdentityClaim::SIZE, // Discriminator + size of IdentityClaim.
+        program_id,
+        &[b"identity", attestation.poll.as_ref(), attestation.identity_hash.as_ref(), &[bump]],
+    )?; // Voter pays, like for their Voter record.
+    let claim = IdentityClaim {
+        poll: attestation.poll,                   // Poll the claim is for.
+        identity_hash: attestation.identity_hash, // Claimed identity.
+        wallet: wallet.key(),                     // Wallet that used it.
+        bump,                                     // Save bump for PDA recreation.
+    };
+    claim.try_serialize(&mut &mut identity.try_borrow_mut_data()?[..])?; // Discriminator + fields.
+    Ok(())
+}
+
+/// A ballot about to be recorded.
+struct Ballot {
+    candidate_idx: u8,
+    weight: u64,
+    observed_balance: u64,
+    locked_amount: u64,
+    burned: u64,
+}
+
+/// Fails unless `claim_reward` may pay `voter` from a vault holding `vault_balance`: the poll
+/// has ended (`PollStillOpen`), the record hasn't been paid (`RewardAlreadyClaimed`), its
+/// ballot is counted (`NotRevealed`) and the vault covers `reward_amount` (`RewardsExhausted`).
+fn check_claim(poll: &Poll, voter: &Voter, vault_balance: u64, now: i64) -> Result<()> {
+    require!(now > poll.end_ts, VotingError::PollStillOpen); // Ballots are final.
+    require!(!voter.reward_claimed, VotingError::RewardAlreadyClaimed); // Once per voter.
+    require!(!poll.commit_reveal || voter.revealed, VotingError::NotRevealed); // Counted ballots only.
+    require!(vault_balance >= poll.reward_amount, VotingError::RewardsExhausted); // Vault ran dry.
+    Ok(())
+}
+
+/// Fails unless `reveal_vote` may count `voter`'s commitment for `candidate_idx` now: within
+/// the reveal phase, not revealed yet, for a valid candidate, and hashing to the stored
+/// commitment with `salt` and the record's own wallet.
+fn check_reveal(poll: &Poll, voter: &Voter, candidate_idx: u8, salt: &[u8; 32], now: i64) -> Result<()> {
+    require!(
+        now > poll.end_ts && now <= poll.reveal_deadline_ts,
+        VotingError::RevealNotOpen
+    ); // Reveal phase only.
+    require!(!voter.revealed && !voter.abstained, VotingError::AlreadyRevealed); // Count once.
+    poll.require_candidate(candidate_idx)?; // In range and approved.
+    require!(
+        vote_commitment(candidate_idx, salt, &voter.wallet) == voter.commitment,
+        VotingError::CommitmentMismatch
+    ); // Wrong candidate, salt or wallet.
+    Ok(())
+}
+
+/// Fills the voter record for `wallet`, adds the ballot's weight to its candidate and emits
+/// `VoteCast`. Callers have already validated eligibility and the candidate index.
+fn record_ballot(
+    poll: &mut Account<Poll>,
+    voter: &mut Account<Voter>,
+    wallet: Pubkey,
+    bump: u8,
+    ballot: Ballot,
+    now: i64,
+) -> Result<()> {
+    open_voter_record(poll, voter, wallet, bump, &ballot, now, true)?;
+    add_to_tally(poll, wallet, ballot.candidate_idx, ballot.weight, now)
+}
+
+/// Like `record_ballot` for points-based polls: stores `stored` (see `Voter::ballot`) on the
+/// voter record and adds its points (times the weight) to every candidate.
+fn record_points_ballot(
+    poll: &mut Account<Poll>,
+    voter: &mut Account<Voter>,
+    wallet: Pubkey,
+    bump: u8,
+    ballot: Ballot,
+    stored: Vec<u16>,
+    now: i64,
+) -> Result<()> {
+    open_voter_record(poll, voter, wallet, bump, &ballot, now, true)?;
+    for (idx, p) in ballot_points(poll.ballot_type, &stored).into_iter().enumerate() {
+        if p > 0 {
+            let added = ballot.weight.checked_mul(p as u64).ok_or(VotingError::Overflow)?;
+            add_to_tally(poll, wallet, idx as u8, added, now)?;
+        }
+    }
+    voter.ballot = stored; // What retraction must subtract again.
+    Ok(())
+}
+
+/// Points per candidate of a stored points-based ballot: Borda rankings give `n-1, n-2, ..., 0`
+/// in ranked order; approval flags, scores and cumulative allocations are already points.
+fn ballot_points(ballot_type: BallotType, stored: &[u16]) -> Vec<u16> {
+    match ballot_type {
+        BallotType::Borda => {
+            let n = stored.len(); // Full permutation of the candidates.
+            let mut points = vec![0u16; n];
+            for (rank, &c) in stored.iter().enumerate() {
+                points[c as usize] = (n - 1 - rank) as u16; // Best gets n-1.
+            }
+            points
+        }
+        _ => stored.to_vec(), // Approval flags, scores and allocations (points or bps) count as-is.
+    }
+}
+
+/// Fills a fresh voter record for `wallet` and counts it towards `max_voters`. `cast` is false
+/// for a `commit_vote` commitment, which adds neither turnout nor a used ballot until its
+/// reveal.
+fn open_voter_record(
+    poll: &mut Account<Poll>,
+    voter: &mut Account<Voter>,
+    wallet: Pubkey,
+    bump: u8,
+    ballot: &Ballot,
+    now: i64,
+    cast: bool,
+) -> Result<()> {
+    require!(
+        poll.authority_can_vote || wallet != poll.authority,
+        VotingError::AuthorityCannotVote
+    ); // Neutral organizer, also via delegation.
+    require!(!poll.is_full(), VotingError::PollFull); // First `max_voters` wallets only.
+    poll.voter_count = poll.voter_count.checked_add(1).ok_or(VotingError::Overflow)?; // One more participant.
+    if cast {
+        poll.turnout = poll.turnout.checked_add(ballot.weight).ok_or(VotingError::Overflow)?; // Towards quorum.
+    }
+    poll.record_activity(now)?; // Turnout analytics.
+    voter.version = VOTER_VERSION; // Current layout.
+    voter.has_voted = true; // Flag that this wallet voted.
+    voter.poll = poll.key(); // Store poll reference.
+    voter.wallet = wallet; // Store voter wallet.
+    voter.bump = bump; // Save bump for PDA recreation.
+    voter.candidate_idx = ballot.candidate_idx; // Remember choice for audits and retraction.
+    voter.voted_at = now; // When the ballot was cast.
+    voter.weight = ballot.weight; // Applied weight, so retract/change undo exactly this much.
+    voter.observed_balance = ballot.observed_balance; // Raw balance the weight was derived from.
+    voter.locked_amount = ballot.locked_amount; // What `unlock_tokens` must return.
+    voter.burned = ballot.burned; // Burn-to-vote polls: destroyed with this record's first ballot.
+    voter.votes_used = u8::from(cast); // First ballot of this wallet, unless still hidden.
+    voter.choices = [0; MAX_VOTES_PER_WALLET as usize];
+    voter.choices[0] = ballot.candidate_idx;
+    Ok(())
+}
+
+/// Adds one more ballot for a wallet that already voted, in polls with `votes_per_wallet > 1`.
+/// It counts the weight recorded with the first ballot; holdings aren't re-read or re-locked.
+fn record_extra_ballot(
+    poll: &mut Account<Poll>,
+    voter: &mut Account<Voter>,
+    candidate_idx: u8,
+    now: i64,
+) -> Result<()> {
+    require!(poll.votes_per_wallet > 1, VotingError::AlreadyVoted); // Single-ballot polls: as before.
+    require!(!voter.abstained, VotingError::AlreadyVoted); // Abstainers don't get ballots on top.
+    require!(voter.votes_used < poll.votes_per_wallet, VotingError::VoteLimitReached);
+    let used = voter.votes_used as usize; // Ballots cast so far.
+    require!(
+        poll.allow_repeat_candidate || !voter.choices[..used].contains(&candidate_idx),
+        VotingError::DuplicateChoice
+    ); // Optionally one ballot per candidate.
+    voter.choices[used] = candidate_idx; // Remember for retraction.
+    voter.votes_used += 1;
+    voter.voted_at = now; // When the latest ballot was cast.
+    poll.record_activity(now)?; // Turnout analytics.
+    add_to_tally(poll, voter.wallet, candidate_idx, voter.weight, now)
+}
+
+/// Adds `weight` to a candidate's tally (overflow-checked) and emits `VoteCast`. Hidden-tally
+/// polls skip both; `tabulate` counts their ballots after the close.
+fn add_to_tally(poll: &mut Account<Poll>, wallet: Pubkey, candidate_idx: u8, weight: u64, now: i64) -> Result<()> {
+    if poll.hide_tallies {
+        return Ok(()); // Nothing public until tabulation.
+    }
+    let idx = candidate_idx as usize;
+    poll.votes[idx] = poll
+        .votes[idx]
+        .checked_add(weight)
+        .ok_or(VotingError::Overflow)?;
+    poll.last_increment_ts[idx] = now; // For `TieBreak::EarliestLead`.
+
+    emit!(VoteCast {
+        poll: poll.key(),
+        wallet,
+        candidate_idx,
+        weight,
+        new_count: poll.votes[idx],
+        against: false,
+    });
+    Ok(())
+}
+
+/// `(observed_balance, weight)` of a new ballot under the poll's weighting. Token modes use the
+/// raw balance (base units, not divided by decimals) so no mint account is needed and
+/// fractional holdings still count. `Equal` observes nothing and weighs 1.
+fn vote_weight(
+    poll: &Poll,
+    wallet: &Pubkey,
+    weight_token_account: Option<&UncheckedAccount>,
+    stake_account: Option<&UncheckedAccount>,
+    credential: Option<&VoterCredential>,
+    balance_proof: Option<&BalanceProof>,
+    epoch: u64,
+) -> Result<(u64, u64)> {
+    match poll.weighting {
+        VoteWeighting::Equal => Ok((0, 1)), // One wallet, one vote.
+        VoteWeighting::TokenBalance { mint } => {
+            let amount = weight_balance(mint, wallet, weight_token_account)?;
+            Ok((amount, amount)) // Linear in holdings.
+        }
+        VoteWeighting::Credential => {
+            let credential = credential.ok_or(VotingError::NotEligible)?; // Issued by the authority.
+            Ok((0, credential.weight)) // Share count from `register_voter` / `update_weight`.
+        }
+        VoteWeighting::Stake => {
+            let info = stake_account.ok_or(VotingError::NoVotingPower)?; // Voter's stake account.
+            let stake = stake::unpack_stake_account(info)?; // Must be delegated native stake.
+            require!(
+                stake.staker == *wallet || stake.withdrawer == *wallet,
+                VotingError::InvalidStakeAccount
+            ); // Signer controls the stake.
+            require!(stake.is_active(epoch), VotingError::StakeNotActive); // No (de)activating stake.
+            require!(stake.stake > 0, VotingError::NoVotingPower);
+            Ok((stake.stake, stake.stake)) // Delegated lamports.
+        }
+        VoteWeighting::Escrow { mint } => {
+            let amount = weight_balance(mint, wallet, weight_token_account)?;
+            Ok((amount, amount)) // The whole balance gets locked by `vote`.
+        }
+        VoteWeighting::QuadraticToken { mint } => {
+            let amount = weight_balance(mint, wallet, weight_token_account)?;
+            Ok((amount, isqrt(amount))) // >= 1 since amount >= 1.
+        }
+        VoteWeighting::Snapshot { .. } => {
+            let root = poll.weight_root.ok_or(VotingError::WeightRootNotSet)?; // Published by the authority.
+            let claim = balance_proof.ok_or(VotingError::InvalidProof)?; // Voter's snapshot claim.
+            let leaf = merkle::weight_leaf_hash(wallet, claim.balance); // hash(wallet || balance).
+            require!(
+                merkle::verify(&claim.proof, &root, leaf),
+                VotingError::InvalidProof
+            ); // Balance is in the published snapshot.
+            require!(claim.balance > 0, VotingError::NoVotingPower); // Empty wallets can't vote.
+            Ok((claim.balance, claim.balance)) // Linear in snapshot holdings.
+        }
+    }
+}
+
+/// Checks that `vault` is a `mint` token account owned by the poll's PDA `[seed, poll]`
+/// (`b"escrow"` or `b"rewards"`); returns the token account and the PDA bump.
+fn require_vault(
+    seed: &[u8],
+    poll: &Pubkey,
+    mint: Pubkey,
+    vault: &AccountInfo,
+    program_id: &Pubkey,
+) -> Result<(token::TokenAccount, u8)> {
+    let (owner, bump) = Pubkey::find_program_address(&[seed, poll.as_ref()], program_id);
+    let holding = token::unpack_token_account(vault)?; // Must be a real token account.
+    require_keys_eq!(holding.mint, mint, VotingError::InvalidTokenAccount); // Vault's token.
+    require_keys_eq!(holding.owner, owner, VotingError::InvalidTokenAccount); // Only the program can move it.
+    Ok((holding, bump))
+}
+
+/// Non-zero raw balance of `mint` held by `wallet` in `weight_token_account`.
+fn weight_balance(
+    mint: Pubkey,
+    wallet: &Pubkey,
+    weight_token_account: Option<&UncheckedAccount>,
+) -> Result<u64> {
+    let info = weight_token_account.ok_or(VotingError::NoVotingPower)?; // Voter's token account.
+    let holding = token::unpack_token_account(info)?; // Must be a real token account.
+    require_keys_eq!(holding.mint, mint, VotingError::InvalidTokenAccount); // Weighting token.
+    require_keys_eq!(holding.owner, *wallet, VotingError::InvalidTokenAccount); // Held by the signer.
+    require!(holding.amount > 0, VotingError::NoVotingPower); // Empty wallets can't vote.
+    Ok(holding.amount)
+}
+
+/// `floor(sqrt(n))` using integer Newton iteration (no floats on chain).
+pub fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n; // Start above the root; the sequence decreases monotonically to it.
+    let mut y = n / 2 + (n & 1); // ceil(n / 2) = (x + n / x) / 2 for x = n, without overflowing.
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2; // sqrt(n) <= x <= 2^63 and n / x <= x, so the sum fits.
+    }
+    x
+}
+
+/// Checks that `wallet` holds exactly one token of an NFT whose metadata lists `collection`
+/// as a verified collection.
+#[cfg(feature = "nft-gate")]
+fn check_collection_gate(
+    collection: Pubkey,
+    wallet: &Pubkey,
+    nft_token_account: Option<&UncheckedAccount>,
+    nft_metadata: Option<&UncheckedAccount>,
+) -> Result<()> {
+    let token_info = nft_token_account.ok_or(VotingError::NotEligible)?; // Voter's NFT token account.
+    let metadata_info = nft_metadata.ok_or(VotingError::NotEligible)?; // Metadata of that NFT.
+    let holding = token::unpack_token_account(token_info)?; // Must be a real token account.
+    require_keys_eq!(holding.owner, *wallet, VotingError::NotEligible); // Held by the signer.
+    require!(holding.amount == 1, VotingError::NotEligible); // Holds the NFT itself.
+    let meta = metadata::unpack_metadata(metadata_info)?; // Owned by the metadata program.
+    require_keys_eq!(meta.mint, holding.mint, VotingError::InvalidMetadata); // Metadata for this NFT.
+    let (item_collection, verified) = meta.collection.ok_or(VotingError::NotEligible)?; // Must be in a collection.
+    require_keys_eq!(item_collection, collection, VotingError::NotEligible); // The gated one.
+    require!(verified, VotingError::CollectionNotVerified); // Collection authority signed off.
+    Ok(())
+}
+
+/// Built without `nft-gate`: collection-gated polls can't be verified, so votes fail closed.
+#[cfg(not(feature = "nft-gate"))]
+fn check_collection_gate(
+    _collection: Pubkey,
+    _wallet: &Pubkey,
+    _nft_token_account: Option<&UncheckedAccount>,
+    _nft_metadata: Option<&UncheckedAccount>,
+) -> Result<()> {
+    err!(VotingError::FeatureNotEnabled)
+}
+
+/// Creates and initializes the receipt mint and its metadata (see `init_receipt_mint`).
+#[cfg(feature = "receipt-nft")]
+fn create_receipt_mint(a: &InitReceiptMint, mint_bump: u8, authority_bump: u8, uri_base: &str) -> Result<()> {
+    let poll_key = a.poll.key();
+    let uri = format!("{}{}", uri_base, poll_key); // Points at this poll.
+    require!(uri.len() <= receipt::MAX_URI_BYTES, VotingError::ReceiptUriTooLong);
+    require_keys_eq!(
+        a.metadata.key(),
+        receipt::metadata_address(a.receipt_mint.key),
+        VotingError::InvalidMetadata
+    );
+    require_keys_eq!(a.metadata_program.key(), receipt::mpl_token_metadata::ID, VotingError::InvalidMetadata);
+    create_pda_account(
+        &a.authority.to_account_info(),
+        &a.receipt_mint.to_account_info(),
+        &a.system_program.to_account_info(),
+        receipt::MINT_LEN,
+        &token::spl_token::ID,
+        &[b"receipt_mint", poll_key.as_ref(), &[mint_bump]],
+    )?; // Owned by the token program from here on.
+    receipt::initialize_mint(&a.token_program, &a.receipt_mint, a.receipt_authority.key)?;
+    let mut name = a.poll.title.clone();
+    while name.len() > receipt::MAX_NAME_BYTES {
+        name.pop(); // Metadata names are capped; keep whole characters.
+    }
+    receipt::create_metadata(
+        receipt::MetadataAccounts {
+            metadata_program: &a.metadata_program,
+            metadata: &a.metadata,
+            mint: &a.receipt_mint,
+            authority: &a.receipt_authority,
+            payer: &a.authority,
+            system_program: &a.system_program,
+        },
+        &name,
+        receipt::SYMBOL,
+        &uri,
+        &[&[b"receipt", poll_key.as_ref(), &[authority_bump]]],
+    )
+}
+
+/// Built without `receipt-nft`: there are no mint or metadata CPIs.
+#[cfg(not(feature = "receipt-nft"))]
+fn create_receipt_mint(_a: &InitReceiptMint, _mint_bump: u8, _authority_bump: u8, _uri_base: &str) -> Result<()> {
+    err!(VotingError::FeatureNotEnabled)
+}
+
+/// Mints one receipt token to the voter's ATA, creating it if needed (see `claim_receipt`).
+#[cfg(feature = "receipt-nft")]
+fn mint_receipt(a: &ClaimReceipt, authority_bump: u8) -> Result<()> {
+    token::create_associated_token_account(
+        &a.associated_token_program,
+        &a.wallet,
+        &a.destination,
+        &a.wallet,
+        &a.receipt_mint,
+        &a.system_program,
+        &a.token_program,
+    )?; // No-op when it exists.
+    let poll_key = a.poll.key();
+    receipt::mint_to(
+        &a.token_program,
+        &a.receipt_mint,
+        &a.destination,
+        &a.receipt_authority,
+        1,
+        &[&[b"receipt", poll_key.as_ref(), &[authority_bump]]],
+    )
+}
+
+/// Built without `receipt-nft`: receipts can't be minted.
+#[cfg(not(feature = "receipt-nft"))]
+fn mint_receipt(_a: &ClaimReceipt, _authority_bump: u8) -> Result<()> {
+    err!(VotingError::FeatureNotEnabled)
+}
+
+/// The two finalists of a runoff, leader first. Fails with `RunoffNotNeeded` when runoffs are
+/// off (`majority_bps = 0`), nobody voted or the leader holds at least `majority_bps` of
+/// `votes`, and with `RunoffTie` when second place is shared.
+fn runoff_finalists(votes: &[u64], majority_bps: u16) -> Result<[usize; 2]> {
+    require!(majority_bps > 0, VotingError::RunoffNotNeeded); // Runoffs disabled.
+    let total: u128 = votes.iter().map(|&v| v as u128).sum(); // All ballots.
+    let mut order: Vec<usize> = (0..votes.len()).collect();
+    order.sort_by_key(|&c| std::cmp::Reverse(votes[c])); // Best first.
+    let leader = votes[order[0]] as u128;
+    require!(
+        total > 0 && leader * 10_000 < majority_bps as u128 * total,
+        VotingError::RunoffNotNeeded
+    ); // Leader already has the required share (or nobody voted).
+    let second = votes[order[1]];
+    require!(order.get(2).map(|&c| votes[c]) != Some(second), VotingError::RunoffTie); // Finalists must be unambiguous.
+    Ok([order[0], order[1]])
+}
+
+/// True when `yes` is at least `pass_bps` basis points of `yes + no`. Zero votes never pass.
+pub fn referendum_passed(yes: u64, no: u64, pass_bps: u16) -> bool {
+    let total = yes as u128 + no as u128; // Can't overflow in u128.
+    total > 0 && yes as u128 * 10_000 >= pass_bps as u128 * total
+}
+
+/// Returns the index of the unique top candidate, or `(None, true)` when several share the
+/// highest count. All-zero tallies return `(None, false)`.
+pub fn compute_winner(votes: &[u64]) -> (Option<u8>, bool) {
+    let max = votes.iter().copied().max().unwrap_or(0); // Highest tally.
+    if max == 0 {
+        return (None, false); // Nobody voted.
+    }
+    let mut leaders = votes.iter().enumerate().filter(|(_, v)| **v == max); // Candidates at the top.
+    let first = leaders.next().map(|(i, _)| i as u8); // First leader found.
+    if leaders.next().is_some() {
+        (None, true) // More than one leader: tie.
+    } else {
+        (first, false)
+    }
+}
+
+/// Like `compute_winner` on net scores `votes[i] - downvotes[i]`, which may be negative: the
+/// highest net wins even if below zero. No ballots at all returns `(None, false)`.
+pub fn compute_net_winner(votes: &[u64], downvotes: &[u64]) -> (Option<u8>, bool) {
+    if votes.iter().chain(downvotes).all(|v| *v == 0) {
+        return (None, false); // Nobody voted.
+    }
+    let net: Vec<i128> = votes
+        .iter()
+        .zip(downvotes)
+        .map(|(&up, &down)| up as i128 - down as i128)
+        .collect(); // Can't overflow: both sides fit in u64.
+    let max = net.iter().copied().max().unwrap_or(0); // Best net score.
+    let mut leaders = net.iter().enumerate().filter(|(_, v)| **v == max); // Candidates at the top.
+    let first = leaders.next().map(|(i, _)| i as u8); // First leader found.
+    if leaders.next().is_some() {
+        (None, true) // More than one leader: tie.
+    } else {
+        (first, false)
+    }
+}
+
+/// Accounts needed to initialize a poll. The account is sized for the given title,
+/// description and candidates plus room up to `options.max_candidates` (`Poll::space`), not
+/// for the largest possible poll.
+#[derive(Accounts)]
+#[instruction(
+    title: String,
+    description: String,
+    candidates: Vec<String>,
+    start_ts: i64,
+    end_ts: i64,
+    options: PollOptions
+)]
+pub struct InitPoll<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority, // Authority funds the counter on first use.
+        space = 8 + PollCounter::SIZE, // Discriminator + size of PollCounter.
+        seeds = [b"counter", authority.key.as_ref()], // One counter per authority.
+        bump
+    )]
+    pub counter: Account<'info, PollCounter>, // Supplies the seed for the new poll.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + Poll::space(&title, &description, &candidates, options.reserved_candidates(candidates.len()), options.receiptless_voters), // Discriminator + this poll's data.
+        seeds = [b"poll", authority.key.as_ref(), &counter.count.to_le_bytes()], // PDA seeds.
+        bump // PDA bump supplied by Anchor.
+    )]
+    pub poll: Account<'info, Poll>, // Poll account to create.
+    /// CHECK: `[b"ranked", poll]`; created by the handler for ranked-choice polls.
+    #[account(mut, seeds = [b"ranked", poll.key().as_ref()], bump)]
+    pub ranked_tally: Option<UncheckedAccount<'info>>, // Required when `ballot_type` is `RankedChoice`.
+    /// CHECK: `[b"treasury", poll]`, a system account holding vote fees; funded by the handler.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when `vote_fee_lamports` or `candidate_fee_lamports` is > 0.
+    /// CHECK: `[b"fees", poll]`; owns `fee_vault` and signs its withdrawals.
+    #[account(seeds = [b"fees", poll.key().as_ref()], bump)]
+    pub fee_vault_authority: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Associated token account of `fee_vault_authority` for `fee_mint`; created by the handler.
+    #[account(mut)]
+    pub fee_vault: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Must be `options.fee_mint`; checked by the handler.
+    pub fee_mint: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: SPL Token or Token-2022, owner of `fee_mint`; checked by the handler.
+    pub token_program: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Must be the Associated Token Account program.
+    #[account(address = token::associated_token::ID @ VotingError::InvalidTokenAccount)]
+    pub associated_token_program: Option<UncheckedAccount<'info>>, // Creates `fee_vault`.
+    #[account(mut, seeds = [b"registry"], bump = registry.bump)]
+    pub registry: Option<Account<'info, Registry>>, // Optional: assigns the poll a `poll_id`.
+    #[account(mut, seeds = [b"index", authority.key().as_ref()], bump = authority_index.bump)]
+    pub authority_index: Option<Account<'info, AuthorityIndex>>, // Optional: lists the poll.
+    /// CHECK: `[b"config"]`; may not exist. The handler reads it only when this program owns it.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Protocol fee settings, if deployed.
+    /// CHECK: Must be `protocol_config.fee_recipient`; checked by the handler when a fee is due.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>, // Required when the protocol charges a fee.
+    /// CHECK: `[b"creator", authority]`; may not exist. The handler checks it only while creators are restricted.
+    #[account(seeds = [b"creator", authority.key().as_ref()], bump)]
+    pub creator_credential: Option<UncheckedAccount<'info>>, // Required while the protocol restricts creators.
+    #[account(
+        init_if_needed,
+        payer = authority, // The first rate-limited creation pays the rent.
+        space = 8 + CreatorStats::INIT_SPACE, // Discriminator + size of CreatorStats.
+        seeds = [b"creator_stats", authority.key().as_ref()], // One per authority.
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>, // Required while the protocol rate-limits creation.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Wallet paying for the poll account.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to create the poll registry.
+#[derive(Accounts)]
+pub struct InitRegistry<'info> {
+    #[account(
+        init,
+        payer = payer, // Anyone may create it; it has no admin.
+        space = 8 + Registry::INIT_SPACE, // Discriminator + size of Registry.
+        seeds = [b"registry"], // Program-wide singleton.
+        bump
+    )]
+    pub registry: Account<'info, Registry>, // Registry to create.
+    #[account(mut)]
+    pub payer: Signer<'info>, // Funds the account.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to create an authority's poll index.
+#[derive(Accounts)]
+pub struct InitAuthorityIndex<'info> {
+    #[account(
+        init,
+        payer = authority, // Authority funds the account.
+        space = 8 + AuthorityIndex::space(0), // Discriminator + empty list; grows per poll.
+        seeds = [b"index", authority.key().as_ref()], // One index per authority.
+        bump
+    )]
+    pub authority_index: Account<'info, AuthorityIndex>, // Index to create.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll creator whose polls get listed.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to create the protocol fee config.
+#[derive(Accounts)]
+pub struct InitConfig<'info> {
+    #[account(
+        init,
+        payer = admin, // Protocol admin funds the account.
+        space = 8 + Config::INIT_SPACE, // Discriminator + size of Config.
+        seeds = [b"config"], // Program-wide singleton.
+        bump
+    )]
+    pub config: Account<'info, Config>, // Config to create.
+    #[account(
+        seeds = [crate::ID.as_ref()], // This program's data account.
+        seeds::program = anchor_lang::solana_program::bpf_loader_upgradeable::ID,
+        bump,
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ VotingError::NotProtocolAdmin
+    )]
+    pub program_data: Account<'info, ProgramData>, // Holds the upgrade authority.
+    #[account(mut)]
+    pub admin: Signer<'info>, // Upgrade authority; becomes the protocol admin.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to sweep an abandoned poll's deposit; no signer beyond the fee payer.
+#[derive(Accounts)]
+pub struct SweepAbandoned<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll holding the deposit.
+    #[account(seeds = [b"config"], bump = protocol_config.bump)]
+    pub protocol_config: Account<'info, Config>, // Names the protocol treasury.
+    /// CHECK: Only receives lamports; must be the config's `fee_recipient`.
+    #[account(mut, address = protocol_config.fee_recipient @ VotingError::AccountMismatch)]
+    pub fee_recipient: UncheckedAccount<'info>, // Protocol treasury.
+}
+
+/// Accounts needed to change the protocol settings or start an admin handover.
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump, has_one = admin @ VotingError::NotProtocolAdmin)]
+    pub config: Account<'info, Config>, // Config to update.
+    pub admin: Signer<'info>, // Protocol admin.
+}
+
+/// Accounts needed to allow a wallet to create polls.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct AddCreator<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ VotingError::NotProtocolAdmin)]
+    pub config: Account<'info, Config>, // Protocol settings; names the admin.
+    #[account(
+        init,
+        payer = admin, // Admin funds the credential.
+        space = 8 + CreatorCredential::INIT_SPACE, // Discriminator + size of CreatorCredential.
+        seeds = [b"creator", wallet.as_ref()], // One per wallet.
+        bump
+    )]
+    pub creator_credential: Account<'info, CreatorCredential>, // Credential to create.
+    #[account(mut)]
+    pub admin: Signer<'info>, // Protocol admin; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to revoke a wallet's poll creator credential.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RemoveCreator<'info> {
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ VotingError::NotProtocolAdmin)]
+    pub config: Account<'info, Config>, // Protocol settings; names the admin.
+    #[account(
+        mut,
+        seeds = [b"creator", wallet.as_ref()],
+        bump = creator_credential.bump,
+        close = admin // Rent goes back to the admin.
+    )]
+    pub creator_credential: Account<'info, CreatorCredential>, // Credential to close.
+    #[account(mut)]
+    pub admin: Signer<'info>, // Protocol admin; receives the lamports.
+}
+
+/// Accounts needed to accept the protocol admin role.
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(mut, seeds = [b"config"], bump = config.bump)]
+    pub config: Account<'info, Config>, // Config being handed over.
+    pub new_admin: Signer<'info>, // Must be `config.pending_admin`.
+}
+
+/// Same accounts as `InitPoll`, sized for the two referendum options.
+#[derive(Accounts)]
+#[instruction(title: String, description: String, start_ts: i64, end_ts: i64, pass_bps: u16, options: PollOptions)]
+pub struct InitReferendum<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority, // Authority funds the counter on first use.
+        space = 8 + PollCounter::SIZE, // Discriminator + size of PollCounter.
+        seeds = [b"counter", authority.key.as_ref()], // One counter per authority.
+        bump
+    )]
+    pub counter: Account<'info, PollCounter>, // Supplies the seed for the new poll.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + Poll::space(&title, &description, &REFERENDUM_OPTIONS, 2, options.receiptless_voters), // Discriminator + this poll's data.
+        seeds = [b"poll", authority.key.as_ref(), &counter.count.to_le_bytes()], // PDA seeds.
+        bump // PDA bump supplied by Anchor.
+    )]
+    pub poll: Account<'info, Poll>, // Poll account to create.
+    /// CHECK: `[b"ranked", poll]`; created by the handler for ranked-choice polls.
+    #[account(mut, seeds = [b"ranked", poll.key().as_ref()], bump)]
+    pub ranked_tally: Option<UncheckedAccount<'info>>, // Required when `ballot_type` is `RankedChoice`.
+    /// CHECK: `[b"treasury", poll]`, a system account holding vote fees; funded by the handler.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when `vote_fee_lamports` or `candidate_fee_lamports` is > 0.
+    /// CHECK: `[b"fees", poll]`; owns `fee_vault` and signs its withdrawals.
+    #[account(seeds = [b"fees", poll.key().as_ref()], bump)]
+    pub fee_vault_authority: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Associated token account of `fee_vault_authority` for `fee_mint`; created by the handler.
+    #[account(mut)]
+    pub fee_vault: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Must be `options.fee_mint`; checked by the handler.
+    pub fee_mint: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: SPL Token or Token-2022, owner of `fee_mint`; checked by the handler.
+    pub token_program: Option<UncheckedAccount<'info>>, // Required when `fee_mint` is set.
+    /// CHECK: Must be the Associated Token Account program.
+    #[account(address = token::associated_token::ID @ VotingError::InvalidTokenAccount)]
+    pub associated_token_program: Option<UncheckedAccount<'info>>, // Creates `fee_vault`.
+    #[account(mut, seeds = [b"registry"], bump = registry.bump)]
+    pub registry: Option<Account<'info, Registry>>, // Optional: assigns the poll a `poll_id`.
+    #[account(mut, seeds = [b"index", authority.key().as_ref()], bump = authority_index.bump)]
+    pub authority_index: Option<Account<'info, AuthorityIndex>>, // Optional: lists the poll.
+    /// CHECK: `[b"config"]`; may not exist. The handler reads it only when this program owns it.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Protocol fee settings, if deployed.
+    /// CHECK: Must be `protocol_config.fee_recipient`; checked by the handler when a fee is due.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>, // Required when the protocol charges a fee.
+    /// CHECK: `[b"creator", authority]`; may not exist. The handler checks it only while creators are restricted.
+    #[account(seeds = [b"creator", authority.key().as_ref()], bump)]
+    pub creator_credential: Option<UncheckedAccount<'info>>, // Required while the protocol restricts creators.
+    #[account(
+        init_if_needed,
+        payer = authority, // The first rate-limited creation pays the rent.
+        space = 8 + CreatorStats::INIT_SPACE, // Discriminator + size of CreatorStats.
+        seeds = [b"creator_stats", authority.key().as_ref()], // One per authority.
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>, // Required while the protocol rate-limits creation.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Wallet paying for the poll account.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to create a zero-copy large poll.
+#[derive(Accounts)]
+pub struct InitPollLarge<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority, // Authority funds the counter on first use.
+        space = 8 + PollCounter::SIZE, // Discriminator + size of PollCounter.
+        seeds = [b"counter", authority.key.as_ref()], // One counter per authority.
+        bump
+    )]
+    pub counter: Account<'info, PollCounter>, // Supplies the seed for the new poll.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + PollLarge::SIZE, // Discriminator + fixed layout.
+        seeds = [b"poll", authority.key.as_ref(), &counter.count.to_le_bytes()], // Same scheme as `init_poll`.
+        bump
+    )]
+    pub poll: AccountLoader<'info, PollLarge>, // Poll account to create.
+    /// CHECK: `[b"config"]`; may not exist. The handler reads it only when this program owns it.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Protocol fee settings, if deployed.
+    /// CHECK: Must be `protocol_config.fee_recipient`; checked by the handler when a fee is due.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>, // Required when the protocol charges a fee.
+    /// CHECK: `[b"creator", authority]`; may not exist. The handler checks it only while creators are restricted.
+    #[account(seeds = [b"creator", authority.key().as_ref()], bump)]
+    pub creator_credential: Option<UncheckedAccount<'info>>, // Required while the protocol restricts creators.
+    #[account(
+        init_if_needed,
+        payer = authority, // The first rate-limited creation pays the rent.
+        space = 8 + CreatorStats::INIT_SPACE, // Discriminator + size of CreatorStats.
+        seeds = [b"creator_stats", authority.key().as_ref()], // One per authority.
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>, // Required while the protocol rate-limits creation.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Wallet paying for the poll account.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to add candidates to a large poll.
+#[derive(Accounts)]
+pub struct AddCandidatesLarge<'info> {
+    #[account(mut, has_one = authority)] // Only the stored authority may add candidates.
+    pub poll: AccountLoader<'info, PollLarge>, // Poll being edited.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed to vote on a large poll.
+#[derive(Accounts)]
+pub struct VoteLarge<'info> {
+    #[account(mut)]
+    pub poll: AccountLoader<'info, PollLarge>, // Poll being voted on.
+    #[account(
+        init, // Fails if this wallet already voted.
+        payer = wallet, // Voter pays rent for their own record.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // PDA unique per (poll, wallet).
+        bump,
+        space = 8 + Voter::INIT_SPACE // Discriminator + size of Voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record PDA to mark participation.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; signs and funds the PDA.
+    pub system_program: Program<'info, System>, // Required for PDA creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to create a split poll.
+#[derive(Accounts)]
+pub struct InitPollV2<'info> {
+    #[account(
+        init_if_needed,
+        payer = authority, // Authority funds the counter on first use.
+        space = 8 + PollCounter::SIZE, // Discriminator + size of PollCounter.
+        seeds = [b"counter", authority.key.as_ref()], // One counter per authority.
+        bump
+    )]
+    pub counter: Account<'info, PollCounter>, // Supplies the seed for the new poll.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + PollConfig::INIT_SPACE, // Discriminator + max size for PollConfig.
+        seeds = [b"poll", authority.key.as_ref(), &counter.count.to_le_bytes()], // Same scheme as `init_poll`.
+        bump
+    )]
+    pub config: Account<'info, PollConfig>, // Immutable half to create.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + PollTally::SIZE, // Discriminator + fixed layout.
+        seeds = [b"poll_tally", config.key().as_ref()], // One tally per config.
+        bump
+    )]
+    pub tally: AccountLoader<'info, PollTally>, // Mutable half to create.
+    /// CHECK: `[b"config"]`; may not exist. The handler reads it only when this program owns it.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Protocol fee settings, if deployed.
+    /// CHECK: Must be `protocol_config.fee_recipient`; checked by the handler when a fee is due.
+    #[account(mut)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>, // Required when the protocol charges a fee.
+    /// CHECK: `[b"creator", authority]`; may not exist. The handler checks it only while creators are restricted.
+    #[account(seeds = [b"creator", authority.key().as_ref()], bump)]
+    pub creator_credential: Option<UncheckedAccount<'info>>, // Required while the protocol restricts creators.
+    #[account(
+        init_if_needed,
+        payer = authority, // The first rate-limited creation pays the rent.
+        space = 8 + CreatorStats::INIT_SPACE, // Discriminator + size of CreatorStats.
+        seeds = [b"creator_stats", authority.key().as_ref()], // One per authority.
+        bump
+    )]
+    pub creator_stats: Option<Account<'info, CreatorStats>>, // Required while the protocol rate-limits creation.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Wallet paying for both accounts.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to vote on a split poll.
+#[derive(Accounts)]
+pub struct VoteV2<'info> {
+    /// CHECK: Not read; the tally's seeds and `has_one = config` tie it to this address.
+    pub config: UncheckedAccount<'info>, // Poll being voted on (not write-locked or decoded).
+    #[account(mut, has_one = config)] // Only `init_poll_v2` creates tallies, so no seed re-derivation.
+    pub tally: AccountLoader<'info, PollTally>, // Counts being incremented in place.
+    #[account(
+        init, // Fails if this wallet already voted.
+        payer = wallet, // Voter pays rent for their own record.
+        seeds = [b"voter", config.key().as_ref(), wallet.key().as_ref()], // PDA unique per (poll, wallet).
+        bump,
+        space = 8 + Voter::INIT_SPACE // Discriminator + size of Voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record PDA to mark participation.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; signs and funds the PDA.
+    pub system_program: Program<'info, System>, // Required for PDA creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to create a runoff poll.
+#[derive(Accounts)]
+pub struct CreateRunoff<'info> {
+    #[account(mut, has_one = authority, constraint = parent.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may start a runoff.
+    pub parent: Box<Account<'info, Poll>>, // Finalized first round.
+    #[account(
+        init_if_needed,
+        payer = authority, // Authority funds the counter on first use.
+        space = 8 + PollCounter::SIZE, // Discriminator + size of PollCounter.
+        seeds = [b"counter", authority.key.as_ref()], // One counter per authority.
+        bump
+    )]
+    pub counter: Account<'info, PollCounter>, // Supplies the seed for the runoff.
+    #[account(
+        init,
+        payer = authority, // Authority funds account creation.
+        space = 8 + Poll::INIT_SPACE, // Discriminator + max size for Poll.
+        seeds = [b"poll", authority.key.as_ref(), &counter.count.to_le_bytes()], // Same scheme as `init_poll`.
+        bump
+    )]
+    pub runoff: Box<Account<'info, Poll>>, // Runoff poll to create.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to cast a vote.
+#[derive(Accounts)]
+pub struct Vote<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Must point to the correct authority; poll is mutable for vote counts.
+    pub poll: Box<Account<'info, Poll>>, // Poll being voted on (boxed: this struct has many accounts).
+    /// CHECK: Read-only authority pubkey stored on the poll; no additional data is read or written.
+    pub authority: AccountInfo<'info>, // Authority pubkey stored in the poll.
+    #[account(
+        init_if_needed, // Existing records are loaded so the handler can return `AlreadyVoted`.
+        payer = wallet, // Voter pays rent for their own record.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // PDA unique per (poll, wallet).
+        bump,
+        space = 8 + Voter::INIT_SPACE // Discriminator + size of Voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record PDA to mark participation.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; signs and funds the PDA.
+    #[account(
+        seeds = [b"cred", poll.key().as_ref(), wallet.key().as_ref()], // Credential for this signer.
+        bump = credential.bump,
+        has_one = poll,
+        has_one = wallet
+    )]
+    pub credential: Option<Account<'info, VoterCredential>>, // Required when the poll is allowlisted.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    #[account(
+        seeds = [b"attest", poll.key().as_ref(), wallet.key().as_ref()], // Attestation for this signer.
+        bump = attestation.bump,
+        has_one = poll,
+        has_one = wallet
+    )]
+    pub attestation: Option<Account<'info, Attestation>>, // Required when the poll has a registrar.
+    /// CHECK: Identity PDA `[b"identity", poll, attestation.identity_hash]`; checked and
+    /// created by `claim_identity`.
+    #[account(mut)]
+    pub identity: Option<UncheckedAccount<'info>>, // Required when the poll has a registrar.
+    /// CHECK: Parsed by `token::unpack_token_account` (owner program, mint, owner, amount).
+    pub gate_token_account: Option<UncheckedAccount<'info>>, // Required when the poll is token-gated.
+    /// CHECK: Parsed by `token::unpack_token_account`; must hold the NFT (amount == 1).
+    pub nft_token_account: Option<UncheckedAccount<'info>>, // Required when the poll is collection-gated.
+    /// CHECK: Parsed by `metadata::unpack_metadata` (owner program, mint, collection).
+    pub nft_metadata: Option<UncheckedAccount<'info>>, // Metaplex metadata for the NFT above.
+    /// CHECK: Parsed by `token::unpack_token_account` (owner program, mint, owner, amount).
+    #[account(mut)]
+    pub weight_token_account: Option<UncheckedAccount<'info>>, // Required in token weighting modes; debited in `Escrow`.
+    /// CHECK: Parsed by `stake::unpack_stake_account` (owner program, authorities, delegation).
+    pub stake_account: Option<UncheckedAccount<'info>>, // Required in `Stake` weighting.
+    /// CHECK: Checked by `require_vault` (mint, owned by the poll's escrow PDA).
+    #[account(mut)]
+    pub escrow_vault: Option<UncheckedAccount<'info>>, // Required in `Escrow` weighting.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: Option<UncheckedAccount<'info>>, // Required in `Escrow` weighting, for token fees and burns.
+    #[account(mut, seeds = [b"ranked", poll.key().as_ref()], bump = ranked_tally.bump, has_one = poll)]
+    pub ranked_tally: Option<Box<Account<'info, RankedTally>>>, // Required by `vote_ranked`.
+    /// CHECK: System account `[b"treasury", poll]` receiving `vote_fee`.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll charges a fee.
+    /// CHECK: The wallet's associated token account for `fee_mint`; checked by `pay_vote_fee`.
+    #[account(mut)]
+    pub fee_token_account: Option<UncheckedAccount<'info>>, // Required when the poll charges a token fee.
+    /// CHECK: Must be the poll's fee vault.
+    #[account(mut, address = poll.fee_vault @ VotingError::AccountMismatch)]
+    pub fee_vault: Option<UncheckedAccount<'info>>, // Receives `fee_amount`.
+    /// CHECK: Parsed by `token::unpack_token_account`; must hold `burn_mint` for the signer.
+    #[account(mut)]
+    pub burn_token_account: Option<UncheckedAccount<'info>>, // Required in burn-to-vote polls.
+    /// CHECK: Must be `poll.burn_mint` (checked by `admit`); its supply shrinks.
+    #[account(mut)]
+    pub burn_mint: Option<UncheckedAccount<'info>>, // Required in burn-to-vote polls.
+    pub system_program: Program<'info, System>, // System program for account creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to issue a voter credential.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RegisterVoter<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may register voters.
+    pub poll: Account<'info, Poll>, // Poll the credential is for.
+    #[account(
+        init,
+        payer = authority, // Authority funds the credential.
+        space = 8 + VoterCredential::SIZE, // Discriminator + size of VoterCredential.
+        seeds = [b"cred", poll.key().as_ref(), wallet.as_ref()], // One credential per (poll, wallet).
+        bump
+    )]
+    pub credential: Account<'info, VoterCredential>, // Credential to create.
+    #[account(mut, has_one = poll, seeds = [b"participation", poll.key().as_ref()], bump = participation.bump)]
+    pub participation: Option<Account<'info, ParticipationBitmap>>, // Required once the poll has one.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to give an existing credential a bitmap slot.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct IndexVoter<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may assign slots.
+    pub poll: Account<'info, Poll>, // Poll the credential is for.
+    #[account(
+        mut,
+        has_one = poll,
+        seeds = [b"cred", poll.key().as_ref(), wallet.as_ref()],
+        bump = credential.bump
+    )]
+    pub credential: Account<'info, VoterCredential>, // Credential to index.
+    #[account(mut, has_one = poll, seeds = [b"participation", poll.key().as_ref()], bump = participation.bump)]
+    pub participation: Account<'info, ParticipationBitmap>, // Hands out the slot.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed to issue credentials in bulk; the credential PDAs go in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct RegisterVotersBatch<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may register voters.
+    pub poll: Account<'info, Poll>, // Poll the credentials are for.
+    #[account(mut, has_one = poll, seeds = [b"participation", poll.key().as_ref()], bump = participation.bump)]
+    pub participation: Option<Account<'info, ParticipationBitmap>>, // Required once the poll has one.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays rent for every credential.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to change a credential's weight.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct UpdateWeight<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may change weights.
+    pub poll: Account<'info, Poll>, // Poll the credential is for.
+    #[account(
+        mut,
+        has_one = poll,
+        seeds = [b"cred", poll.key().as_ref(), wallet.as_ref()],
+        bump = credential.bump
+    )]
+    pub credential: Account<'info, VoterCredential>, // Credential to update.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed for the registrar to attest a wallet.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct Attest<'info> {
+    #[account(constraint = poll.registrar == Some(registrar.key()) @ VotingError::NotRegistrar, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the attestation is for.
+    #[account(
+        init,
+        payer = registrar, // Registrar funds the attestation.
+        space = 8 + Attestation::SIZE, // Discriminator + size of Attestation.
+        seeds = [b"attest", poll.key().as_ref(), wallet.as_ref()], // One per (poll, wallet).
+        bump
+    )]
+    pub attestation: Account<'info, Attestation>, // Attestation to create.
+    #[account(mut)]
+    pub registrar: Signer<'info>, // Poll's identity registrar; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to revoke an attestation.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RevokeAttestation<'info> {
+    #[account(constraint = poll.registrar == Some(registrar.key()) @ VotingError::NotRegistrar, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the attestation is for.
+    #[account(
+        mut,
+        has_one = poll,
+        seeds = [b"attest", poll.key().as_ref(), wallet.as_ref()],
+        bump = attestation.bump,
+        close = registrar // Rent goes back to the registrar.
+    )]
+    pub attestation: Account<'info, Attestation>, // Attestation to close.
+    /// CHECK: Attested wallet's voter PDA; only checked to be empty (not voted yet).
+    #[account(seeds = [b"voter", poll.key().as_ref(), wallet.as_ref()], bump)]
+    pub voter: UncheckedAccount<'info>, // Must not exist.
+    #[account(mut)]
+    pub registrar: Signer<'info>, // Poll's identity registrar; receives the lamports.
+}
+
+/// Accounts needed to ban a wallet from a poll.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct BanVoter<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may ban.
+    pub poll: Account<'info, Poll>, // Poll the ban applies to.
+    #[account(
+        init,
+        payer = authority, // Authority funds the ban record.
+        space = 8 + Banned::SIZE, // Discriminator + size of Banned.
+        seeds = [b"ban", poll.key().as_ref(), wallet.as_ref()], // One per (poll, wallet).
+        bump
+    )]
+    pub banned: Account<'info, Banned>, // Ban to create.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to lift a ban.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct UnbanVoter<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may unban.
+    pub poll: Account<'info, Poll>, // Poll the ban applies to.
+    #[account(
+        mut,
+        has_one = poll,
+        seeds = [b"ban", poll.key().as_ref(), wallet.as_ref()],
+        bump = banned.bump,
+        close = authority // Rent goes back to the authority.
+    )]
+    pub banned: Account<'info, Banned>, // Ban to close.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; receives the lamports.
+}
+
+/// Accounts needed to revoke a voter credential.
+#[derive(Accounts)]
+#[instruction(wallet: Pubkey)]
+pub struct RevokeVoter<'info> {
+    #[account(has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may revoke.
+    pub poll: Account<'info, Poll>, // Poll the credential is for.
+    #[account(
+        mut,
+        has_one = poll,
+        seeds = [b"cred", poll.key().as_ref(), wallet.as_ref()],
+        bump = credential.bump,
+        close = authority // Rent goes back to the authority.
+    )]
+    pub credential: Account<'info, VoterCredential>, // Credential to close.
+    #[account(has_one = poll, seeds = [b"participation", poll.key().as_ref()], bump = participation.bump)]
+    pub participation: Option<Account<'info, ParticipationBitmap>>, // Required for credentials with a slot.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; receives the lamports.
+}
+
+impl<'info> Vote<'info> {
+    /// Charges the signer this poll's `vote_fee` or token fee (see `pay_vote_fee`).
+    fn pay_vote_fee(&self) -> Result<()> {
+        pay_vote_fee(
+            &self.poll,
+            &self.wallet.to_account_info(),
+            self.treasury.as_ref(),
+            &self.system_program.to_account_info(),
+            Some(TokenFee {
+                source: self.fee_token_account.as_ref(),
+                vault: self.fee_vault.as_ref(),
+                token_program: self.token_program.as_ref(),
+            }),
+        )
+    }
+
+    /// Eligibility checks shared by every ballot instruction: bans, registrar attestation,
+    /// allowlists, token/NFT gates and the minimum SOL balance.
+    fn check_eligibility(&self, proof: &[[u8; 32]]) -> Result<()> {
+        let poll = &self.poll;
+        let wallet = self.wallet.key();
+        require!(poll.receiptless_voters == 0, VotingError::ReceiptlessPoll); // Ballots go through `vote_light`.
+        require!(poll.participation.is_none(), VotingError::ParticipationPoll); // Ballots go through `vote_indexed`.
+        require!(self.ban.data_is_empty(), VotingError::VoterBanned); // Barred from this poll.
+        if poll.registrar.is_some() {
+            // Seeds/has_one on the optional account tie it to (poll, wallet).
+            require!(self.attestation.is_some(), VotingError::NotAttested);
+        }
+        if poll.allowlist_required {
+            // Seeds/has_one on the optional account already tie it to (poll, wallet).
+            require!(self.credential.is_some(), VotingError::NotEligible);
+        }
+        if let Some(root) = poll.allowlist_root {
+            let leaf = merkle::leaf_hash(&wallet); // hash(wallet_pubkey).
+            require!(merkle::verify(proof, &root, leaf), VotingError::InvalidProof);
+        }
+        if let Some(gate_mint) = poll.gate_mint {
+            let info = self
+                .gate_token_account
+                .as_ref()
+                .ok_or(VotingError::InsufficientTokens)?; // Gated polls need the voter's token account.
+            let holding = token::unpack_token_account(info)?; // Must be a real token account.
+            require!(holding.mint == gate_mint, VotingError::InsufficientTokens); // Right token.
+            require!(holding.owner == wallet, VotingError::InsufficientTokens); // Held by the signer.
+            require!(holding.amount >= poll.min_balance, VotingError::InsufficientTokens); // Enough of it.
+        }
+        if poll.min_lamports > 0 {
+            // The voter record is already created (and its rent debited) by the time the handler
+            // runs, so this is the balance the wallet keeps after paying for it.
+            require!(
+                self.wallet.lamports() >= poll.min_lamports,
+                VotingError::WalletTooNew
+            );
+        }
+        if let Some(collection) = poll.gate_collection {
+            check_collection_gate(
+                collection,
+                &wallet,
+                self.nft_token_account.as_ref(),
+                self.nft_metadata.as_ref(),
+            )?; // Voter holds a verified item of the collection.
+        }
+        Ok(())
+    }
+
+    /// First-ballot bookkeeping shared by every ballot instruction: claims the attested
+    /// identity, derives the weight, locks escrowed tokens and burns the burn-to-vote amount.
+    /// `candidate_idx` is left at 0.
+    fn admit(
+        &mut self,
+        balance_proof: Option<&BalanceProof>,
+        epoch: u64,
+        program_id: &Pubkey,
+    ) -> Result<Ballot> {
+        if let Some(attestation) = &self.attestation {
+            let identity = self.identity.as_ref().ok_or(VotingError::NotAttested)?;
+            claim_identity(
+                attestation,
+                identity,
+                &self.wallet.to_account_info(),
+                &self.system_program.to_account_info(),
+                program_id,
+            )?; // One ballot per attested identity, whatever the wallet.
+        }
+        let (observed_balance, weight) = vote_weight(
+            &self.poll,
+            &self.wallet.key(),
+            self.weight_token_account.as_ref(),
+            self.stake_account.as_ref(),
+            self.credential.as_deref(),
+            balance_proof,
+            epoch,
+        )?; // (0, 1) in `Equal` mode.
+        let mut locked_amount = 0; // Tokens moved into escrow by this ballot.
+        if let VoteWeighting::Escrow { mint } = self.poll.weighting {
+            let vault = self.escrow_vault.as_ref().ok_or(VotingError::InvalidTokenAccount)?;
+            let token_program = self.token_program.as_ref().ok_or(VotingError::InvalidTokenAccount)?;
+            require_vault(b"escrow", &self.poll.key(), mint, vault, program_id)?; // Program-owned vault for this poll.
+            token::transfer(
+                token_program,
+                self.weight_token_account.as_ref().unwrap(), // Checked by `vote_weight`.
+                vault,
+                &self.wallet.to_account_info(),
+                weight,
+                &[],
+            )?; // Lock the whole balance; the weight is the locked amount.
+            locked_amount = weight;
+            self.poll.escrow_locked = self
+                .poll
+                .escrow_locked
+                .checked_add(weight)
+                .ok_or(VotingError::Overflow)?; // Keeps the poll open until everyone unlocked.
+        }
+        let mut burned = 0; // Tokens destroyed by this ballot.
+        if let Some(mint) = self.poll.burn_mint {
+            let account = self.burn_token_account.as_ref().ok_or(VotingError::InsufficientTokens)?;
+            let mint_info = self.burn_mint.as_ref().ok_or(VotingError::InvalidTokenAccount)?;
+            let token_program = self.token_program.as_ref().ok_or(VotingError::InvalidTokenAccount)?;
+            require_keys_eq!(mint_info.key(), mint, VotingError::InvalidTokenAccount); // The poll's token.
+            let holding = token::unpack_token_account(account)?; // Must be a real token account.
+            require_keys_eq!(holding.mint, mint, VotingError::InsufficientTokens);
+            require_keys_eq!(holding.owner, self.wallet.key(), VotingError::InsufficientTokens); // The signer's tokens.
+            require!(holding.amount >= self.poll.burn_amount, VotingError::InsufficientTokens); // Enough to burn.
+            token::burn(
+                token_program,
+                account,
+                mint_info,
+                &self.wallet.to_account_info(),
+                self.poll.burn_amount,
+                &[],
+            )?; // The wallet signs as owner.
+            burned = self.poll.burn_amount;
+            self.poll.total_burned = self
+                .poll
+                .total_burned
+                .checked_add(burned)
+                .ok_or(VotingError::Overflow)?;
+        }
+        Ok(Ballot { candidate_idx: 0, weight, observed_balance, locked_amount, burned })
+    }
+}
+
+/// Accounts needed for a delegate to vote on a delegator's behalf.
+#[derive(Accounts)]
+pub struct VoteAsDelegate<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Box<Account<'info, Poll>>, // Poll being voted on (boxed to keep the frame small).
+    #[account(
+        has_one = poll, // Delegation must be for this poll.
+        seeds = [b"delegate", poll.key().as_ref(), delegation.delegator.as_ref()],
+        bump = delegation.bump,
+        constraint = delegation.to == delegate.key() @ VotingError::NotDelegate
+    )]
+    pub delegation: Account<'info, Delegation>, // Live delegation to the signer.
+    #[account(
+        init_if_needed, // Existing records are loaded so the handler can return `AlreadyVoted`.
+        payer = delegate, // Delegate pays rent for the delegator's record.
+        seeds = [b"voter", poll.key().as_ref(), delegation.delegator.as_ref()], // Delegator's PDA.
+        bump,
+        space = 8 + Voter::INIT_SPACE // Discriminator + size of Voter.
+    )]
+    pub voter: Account<'info, Voter>, // Delegator's voter record.
+    #[account(
+        seeds = [b"cred", poll.key().as_ref(), delegation.delegator.as_ref()], // Delegator's credential.
+        bump = credential.bump,
+        has_one = poll
+    )]
+    pub credential: Option<Account<'info, VoterCredential>>, // Required when the poll is allowlisted.
+    /// CHECK: Delegator's ban PDA; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), delegation.delegator.as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the delegator is banned.
+    #[account(mut)]
+    pub delegate: Signer<'info>, // Wallet the vote was delegated to.
+    /// CHECK: System account `[b"treasury", poll]` receiving `vote_fee`.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll charges a fee.
+    pub system_program: Program<'info, System>, // System program for account creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to delegate a vote.
+#[derive(Accounts)]
+pub struct Delegate<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the delegation is for.
+    #[account(
+        init,
+        payer = delegator, // Delegator funds the record.
+        space = 8 + Delegation::SIZE, // Discriminator + size of Delegation.
+        seeds = [b"delegate", poll.key().as_ref(), delegator.key().as_ref()], // One per (poll, delegator).
+        bump
+    )]
+    pub delegation: Account<'info, Delegation>, // Delegation to create.
+    /// CHECK: Delegator's voter PDA; only checked to be empty (not voted yet).
+    #[account(seeds = [b"voter", poll.key().as_ref(), delegator.key().as_ref()], bump)]
+    pub delegator_voter: UncheckedAccount<'info>, // Must not exist yet.
+    #[account(mut)]
+    pub delegator: Signer<'info>, // Wallet handing over its vote; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to extend a delegation.
+#[derive(Accounts)]
+pub struct ExtendDelegation<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the delegation is for.
+    #[account(
+        mut,
+        has_one = poll,
+        has_one = delegator, // Only the delegator may extend.
+        seeds = [b"delegate", poll.key().as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump
+    )]
+    pub delegation: Account<'info, Delegation>, // Delegation to extend.
+    pub delegator: Signer<'info>, // Wallet that delegated.
+}
+
+/// Accounts needed to revoke a delegation.
+#[derive(Accounts)]
+pub struct Undelegate<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the delegation is for.
+    #[account(
+        mut,
+        has_one = poll,
+        has_one = delegator, // Only the delegator may revoke.
+        seeds = [b"delegate", poll.key().as_ref(), delegator.key().as_ref()],
+        bump = delegation.bump,
+        close = delegator // Rent goes back to the delegator.
+    )]
+    pub delegation: Account<'info, Delegation>, // Delegation to close.
+    #[account(mut)]
+    pub delegator: Signer<'info>, // Receives the lamports.
+}
+
+/// Accounts needed to close a finished poll.
+#[derive(Accounts)]
+pub struct ClosePoll<'info> {
+    #[account(
+        mut,
+        constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion, // Current layout only.
+        has_one = authority, // Only the stored authority may close.
+        close = authority // Rent goes back to the authority.
+    )]
+    pub poll: Account<'info, Poll>, // Poll account to close.
+    /// CHECK: System account `[b"treasury", poll]`; the program signs for it with its seeds.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required for fee polls; its rent is returned.
+    /// CHECK: Must be the poll's fee vault; checked again by `require_vault`.
+    #[account(mut, address = poll.fee_vault @ VotingError::AccountMismatch)]
+    pub fee_vault: Option<UncheckedAccount<'info>>, // Required for token-fee polls; closed.
+    /// CHECK: `[b"fees", poll]`; owns the vault, signs via seeds.
+    #[account(seeds = [b"fees", poll.key().as_ref()], bump)]
+    pub fee_vault_authority: Option<UncheckedAccount<'info>>, // Required for token-fee polls.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::close_account`).
+    pub token_program: Option<UncheckedAccount<'info>>, // Required for token-fee polls.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin receiving the lamports.
+    pub system_program: Program<'info, System>, // Moves the treasury's rent.
+}
+
+/// Accounts needed to add to a poll's prize pool.
+#[derive(Accounts)]
+pub struct FundPrize<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose winner gets the prize.
+    #[account(
+        init_if_needed,
+        payer = funder, // The first funder pays the pool's rent.
+        space = 8 + PrizePool::INIT_SPACE, // Discriminator + size of PrizePool.
+        seeds = [b"prize", poll.key().as_ref()], // One pool per poll.
+        bump
+    )]
+    pub prize_pool: Account<'info, PrizePool>, // Holds the prize lamports.
+    #[account(
+        init_if_needed,
+        payer = funder, // Funder pays rent for their own record.
+        space = 8 + Contribution::INIT_SPACE, // Discriminator + size of Contribution.
+        seeds = [b"contribution", poll.key().as_ref(), funder.key().as_ref()], // One per (poll, funder).
+        bump
+    )]
+    pub contribution: Account<'info, Contribution>, // Running total for refunds.
+    #[account(mut)]
+    pub funder: Signer<'info>, // Anyone adding to the prize.
+    pub system_program: Program<'info, System>, // Moves the lamports.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to pay the prize to the winner; no signer beyond the fee payer is required.
+#[derive(Accounts)]
+pub struct PayoutPrize<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Finalized poll with a winner.
+    #[account(mut, seeds = [b"prize", poll.key().as_ref()], bump = prize_pool.bump, close = winner)]
+    pub prize_pool: Account<'info, PrizePool>, // Pool emptied into the winner's wallet.
+    /// CHECK: Only receives lamports; the handler checks it is the winner's `candidate_wallets` entry.
+    #[account(mut)]
+    pub winner: UncheckedAccount<'info>, // Winning candidate's wallet.
+}
+
+/// Accounts needed to contest a finalized result.
+#[derive(Accounts)]
+pub struct FlagDispute<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Finalized poll being contested.
+    #[account(seeds = [b"config"], bump = protocol_config.bump)]
+    pub protocol_config: Option<Account<'info, Config>>, // Names the protocol admin; needed when the admin signs.
+    pub signer: Signer<'info>, // Poll authority or protocol admin.
+}
+
+/// Accounts needed to co-sign a finalized result.
+#[derive(Accounts)]
+pub struct CertifyResults<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Finalized poll with a certifier.
+    pub certifier: Signer<'info>, // Must be `poll.certifier`.
+}
+
+/// Accounts needed to rule on a disputed result.
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Disputed poll.
+    #[account(seeds = [b"config"], bump = config.bump, has_one = admin @ VotingError::NotProtocolAdmin)]
+    pub config: Account<'info, Config>, // Names the protocol admin.
+    pub admin: Signer<'info>, // Protocol admin.
+}
+
+/// Accounts needed to take back a contribution to an unwinnable prize.
+#[derive(Accounts)]
+pub struct RefundContribution<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Cancelled poll, or finalized without a payable winner.
+    #[account(mut, seeds = [b"prize", poll.key().as_ref()], bump = prize_pool.bump)]
+    pub prize_pool: Account<'info, PrizePool>, // Pool the refund comes from.
+    #[account(
+        mut,
+        seeds = [b"contribution", poll.key().as_ref(), funder.key().as_ref()],
+        bump = contribution.bump,
+        close = funder // Rent goes back with the refund.
+    )]
+    pub contribution: Account<'info, Contribution>, // What this funder put in.
+    #[account(mut)]
+    pub funder: Signer<'info>, // Wallet being refunded.
+}
+
+/// Accounts needed to pay out a poll's fee treasury; no signer beyond the fee payer is required.
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose fees are withdrawn; read-only.
+    /// CHECK: System account `[b"treasury", poll]`; the program signs for it with its seeds.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: UncheckedAccount<'info>, // Fees collected so far.
+    /// CHECK: Only receives lamports; must be the address stored on the poll.
+    #[account(mut, address = poll.fee_beneficiary @ VotingError::AccountMismatch)]
+    pub fee_beneficiary: UncheckedAccount<'info>, // Recipient chosen at init.
+    pub system_program: Program<'info, System>, // Moves lamports out of the treasury.
+}
+
+/// Accounts needed to pay out a token-fee poll's vault; no signer beyond the fee payer is required.
+#[derive(Accounts)]
+pub struct WithdrawFeeTokens<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose fees are withdrawn; read-only.
+    /// CHECK: `[b"fees", poll]`; owns the vault, signs via seeds.
+    #[account(seeds = [b"fees", poll.key().as_ref()], bump)]
+    pub fee_vault_authority: UncheckedAccount<'info>, // Vault owner.
+    /// CHECK: Must be the poll's fee vault; checked again by `require_vault`.
+    #[account(mut, address = poll.fee_vault @ VotingError::AccountMismatch)]
+    pub fee_vault: UncheckedAccount<'info>, // Fees collected so far.
+    /// CHECK: Must be the fee beneficiary's associated token account (checked by the handler).
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>, // Receives the tokens.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: UncheckedAccount<'info>, // Runs the transfer.
+}
+
+/// Accounts needed to refund a rejected candidate's registration fee; anyone may send it.
+#[derive(Accounts)]
+pub struct RefundCandidateFee<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose treasury holds the fee.
+    #[account(
+        mut,
+        seeds = [b"candidate_fee", poll.key().as_ref(), wallet.key().as_ref()],
+        bump = candidate_fee_record.bump,
+        has_one = poll @ VotingError::AccountMismatch,
+        has_one = wallet @ VotingError::AccountMismatch,
+        close = wallet // Rent goes back to the registrant.
+    )]
+    pub candidate_fee_record: Account<'info, CandidateFee>, // Fee being refunded.
+    /// CHECK: System account `[b"treasury", poll]`; the program signs for it with its seeds.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: UncheckedAccount<'info>, // Pays the refund.
+    /// CHECK: Only receives lamports; must be the wallet on the record.
+    #[account(mut)]
+    pub wallet: UncheckedAccount<'info>, // Registrant being refunded.
+    pub system_program: Program<'info, System>, // Moves the refund out of the treasury.
+}
+
+/// Accounts needed to upgrade a v1 poll to the current layout.
+#[derive(Accounts)]
+pub struct MigratePoll<'info> {
+    /// CHECK: v1 layout, which `Account<Poll>` can't read; `migrate_poll` checks owner,
+    /// discriminator, authority and the title-seeded address by hand.
+    #[account(mut)]
+    pub poll: UncheckedAccount<'info>, // Poll account to upgrade in place.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays the extra rent.
+    pub system_program: Program<'info, System>, // Required for the rent top-up.
+}
+
+/// Accounts needed to close a voter record after a poll ends.
+#[derive(Accounts)]
+pub struct CloseVoter<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the record belongs to; read for end_ts.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        has_one = wallet, // Only the wallet that voted may close it.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump,
+        close = wallet // Rent goes back to the voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record to close.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet that cast the vote; receives the lamports.
+}
+
+/// Accounts needed to create a poll's receipt mint.
+#[derive(Accounts)]
+pub struct InitReceiptMint<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may add receipts.
+    pub poll: Account<'info, Poll>, // Poll getting receipts.
+    /// CHECK: `[b"receipt_mint", poll]`; created by the handler as an SPL Token mint.
+    #[account(mut, seeds = [b"receipt_mint", poll.key().as_ref()], bump)]
+    pub receipt_mint: UncheckedAccount<'info>, // Mint to create.
+    /// CHECK: PDA `[b"receipt", poll]`; mint and metadata update authority, only signs.
+    #[account(seeds = [b"receipt", poll.key().as_ref()], bump)]
+    pub receipt_authority: UncheckedAccount<'info>, // Signs mints.
+    /// CHECK: Metadata PDA of `receipt_mint`; checked by the handler, created by Token Metadata.
+    #[account(mut)]
+    pub metadata: UncheckedAccount<'info>, // Receipt name and URI.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays for the mint and metadata.
+    /// CHECK: Must be the SPL Token program (checked by the handler).
+    pub token_program: UncheckedAccount<'info>, // Owner of the mint.
+    /// CHECK: Must be the Token Metadata program (checked by the handler).
+    pub metadata_program: UncheckedAccount<'info>, // Creates the metadata.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to mint a voter's receipt.
+#[derive(Accounts)]
+pub struct ClaimReceipt<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll voted in; read-only.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        has_one = wallet, // Only the wallet that voted may claim.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, Voter>, // Record flagged once minted.
+    /// CHECK: Must be `poll.receipt_mint` (checked by the handler).
+    #[account(mut)]
+    pub receipt_mint: UncheckedAccount<'info>, // Receipt mint of this poll.
+    /// CHECK: PDA `[b"receipt", poll]`; mint authority, only signs.
+    #[account(seeds = [b"receipt", poll.key().as_ref()], bump)]
+    pub receipt_authority: UncheckedAccount<'info>, // Signs the mint.
+    /// CHECK: The wallet's associated token account for the mint (checked by the handler).
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>, // Receives the receipt; created if missing.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Voter; pays for the token account.
+    /// CHECK: Must be the SPL Token program.
+    #[account(address = token::spl_token::ID @ VotingError::InvalidTokenAccount)]
+    pub token_program: UncheckedAccount<'info>, // Owner of the mint.
+    /// CHECK: Must be the Associated Token Account program.
+    #[account(address = token::associated_token::ID @ VotingError::InvalidTokenAccount)]
+    pub associated_token_program: UncheckedAccount<'info>, // Creates the token account.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to sweep a stale voter record.
+#[derive(Accounts)]
+pub struct SweepVoter<'info> {
+    /// CHECK: The record's poll, read as a `Poll` if it still exists (or empty once closed).
+    #[account(address = voter.poll @ VotingError::AccountMismatch)]
+    pub poll: UncheckedAccount<'info>, // Poll the record belongs to.
+    #[account(
+        mut,
+        seeds = [b"voter", voter.poll.as_ref(), voter.wallet.as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump,
+        close = wallet // Rent (minus the bounty) goes back to the voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record to close.
+    /// CHECK: Only receives lamports; must be the wallet that voted.
+    #[account(mut, address = voter.wallet @ VotingError::AccountMismatch)]
+    pub wallet: UncheckedAccount<'info>, // Original voter.
+    #[account(mut)]
+    pub cranker: Signer<'info>, // Anyone; receives `VOTER_SWEEP_BOUNTY`.
+}
+
+/// Accounts needed to return escrowed tokens; no signer beyond the fee payer is required.
+#[derive(Accounts)]
+pub struct UnlockTokens<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll the tokens were locked for.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        seeds = [b"voter", poll.key().as_ref(), voter.wallet.as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, Voter>, // Record holding the locked amount.
+    /// CHECK: PDA `[b"escrow", poll]` that owns the vault; only signs the transfer.
+    #[account(seeds = [b"escrow", poll.key().as_ref()], bump)]
+    pub escrow_authority: UncheckedAccount<'info>, // Vault owner.
+    /// CHECK: Checked by `require_vault`.
+    #[account(mut)]
+    pub escrow_vault: UncheckedAccount<'info>, // Source of the returned tokens.
+    /// CHECK: Parsed by `token::unpack_token_account`; must be owned by `voter.wallet`.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>, // Voter's token account for the mint.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: UncheckedAccount<'info>, // Program owning the vault.
+}
+
+/// Accounts needed to fund a poll's voter rewards.
+#[derive(Accounts)]
+pub struct FundRewards<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may fund.
+    pub poll: Account<'info, Poll>, // Reward poll; tracks what the vault owes.
+    /// CHECK: Checked by `require_vault` (reward mint, owned by the poll's rewards PDA).
+    #[account(mut)]
+    pub reward_vault: UncheckedAccount<'info>, // Vault receiving the tokens.
+    /// CHECK: Authority's token account; the token program checks owner, mint and balance.
+    #[account(mut)]
+    pub source: UncheckedAccount<'info>, // Tokens being deposited.
+    pub authority: Signer<'info>, // Poll admin; owns `source`.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: UncheckedAccount<'info>, // Program owning the vault.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to pay a voter's reward; no signer beyond the fee payer is required.
+#[derive(Accounts)]
+pub struct ClaimReward<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Reward poll; tracks what the vault owes.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        seeds = [b"voter", poll.key().as_ref(), voter.wallet.as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, Voter>, // Record flagged once paid.
+    /// CHECK: PDA `[b"rewards", poll]` that owns the vault; only signs the transfer.
+    #[account(seeds = [b"rewards", poll.key().as_ref()], bump)]
+    pub rewards_authority: UncheckedAccount<'info>, // Vault owner.
+    /// CHECK: Checked by `require_vault`.
+    #[account(mut)]
+    pub reward_vault: UncheckedAccount<'info>, // Source of the reward.
+    /// CHECK: Must be the voter wallet's associated token account (checked by the handler).
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>, // Voter's ATA for the reward mint.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: UncheckedAccount<'info>, // Program owning the vault.
+}
+
+/// Accounts needed to take back unclaimed rewards.
+#[derive(Accounts)]
+pub struct WithdrawRewards<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may withdraw.
+    pub poll: Account<'info, Poll>, // Reward poll; tracks what the vault owes.
+    /// CHECK: PDA `[b"rewards", poll]` that owns the vault; only signs the transfer.
+    #[account(seeds = [b"rewards", poll.key().as_ref()], bump)]
+    pub rewards_authority: UncheckedAccount<'info>, // Vault owner.
+    /// CHECK: Checked by `require_vault`.
+    #[account(mut)]
+    pub reward_vault: UncheckedAccount<'info>, // Source of the tokens.
+    /// CHECK: Any token account for the mint; the token program checks it.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>, // Where the leftover goes.
+    pub authority: Signer<'info>, // Poll admin.
+    /// CHECK: Must be the SPL Token or Token-2022 program (checked by `token::transfer`).
+    pub token_program: UncheckedAccount<'info>, // Program owning the vault.
+}
+
+/// Accounts needed to cancel a poll before any vote lands.
+#[derive(Accounts)]
+pub struct CancelPoll<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may cancel.
+    pub poll: Account<'info, Poll>, // Poll being cancelled.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed to finalize a poll; no signer restriction beyond the fee payer.
+#[derive(Accounts)]
+pub struct FinalizePoll<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose results get frozen; pays the bounty.
+    #[account(mut)]
+    pub cranker: Signer<'info>, // Anyone (usually the fee payer); receives the finalize bounty.
+    /// CHECK: The `SlotHashes` sysvar, checked by address; parsed by `latest_slot_hash`.
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>, // Required for `TieBreak::Random` polls.
+}
+
+/// Accounts needed for the permissionless finalize crank.
+#[derive(Accounts)]
+pub struct CrankFinalize<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose results get frozen; pays the bounty.
+    #[account(mut)]
+    pub cranker: Signer<'info>, // Anyone; receives the finalize bounty.
+    /// CHECK: The `SlotHashes` sysvar, checked by address; parsed by `latest_slot_hash`.
+    #[account(address = slot_hashes::ID)]
+    pub slot_hashes: Option<UncheckedAccount<'info>>, // Required for `TieBreak::Random` polls.
+}
+
+/// Accounts needed to finalize a ranked-choice poll.
+#[derive(Accounts)]
+pub struct TallyIrv<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Ranked-choice poll whose results get frozen; pays the bounty.
+    #[account(seeds = [b"ranked", poll.key().as_ref()], bump = ranked_tally.bump, has_one = poll)]
+    pub ranked_tally: Box<Account<'info, RankedTally>>, // Preference counters filled by `vote_ranked`.
+    #[account(mut)]
+    pub cranker: Signer<'info>, // Anyone; receives the finalize bounty.
+}
+
+/// Accounts needed to create a poll's participation bitmap.
+#[derive(Accounts)]
+#[instruction(capacity: u32)]
+pub struct InitParticipation<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may switch tracking.
+    pub poll: Box<Account<'info, Poll>>, // Poll whose ballots the bitmap tracks.
+    #[account(
+        init,
+        payer = authority, // Authority funds the bitmap.
+        space = 8 + ParticipationBitmap::space(capacity), // Discriminator + one bit per slot.
+        seeds = [b"participation", poll.key().as_ref()], // One bitmap per poll.
+        bump
+    )]
+    pub participation: Account<'info, ParticipationBitmap>, // Bitmap to create.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays rent.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to vote on a poll with a participation bitmap; no Voter record is created.
+#[derive(Accounts)]
+pub struct VoteIndexed<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Box<Account<'info, Poll>>, // Poll being voted on.
+    /// CHECK: Read-only authority pubkey stored on the poll; no additional data is read or written.
+    pub authority: AccountInfo<'info>, // Authority pubkey stored in the poll.
+    #[account(mut, has_one = poll, seeds = [b"participation", poll.key().as_ref()], bump = participation.bump)]
+    pub participation: Account<'info, ParticipationBitmap>, // Bit per credential slot.
+    #[account(
+        seeds = [b"cred", poll.key().as_ref(), wallet.key().as_ref()], // Credential for this signer.
+        bump = credential.bump,
+        has_one = poll,
+        has_one = wallet
+    )]
+    pub credential: Account<'info, VoterCredential>, // Carries the slot (and weight).
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; pays the fee, if any.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    /// CHECK: System account `[b"treasury", poll]` receiving `vote_fee`.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll charges a fee.
+    pub system_program: Program<'info, System>, // Required for the fee transfer.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to vote on a receiptless poll; no Voter record is created.
+#[derive(Accounts)]
+pub struct VoteLight<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Box<Account<'info, Poll>>, // Poll being voted on; holds the spent-leaf bitmap.
+    /// CHECK: Read-only authority pubkey stored on the poll; no additional data is read or written.
+    pub authority: AccountInfo<'info>, // Authority pubkey stored in the poll.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; must match its allowlist leaf.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    /// CHECK: System account `[b"treasury", poll]` receiving `vote_fee`.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll charges a fee.
+    pub system_program: Program<'info, System>, // Required for the fee transfer.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to vote on a sharded poll; the poll itself stays read-only.
+#[derive(Accounts)]
+pub struct VoteSharded<'info> {
+    #[account(constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Box<Account<'info, Poll>>, // Poll being voted on (not write-locked).
+    #[account(mut, has_one = poll)] // Candidate and shard are checked by the handler.
+    pub tally: Account<'info, Tally>, // Only tally written by this ballot.
+    #[account(
+        init_if_needed, // Existing records are loaded so the handler can return `AlreadyVoted`.
+        payer = wallet, // Voter pays rent for their own record.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // PDA unique per (poll, wallet).
+        bump,
+        space = 8 + Voter::INIT_SPACE // Discriminator + size of Voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record PDA to mark participation.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet casting the vote; signs and funds the PDA.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    /// CHECK: System account `[b"treasury", poll]` receiving `vote_fee`.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll charges a fee.
+    pub system_program: Program<'info, System>, // Required for PDA creation.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to create one candidate's counter shards; they go in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct CreateTallyShards<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may prepare the poll.
+    pub poll: Account<'info, Poll>, // Sharded poll.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays for the shards.
+    pub system_program: Program<'info, System>, // Required for account creation.
+}
+
+/// Accounts needed to fold a sharded poll's tallies; Tally PDAs go in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct CollectTallies<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose `votes` get filled in.
+}
+
+/// Accounts needed to tabulate a hidden-tally poll; Voter records go in `remaining_accounts`.
+#[derive(Accounts)]
+pub struct Tabulate<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose tallies get filled in.
+}
+
+/// Accounts needed to propose or cancel a poll authority transfer.
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Current authority must sign.
+    pub poll: Account<'info, Poll>, // Poll whose admin changes.
+    pub authority: Signer<'info>, // Current poll admin.
+}
+
+/// Accounts needed to accept a poll's authority.
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll being handed over.
+    pub new_authority: Signer<'info>, // Must be `poll.pending_authority`.
+}
+
+/// Accounts needed to extend a poll's end time.
+#[derive(Accounts)]
+pub struct ExtendPoll<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may extend.
+    pub poll: Account<'info, Poll>, // Poll whose schedule changes.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed to pause or resume a poll.
+#[derive(Accounts)]
+pub struct PausePoll<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may pause/resume.
+    pub poll: Account<'info, Poll>, // Poll being paused or resumed.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed for authority edits to a poll before it opens.
+#[derive(Accounts)]
+pub struct UpdatePoll<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may edit.
+    pub poll: Account<'info, Poll>, // Poll being edited.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed for a wallet to register itself as a candidate.
+#[derive(Accounts)]
+pub struct RegisterCandidate<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll being entered.
+    /// CHECK: System account `[b"treasury", poll]`; only receives the fee.
+    #[account(mut, seeds = [b"treasury", poll.key().as_ref()], bump = poll.treasury_bump)]
+    pub treasury: Option<UncheckedAccount<'info>>, // Required when the poll has a `candidate_fee`.
+    #[account(
+        init,
+        payer = candidate, // The registrant pays the record's rent.
+        space = 8 + CandidateFee::INIT_SPACE, // Discriminator + size of CandidateFee.
+        seeds = [b"candidate_fee", poll.key().as_ref(), candidate.key().as_ref()], // One per wallet and poll.
+        bump // PDA bump supplied by Anchor.
+    )]
+    pub candidate_fee_record: Option<Account<'info, CandidateFee>>, // Required when the poll has a `candidate_fee`.
+    #[account(mut)]
+    pub candidate: Signer<'info>, // Wallet recorded in `candidate_wallets`; pays the fee.
+    pub system_program: Program<'info, System>, // Moves the fee and creates the record.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to reject a candidate, with its fee record on fee polls.
+#[derive(Accounts)]
+pub struct RejectCandidate<'info> {
+    #[account(
+        mut,
+        constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion, // Current layout only.
+        has_one = authority // Only the stored authority may reject.
+    )]
+    pub poll: Account<'info, Poll>, // Poll being edited.
+    #[account(mut)]
+    pub candidate_fee_record: Option<Account<'info, CandidateFee>>, // Required for a pending candidate of a fee poll.
+    pub authority: Signer<'info>, // Poll admin.
+}
+
+/// Accounts needed for text edits (title, description, names), which may need more room
+/// than a poll sized by `Poll::space` has.
+#[derive(Accounts)]
+pub struct EditPoll<'info> {
+    #[account(
+        mut,
+        constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion, // Current layout only.
+        has_one = authority, // Only the stored authority may edit.
+        realloc = 8 + Poll::max_space(poll.max_candidates), // Room for the longest texts.
+        realloc::payer = authority, // Authority covers any extra rent.
+        realloc::zero = false
+    )]
+    pub poll: Account<'info, Poll>, // Poll being edited.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays for realloc.
+    pub system_program: Program<'info, System>, // Required for realloc transfers.
+}
+
+/// Accounts needed to add a candidate before a poll opens.
+#[derive(Accounts)]
+pub struct AddCandidate<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may add candidates.
+    pub poll: Account<'info, Poll>, // Poll being edited.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays for the entry on write-in polls.
+    pub system_program: Program<'info, System>, // For growing the poll account.
+}
+
+/// Accounts needed to raise a poll's candidate capacity.
+#[derive(Accounts)]
+pub struct ExpandPoll<'info> {
+    #[account(mut, has_one = authority, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)] // Only the stored authority may expand.
+    pub poll: Account<'info, Poll>, // Poll being grown.
+    #[account(mut)]
+    pub authority: Signer<'info>, // Poll admin; pays the extra rent.
+    pub system_program: Program<'info, System>, // Required for the rent top-up.
+}
+
+/// Accounts needed to retract a vote while the poll is open.
+#[derive(Accounts)]
+pub struct RetractVote<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose tally is decremented.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        has_one = wallet, // Only the wallet that voted may retract.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump,
+        close = wallet // Rent goes back to the voter.
+    )]
+    pub voter: Account<'info, Voter>, // Voter record to close.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    #[account(mut, seeds = [b"ranked", poll.key().as_ref()], bump = ranked_tally.bump, has_one = poll)]
+    pub ranked_tally: Option<Box<Account<'info, RankedTally>>>, // Required on ranked-choice polls.
+    #[account(mut)]
+    pub wallet: Signer<'info>, // Wallet that cast the vote.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to switch an existing vote to another candidate.
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose tallies move.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        has_one = wallet, // Only the wallet that voted may change it.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // Same PDA as in `vote`.
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, Voter>, // Existing voter record.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    pub wallet: Signer<'info>, // Wallet that cast the vote.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// Accounts needed to reveal a committed ballot.
+#[derive(Accounts)]
+pub struct RevealVote<'info> {
+    #[account(mut, constraint = poll.version == POLL_VERSION @ VotingError::UnsupportedVersion)]
+    pub poll: Account<'info, Poll>, // Poll whose tally is incremented.
+    #[account(
+        mut,
+        has_one = poll, // Record must belong to this poll.
+        has_one = wallet, // Only the committing wallet may reveal.
+        seeds = [b"voter", poll.key().as_ref(), wallet.key().as_ref()], // Same PDA as in `commit_vote`.
+        bump = voter.bump
+    )]
+    pub voter: Account<'info, Voter>, // Record holding the commitment.
+    /// CHECK: Ban PDA for this signer; only checked to be empty.
+    #[account(seeds = [b"ban", poll.key().as_ref(), wallet.key().as_ref()], bump)]
+    pub ban: UncheckedAccount<'info>, // Exists only if the wallet is banned.
+    pub wallet: Signer<'info>, // Wallet that committed.
+    /// CHECK: `[b"config"]`; may not exist. Read only for the protocol pause switch.
+    #[account(seeds = [b"config"], bump)]
+    pub protocol_config: UncheckedAccount<'info>, // Refuses the call while the protocol is paused.
+}
+
+/// On-chain poll configuration and results. `#[max_len]` bounds are what `INIT_SPACE`
+/// budgets: `MAX_TITLE_BYTES` title, 256-byte description, 8 names of `MAX_NAME_BYTES`.
+#[account]
+#[derive(InitSpace, Default)]
+pub struct Poll {
+    pub version: u8,            // Layout version (`POLL_VERSION`).
+    pub seq: u64,               // Authority's counter value used in the PDA seeds.
+    pub authority: Pubkey,      // Poll admin.
+    #[max_len(MAX_TITLE_BYTES)]
+    pub title: String,          // Poll title.
+    #[max_len(256)]
+    pub description: String,    // What is being decided (may be empty).
+    #[max_len(8, MAX_NAME_BYTES)]
+    pub candidates: Vec<String>,// Candidate names.
+    #[max_len(8)]
+    pub votes: Vec<u64>,        // Vote counts aligned with candidates.
+    pub start_ts: i64,          // Start time (unix).
+    pub end_ts: i64,            // End time (unix).
+    pub bump: u8,               // PDA bump for poll account.
+    pub cancelled: bool,        // Set by `cancel_poll`; blocks voting.
+    pub finalized: bool,        // Set by `finalize_poll`; results are frozen.
+    pub winner_idx: Option<u8>, // Winning candidate once finalized (None on tie / no votes).
+    pub is_tie: bool,           // True when several candidates share the top count.
+    pub paused: bool,           // Set by `pause_poll`; blocks voting until resumed.
+    pub finalize_bounty: u64,   // Lamports paid to whoever runs `crank_finalize`.
+    pub allowlist_required: bool, // Voting requires a `VoterCredential` issued by the authority.
+    pub allowlist_root: Option<[u8; 32]>, // Merkle root of eligible wallets (see `merkle`).
+    pub gate_mint: Option<Pubkey>, // Voting requires holding this token.
+    pub min_balance: u64,       // Minimum raw `gate_mint` balance to vote.
+    pub gate_collection: Option<Pubkey>, // Voting requires an NFT from this verified collection.
+    pub min_lamports: u64,      // Minimum SOL balance (after voter rent) to vote; 0 = no check.
+    pub weighting: VoteWeighting, // How much each ballot adds to a tally.
+    pub weight_root: Option<[u8; 32]>, // Merkle root of snapshot `(wallet, balance)` pairs.
+    pub escrow_locked: u64,     // Tokens still held in escrow; the poll can't close while > 0.
+    pub max_voters: u32,        // Participant cap (0 = unlimited); reaching it allows early finalize.
+    pub voter_count: u32,       // Wallets with a ballot currently recorded (one per Voter record).
+    pub authority_can_vote: bool, // False rejects ballots recorded for the authority wallet.
+    pub votes_per_wallet: u8,   // Ballots each wallet may cast (1 = classic).
+    pub allow_repeat_candidate: bool, // Whether those ballots may pick the same candidate twice.
+    pub registrar: Option<Pubkey>, // Identity registrar; if set, voters need its attestation.
+    pub ballot_type: BallotType, // Shape of a ballot and how it is tallied.
+    pub allow_underspend: bool, // Cumulative polls: may a ballot use less than the budget?
+    pub kind: PollKind,         // Election or Yes/No referendum.
+    pub passed: bool,           // Referendums: set at finalize when yes-votes met the threshold.
+    pub allow_abstain: bool,    // Whether `vote_abstain` is accepted.
+    pub abstentions: u64,       // Wallets that abstained (not in `votes`).
+    pub allow_downvotes: bool,  // Whether `vote_against` is accepted.
+    #[max_len(8)]
+    pub downvotes: Vec<u64>,    // Weight voted against each candidate, aligned with `votes`.
+    pub num_winners: u8,        // Seats to fill (1 = single winner).
+    #[max_len(8)]
+    pub winners: Vec<u8>,       // Set at finalize: seated candidates, best first.
+    pub seat_tie: bool,         // Set at finalize: the last seat is tied (`winners` holds all tied).
+    pub majority_bps: u16,      // Leader share needed to avoid a runoff (0 = no runoffs).
+    pub runoff_of: Option<Pubkey>, // First-round poll this runoff was created from.
+    pub runoff: Option<Pubkey>, // Runoff created from this poll, if any.
+    pub allow_write_ins: bool,  // Whether `vote_write_in` may add candidates during voting.
+    pub commit_reveal: bool,    // Secret ballots: `commit_vote`, then `reveal_vote` after end_ts.
+    pub reveal_deadline_ts: i64, // Commit-reveal polls: last moment to reveal; finalize after it.
+    pub hide_tallies: bool,     // `votes` stays zero during voting; filled by `tabulate`.
+    pub tabulated: u32,         // Hidden-tally polls: Voter records counted so far.
+    pub quorum: u64,            // Minimum turnout for a valid result (0 = none).
+    pub turnout: u64,           // Total weight of those wallets (once each, however many candidates they back).
+    pub quorum_met: bool,       // Set at finalize; results without quorum are flagged, not discarded.
+    pub tie_break: TieBreak,    // How finalize settles a tie for first place.
+    #[max_len(8)]
+    pub last_increment_ts: Vec<i64>, // When each candidate's count last changed, aligned with `votes`.
+    pub registration_end_ts: i64, // Last moment for `register_candidate` (0 = no self-registration).
+    #[max_len(8)]
+    pub candidate_wallets: Vec<Pubkey>, // Who entered each candidate, aligned with `candidates`.
+    #[max_len(8)]
+    pub approved: Vec<bool>,    // Ballots may name the candidate; false = pending self-registration.
+    pub sharded: bool,          // Ballots go to per-candidate Tally PDAs (`vote_sharded`).
+    pub collected: bool,        // Sharded polls: `collect_tallies` has filled `votes`.
+    pub tally_shards: u8,       // Sharded polls: counters per candidate.
+    pub shard_mask: u8,         // Sharded polls: bit i set once candidate i's counters exist.
+    pub folded_shards: u16,     // Sharded polls: counters collected so far.
+    pub max_candidates: u8,     // Candidate cap; the account has room for this many full names (`expand_poll` raises it).
+    pub poll_id: Option<u64>,   // Sequential id from the `Registry` (None if created without it).
+    pub first_vote_ts: i64,     // When the first ballot was cast (0 = none yet).
+    pub last_vote_ts: i64,      // When the latest ballot was cast.
+    pub hourly_votes: [u32; 24], // Ballots cast per UTC hour of day, all days combined.
+    pub receiptless_voters: u16, // >0: ballots via `vote_light`, allowlist leaves 0..this (no Voter records).
+    pub ballot_hash: [u8; 32],  // Receiptless polls: hash chain over every ballot (`ballot_chain_hash`).
+    #[max_len(RECEIPTLESS_BITMAP_BYTES)]
+    pub spent_leaves: Vec<u8>,  // Receiptless polls: bit i set once leaf i has voted.
+    pub participation: Option<Pubkey>, // `ParticipationBitmap` replacing Voter PDAs, if created.
+    pub vote_fee: u64,          // Lamports each ballot pays into the treasury PDA (0 = none).
+    pub treasury_bump: u8,      // Bump of `[b"treasury", poll]` (fee polls only).
+    pub fee_beneficiary: Pubkey, // Only recipient of `withdraw_treasury` (the authority unless set).
+    pub creation_deposit: u64,   // Protocol deposit still locked in this account; blocks `close_poll`.
+    pub deposit_min_ballots: u32, // Ballots needed at finalize to release the deposit.
+    pub prize_total: u64,        // Lamports in the prize pool; blocks `close_poll` until paid or refunded.
+    pub reward_mint: Option<Pubkey>, // Token paid to each voter by `claim_reward`, if any.
+    pub reward_amount: u64,      // Raw amount of `reward_mint` per Voter record.
+    pub voter_retention_secs: u32, // Voter records may be swept this long after voting ends.
+    pub receipt_mint: Option<Pubkey>, // Participation receipt mint, once `init_receipt_mint` ran.
+    pub candidate_fee: u64,      // Lamports `register_candidate` pays into the treasury (0 = free).
+    pub candidate_refunds_owed: u64, // Fees of rejected candidates not yet refunded; blocks `close_poll`.
+    pub fee_mint: Option<Pubkey>, // Token each ballot pays `fee_amount` of instead of `vote_fee`.
+    pub fee_amount: u64,         // Raw amount of `fee_mint` per ballot.
+    pub fee_vault: Pubkey,       // ATA of `[b"fees", poll]` collecting token fees (token-fee polls only).
+    pub burn_mint: Option<Pubkey>, // Token each voter burns `burn_amount` of to vote, if any.
+    pub burn_amount: u64,        // Raw amount of `burn_mint` burned per Voter record.
+    pub total_burned: u64,       // Sum of every Voter record's `burned`.
+    pub finalized_at: i64,       // Unix time of finalize; starts `DISPUTE_WINDOW_SECS`.
+    pub disputed: bool,          // Result contested (`flag_dispute`); blocks payout until resolved.
+    pub dispute_reason: u8,      // `reason_code` of the last dispute.
+    pub voided: bool,            // Result thrown out by `resolve_dispute`; never paid out.
+    pub certifier: Option<Pubkey>, // Must co-sign results (`certify_results`) before payout or runoff, if set.
+    pub certification_deadline_ts: i64, // After this, results count as certified without the certifier.
+    pub certified: bool,         // Set by `certify_results`.
+    pub results_hash: [u8; 32],  // Commitment to the outcome written at finalize (see `results_hash`); zero before.
+    pub pending_authority: Option<Pubkey>, // Set by `propose_authority` until it signs `accept_authority`.
+    pub rewards_outstanding: u64, // Reward tokens funded and not yet claimed or withdrawn; blocks `close_poll`.
+}
+impl Poll {
+    /// Bytes each candidate adds at most: name (4 + MAX_NAME_BYTES) + votes + downvotes
+    /// + winners + last_increment_ts + candidate_wallets + approved.
+    const PER_CANDIDATE: usize = 4 + MAX_NAME_BYTES + 8 + 8 + 1 + 8 + 32 + 1;
+
+    /// Data size (without discriminator) of a poll with these texts and candidates plus room
+    /// for full-length entries up to `max_candidates` and a spent-leaf bitmap for
+    /// `receiptless_voters`; `INIT_SPACE` for the longest title, description, 8 longest names
+    /// and the largest bitmap. Candidate additions then always fit; text edits reallocate to
+    /// `max_space`.
+    pub fn space<S: AsRef<str>>(
+        title: &str,
+        description: &str,
+        candidates: &[S],
+        max_candidates: u8,
+        receiptless_voters: u16,
+    ) -> usize {
+        let fixed = Self::INIT_SPACE - MAX_TITLE_BYTES - 256 - 8 * Self::PER_CANDIDATE - RECEIPTLESS_BITMAP_BYTES; // Everything that doesn't vary.
+        let names: usize = candidates.iter().map(|c| c.as_ref().len()).sum();
+        let room = (max_candidates as usize).saturating_sub(candidates.len()) * Self::PER_CANDIDATE; // Future entries.
+        let bitmap = (receiptless_voters as usize).div_ceil(8); // One bit per leaf index.
+        fixed + title.len() + description.len() + candidates.len() * (Self::PER_CANDIDATE - MAX_NAME_BYTES) + names + room + bitmap
+    }
+
+    /// Largest data size of a poll with room for `max_candidates`: `INIT_SPACE` up to 8,
+    /// plus a full entry per candidate beyond that (see `expand_poll`).
+    pub fn max_space(max_candidates: u8) -> usize {
+        Self::INIT_SPACE + (max_candidates as usize).saturating_sub(8) * Self::PER_CANDIDATE
+    }
+
+    /// `results_hash` of this poll's current outcome; `key` is the poll account's address.
+    pub fn outcome_hash(&self, key: &Pubkey) -> [u8; 32] {
+        results_hash(key, &self.candidates, &self.votes, self.voter_count, self.end_ts)
+    }
+
+    /// Fails unless ballots may be cast or changed right now:
+    /// not cancelled, finalized, or paused, and start_ts <= now <= end_ts.
+    pub fn require_voting_open(&self, now: i64) -> Result<()> {
+        self.require_consistent()?; // Indexing below trusts the aligned vectors.
+        require!(!self.cancelled, VotingError::PollCancelled); // Cancelled polls take no votes.
+        require!(!self.finalized, VotingError::AlreadyFinalized); // Results are frozen.
+        require!(!self.paused, VotingError::PollPaused); // Kill switch engaged.
+        require!(now >= self.start_ts, VotingError::TooEarly); // Window not open yet.
+        require!(now <= self.end_ts, VotingError::Closed); // Window already over.
+        require!(now > self.registration_end_ts, VotingError::RegistrationOpen); // Field is settled first.
+        require!(
+            self.candidates.len() >= 2 && self.candidates.len() > self.num_winners as usize,
+            VotingError::NotEnoughCandidates
+        ); // Registration may have left too few.
+        require!(
+            self.ballot_type == BallotType::Plurality || self.approved.iter().all(|a| *a),
+            VotingError::CandidateNotApproved
+        ); // Ranked/scored ballots cover the whole list, so it must be settled.
+        Ok(())
+    }
+
+    /// Fails with `CorruptPoll` unless every per-candidate vector has one entry per candidate,
+    /// so a bad layout surfaces as an error instead of an index panic or a shifted tally.
+    pub fn require_consistent(&self) -> Result<()> {
+        let n = self.candidates.len();
+        require!(
+            self.votes.len() == n
+                && self.downvotes.len() == n
+                && self.last_increment_ts.len() == n
+                && self.candidate_wallets.len() == n
+                && self.approved.len() == n,
+            VotingError::CorruptPoll
+        );
+        Ok(())
+    }
+
+    /// Fails unless `register_candidate` is accepted: not cancelled, at or before
+    /// `registration_end_ts` (never without self-registration).
+    pub fn require_registration_open(&self, now: i64) -> Result<()> {
+        self.require_consistent()?; // Approvals index the aligned vectors.
+        require!(!self.cancelled, VotingError::PollCancelled); // Cancelled polls are frozen.
+        require!(now <= self.registration_end_ts, VotingError::RegistrationClosed); // Deadline 0 = disabled.
+        Ok(())
+    }
+
+    /// True when results may be consumed: no certifier, certified, or the certifier missed
+    /// `certification_deadline_ts`.
+    pub fn results_certified(&self, now: i64) -> bool {
+        self.certifier.is_none() || self.certified || now > self.certification_deadline_ts
+    }
+
+    /// True when the prize pool goes back to its funders: cancelled, voided, or finalized
+    /// short of quorum, tied, or without votes. Never while a dispute is open.
+    pub fn prize_refundable(&self) -> bool {
+        !self.disputed
+            && (self.cancelled
+                || self.voided
+                || (self.finalized && (!self.quorum_met || self.is_tie || self.winner_idx.is_none())))
+    }
+
+    /// Fails unless a single-candidate ballot may name `candidate_idx`: in range and approved.
+    pub fn require_candidate(&self, candidate_idx: u8) -> Result<()> {
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < self.candidates.len(), VotingError::BadCandidate); // Validate index in range.
+        require!(self.approved[idx], VotingError::CandidateNotApproved); // Pending registration.
+        Ok(())
+    }
+
+    /// Drops candidate `idx` along with its tallies, timestamps, wallet and approval flag.
+    fn remove_candidate_at(&mut self, idx: usize) {
+        self.candidates.remove(idx);
+        self.votes.remove(idx);
+        self.downvotes.remove(idx);
+        self.last_increment_ts.remove(idx);
+        self.candidate_wallets.remove(idx);
+        self.approved.remove(idx);
+    }
+
+    /// Fails unless the poll is still being set up: not cancelled, before start_ts, no votes.
+    pub fn require_setup_phase(&self, now: i64) -> Result<()> {
+        self.require_consistent()?; // Candidate edits keep the vectors aligned.
+        require!(!self.cancelled, VotingError::PollCancelled); // Cancelled polls are frozen.
+        require!(now < self.start_ts, VotingError::PollStarted); // Voting not open yet.
+        require!(!self.has_votes(), VotingError::VotesAlreadyCast); // No ballots yet.
+        Ok(())
+    }
+
+    /// Fails unless `set_allowlist_root` may store `root`: sharded polls take no merkle
+    /// allowlist (`ShardedOptions`), as `vote_sharded` never checks one, and neither do
+    /// participation-bitmap polls (`ParticipationOptions`), whose `vote_indexed` relies on
+    /// credentials. Receiptless polls need one, and it is pinned once a leaf is spent, as
+    /// `spent_leaves` indexes its leaves (`ReceiptlessOptions`).
+    fn require_allowlist_root(&self, root: &Option<[u8; 32]>) -> Result<()> {
+        require!(!self.sharded || root.is_none(), VotingError::ShardedOptions); // Open electorate only.
+        require!(self.participation.is_none() || root.is_none(), VotingError::ParticipationOptions); // Credentials only.
+        if self.receiptless_voters > 0 {
+            require!(root.is_some(), VotingError::ReceiptlessOptions); // `vote_light` proves leaves of it.
+            require!(
+                self.spent_leaves.iter().all(|&b| b == 0) || *root == self.allowlist_root,
+                VotingError::ReceiptlessOptions
+            ); // Spent bits belong to the old tree.
+        }
+        Ok(())
+    }
+
+    /// Marks leaf `leaf_index` of a receiptless poll spent: `AlreadyVoted` if it was, and
+    /// `InvalidProof` past `receiptless_voters`.
+    fn spend_leaf(&mut self, leaf_index: u16) -> Result<()> {
+        require!(leaf_index < self.receiptless_voters, VotingError::InvalidProof); // Covered by the bitmap.
+        let (byte, bit) = (leaf_index as usize / 8, 1u8 << (leaf_index % 8));
+        require!(self.spent_leaves[byte] & bit == 0, VotingError::AlreadyVoted); // Leaf used once.
+        self.spent_leaves[byte] |= bit;
+        Ok(())
+    }
+
+    /// Copies the settings chosen in `PollOptions` (validated by the caller) onto the poll.
+    fn apply_options(&mut self, options: &PollOptions) {
+        self.finalize_bounty = options.finalize_bounty_lamports; // Reward for whoever cranks finalize.
+        self.allowlist_required = options.allowlist_required; // Only credentialed wallets may vote.
+        self.allowlist_root = options.allowlist_root; // Merkle root of eligible wallets, if any.
+        self.gate_mint = options.gate_mint; // Token holders only, if set.
+        self.min_balance = options.min_balance; // Minimum raw balance of `gate_mint`.
+        self.gate_collection = options.gate_collection; // Verified NFT collection holders only, if set.
+        self.min_lamports = options.min_lamports; // Sybil filter on voter SOL balance; 0 disables.
+        self.weighting = options.weighting; // How much each ballot counts.
+        self.weight_root = options.weight_root; // Snapshot balances root; may also come later.
+        self.max_voters = options.max_voters; // Participant cap; 0 = unlimited.
+        self.authority_can_vote = options.authority_can_vote; // Whether the organizer may vote.
+        self.registrar = options.registrar; // Identity registrar whose attestations are required.
+        self.votes_per_wallet = options.votes_per_wallet; // Ballots each wallet may cast.
+        self.allow_repeat_candidate = options.allow_repeat_candidate; // Several ballots for one candidate?
+        self.ballot_type = options.ballot_type; // Plurality, approval, ...
+        self.allow_underspend = options.allow_underspend; // Cumulative ballots may keep points back.
+        self.kind = options.kind; // Election or Yes/No referendum.
+        self.allow_abstain = options.allow_abstain; // Formal abstentions allowed?
+        self.allow_downvotes = options.allow_downvotes; // Votes against count towards a net score.
+        self.num_winners = options.num_winners; // Seats filled at finalize.
+        self.majority_bps = options.majority_bps; // Leader share below which `create_runoff` works.
+        self.allow_write_ins = options.allow_write_ins; // Voters may add candidates while voting.
+        self.commit_reveal = options.commit_reveal; // Secret ballots, revealed after end_ts.
+        self.reveal_deadline_ts = options.reveal_deadline_ts; // End of the reveal phase.
+        self.hide_tallies = options.hide_tallies; // `votes` stays zero until `tabulate`.
+        self.quorum = options.quorum; // Checked against `turnout` at finalize.
+        self.tie_break = options.tie_break; // Applied only when first place is tied.
+        self.registration_end_ts = options.registration_end_ts; // Voting waits for it.
+        self.sharded = options.sharded; // Tally PDAs made by `init_poll` / `create_tally_shards`.
+        self.tally_shards = options.tally_shards; // 1 unless sharded.
+        self.max_candidates = options.max_candidates; // Cap for add_candidate / write-ins / registration.
+        self.vote_fee = options.vote_fee_lamports; // Charged per ballot by every ballot instruction.
+        self.receiptless_voters = options.receiptless_voters; // `vote_light` instead of Voter records.
+        self.reward_mint = options.reward_mint; // Voter rewards, claimed per Voter record.
+        self.reward_amount = options.reward_amount;
+        self.voter_retention_secs = options.voter_retention_secs; // Before `sweep_voter` may run.
+        self.candidate_fee = options.candidate_fee_lamports; // Taken by `register_candidate`.
+        self.fee_mint = options.fee_mint; // Token vote fee; `fee_vault` is set by `create_poll`.
+        self.fee_amount = options.fee_amount;
+        self.burn_mint = options.burn_mint; // Burn-to-vote, done by `Vote::admit`.
+        self.burn_amount = options.burn_amount;
+        self.certifier = options.certifier; // Co-signer of results, if any.
+        self.certification_deadline_ts = options.certification_deadline_ts;
+        self.spent_leaves = vec![0; (options.receiptless_voters as usize).div_ceil(8)]; // No leaf spent yet.
+    }
+
+    /// Index of the existing candidate a write-in `name` joins ("alice " joins "Alice"), or
+    /// `None` when it is new and there is still room under `max_candidates`.
+    pub fn write_in_candidate(&self, name: &str) -> Result<Option<u8>> {
+        validate_candidate_name(name)?; // Same rules as init_poll.
+        if let Some(idx) = self.candidates.iter().position(|c| same_candidate(c, name)) {
+            return Ok(Some(idx as u8));
+        }
+        require!(
+            self.candidates.len() < self.max_candidates as usize,
+            VotingError::TooManyCandidates
+        ); // Cap chosen at init.
+        Ok(None)
+    }
+
+    /// Moves `end_ts` forward to `new_end_ts` for `extend_poll` and `resume_poll`: keeps the
+    /// voting window within `MAX_DURATION_SECS` and shifts the reveal and certification
+    /// deadlines by the same amount, so the phases after voting keep their length. Fails once
+    /// `tabulate` has counted records or `collect_tallies` has folded shards, since reopened
+    /// ballots would no longer match them.
+    fn move_end(&mut self, new_end_ts: i64) -> Result<()> {
+        require!(self.tabulated == 0, VotingError::AlreadyCounted); // Counting has started.
+        require!(!self.collected && self.folded_shards == 0, VotingError::AlreadyCounted); // Shards are in.
+        let duration = new_end_ts.checked_sub(self.start_ts).ok_or(VotingError::Overflow)?;
+        require!(duration <= MAX_DURATION_SECS, VotingError::DurationTooLong); // Same cap as at init.
+        let shift = new_end_ts.checked_sub(self.end_ts).ok_or(VotingError::Overflow)?;
+        if self.commit_reveal {
+            self.reveal_deadline_ts = self.reveal_deadline_ts.checked_add(shift).ok_or(VotingError::Overflow)?;
+        }
+        if self.certifier.is_some() {
+            self.certification_deadline_ts = self
+                .certification_deadline_ts
+                .checked_add(shift)
+                .ok_or(VotingError::Overflow)?;
+        }
+        self.end_ts = new_end_ts;
+        Ok(())
+    }
+
+    /// True when the poll was created with a treasury PDA (vote or candidate fees).
+    pub fn has_treasury(&self) -> bool {
+        self.vote_fee > 0 || self.candidate_fee > 0
+    }
+
+    /// True once any tally (for or against) is non-zero.
+    pub fn has_votes(&self) -> bool {
+        self.votes.iter().chain(&self.downvotes).any(|v| *v != 0)
+    }
+    /// True when `turnout` (ballots, or weight in weighted polls) reached `quorum`; quorum 0
+    /// is always met.
+    pub fn meets_quorum(&self) -> bool {
+        self.turnout >= self.quorum
+    }
+    /// Fails unless `cancel_poll` may cancel this poll at `now`: not cancelled or finalized
+    /// yet and without ballots; a sharded poll only before `start_ts`, as its shards may hold
+    /// ballots the poll doesn't see.
+    pub fn require_cancellable(&self, now: i64) -> Result<()> {
+        require!(!self.cancelled, VotingError::PollCancelled); // Cancel only once.
+        require!(!self.finalized, VotingError::AlreadyFinalized); // Results already stand.
+        require!(!self.has_votes() && self.voter_count == 0, VotingError::VotesAlreadyCast); // No ballots yet.
+        require!(!self.sharded || now < self.start_ts, VotingError::VotesAlreadyCast); // Shards may hold ballots.
+        Ok(())
+    }
+    /// Fails unless `close_poll` may delete this poll at `now`: cancelled, or over, including
+    /// the reveal phase of a commit-reveal poll (`PollStillOpen`); a hidden-tally poll only once
+    /// finalized (`NotFinalized`), so its tabulated result gets published; and with nothing left
+    /// that needs the account (locked tokens, deposit, prize pool, candidate refunds, reward
+    /// tokens).
+    pub fn require_closable(&self, now: i64) -> Result<()> {
+        let over = now > self.end_ts && (!self.commit_reveal || now > self.reveal_deadline_ts); // Reveals need the poll.
+        require!(self.cancelled || over, VotingError::PollStillOpen);
+        require!(!self.hide_tallies || self.cancelled || self.finalized, VotingError::NotFinalized); // Tabulate and finalize first.
+        require!(self.escrow_locked == 0, VotingError::TokensLocked); // Unlocks need the poll.
+        require!(self.creation_deposit == 0, VotingError::DepositHeld); // Released or swept first.
+        require!(self.prize_total == 0, VotingError::PrizeUnpaid); // The pool needs the poll.
+        require!(self.candidate_refunds_owed == 0, VotingError::CandidateRefundsOwed); // Refunds need the poll.
+        require!(self.rewards_outstanding == 0, VotingError::RewardsNotWithdrawn); // Claims and withdrawals need the poll.
+        Ok(())
+    }
+    /// Index of the candidate `payout_prize` may pay at `now`. The result must be final,
+    /// certified, and past `DISPUTE_WINDOW_SECS` unless the certifier signed it
+    /// (`DisputeWindowOpen`). Disputed results wait for `resolve_dispute`; voided, tied or
+    /// short-of-quorum results never pay out.
+    pub fn require_payable(&self, now: i64) -> Result<usize> {
+        require!(self.finalized, VotingError::NotFinalized); // Winner not known yet.
+        require!(self.results_certified(now), VotingError::NotCertified); // Certifier hasn't signed yet.
+        require!(!self.voided, VotingError::ResultVoided); // Funders get refunds instead.
+        require!(!self.disputed, VotingError::ResultDisputed); // Wait for `resolve_dispute`.
+        let window_end = self.finalized_at.checked_add(DISPUTE_WINDOW_SECS).ok_or(VotingError::Overflow)?;
+        require!(self.certified || now > window_end, VotingError::DisputeWindowOpen); // Still contestable.
+        require!(self.quorum_met, VotingError::QuorumNotMet); // Funders get refunds instead.
+        require!(!self.is_tie, VotingError::PrizeTied);
+        let winner = self.winner_idx.ok_or(VotingError::NoWinner)?; // No votes: no winner.
+        Ok(winner as usize)
+    }
+    /// Stamps a ballot cast at `now` into the activity statistics.
+    fn record_activity(&mut self, now: i64) -> Result<()> {
+        if self.first_vote_ts == 0 {
+            self.first_vote_ts = now;
+        }
+        self.last_vote_ts = now;
+        let hour = (now.rem_euclid(86_400) / 3_600) as usize; // UTC hour of day.
+        self.hourly_votes[hour] = self.hourly_votes[hour].checked_add(1).ok_or(VotingError::Overflow)?;
+        Ok(())
+    }
+
+    /// True once `max_voters` wallets have voted (never for uncapped polls).
+    pub fn is_full(&self) -> bool {
+        self.max_voters > 0 && self.voter_count >= self.max_voters
+    }
+
+    /// True when a delegate can cast the ballot without any of the delegator's own accounts:
+    /// no token/NFT/SOL/identity gates and a weighting that doesn't read live holdings.
+    pub fn supports_delegation(&self) -> bool {
+        self.gate_mint.is_none()
+            && self.gate_collection.is_none()
+            && self.registrar.is_none()
+            && self.min_lamports == 0
+            && matches!(
+                self.weighting,
+                VoteWeighting::Equal | VoteWeighting::Credential | VoteWeighting::Snapshot { .. }
+            )
+    }
+}
+
+/// Poll layout before `version` existed (v1), read only by `migrate_poll`.
+#[derive(AnchorDeserialize)]
+struct PollV1 {
+    authority: Pubkey,
+    title: String,
+    candidates: Vec<String>,
+    votes: Vec<u64>,
+    start_ts: i64,
+    end_ts: i64,
+    bump: u8,
+    cancelled: bool,
+    finalized: bool,
+    winner_idx: Option<u8>,
+    is_tie: bool,
+    paused: bool,
+    finalize_bounty: u64,
+}
+impl PollV1 {
+    /// Decodes a v1 poll account: the `Poll` discriminator followed by the v1 fields
+    /// (`UnsupportedVersion` otherwise).
+    fn read(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() > 8 && data[..8] == <Poll as Discriminator>::DISCRIMINATOR,
+            VotingError::UnsupportedVersion
+        ); // A poll of some version.
+        Self::deserialize(&mut &data[8..]).map_err(|_| VotingError::UnsupportedVersion.into())
+    }
+
+    /// The same poll in the current layout at address `key`: every newer setting at its
+    /// default, aligned vectors filled in and one weight-1 ballot per counted vote.
+    fn upgrade(self, key: &Pubkey) -> Result<Poll> {
+        let n = self.candidates.len();
+        let ballots: u64 = self.votes.iter().sum(); // One weight-1 ballot per wallet.
+        let mut poll = Poll {
+            version: POLL_VERSION,
+            authority: self.authority,
+            title: self.title,
+            candidates: self.candidates,
+            votes: self.votes,
+            start_ts: self.start_ts,
+            end_ts: self.end_ts,
+            bump: self.bump,
+            cancelled: self.cancelled,
+            finalized: self.finalized,
+            winner_idx: self.winner_idx,
+            is_tie: self.is_tie,
+            paused: self.paused,
+            ..Poll::default()
+        };
+        poll.apply_options(&PollOptions::default()); // Classic behavior for every newer setting.
+        poll.finalize_bounty = self.finalize_bounty; // Still parked in the account.
+        poll.downvotes = vec![0; n]; // Aligned vectors the v1 layout lacked.
+        poll.last_increment_ts = vec![0; n];
+        poll.candidate_wallets = vec![self.authority; n];
+        poll.approved = vec![true; n];
+        poll.voter_count = u32::try_from(ballots).map_err(|_| VotingError::Overflow)?;
+        poll.turnout = ballots;
+        if poll.finalized {
+            poll.winners = poll.winner_idx.into_iter().collect(); // Single seat.
+            poll.quorum_met = true; // No quorum existed.
+            poll.results_hash = poll.outcome_hash(key);
+        }
+        Ok(poll)
+    }
+}
+
+/// Per-authority counter that hands out poll PDA seeds.
+#[account]
+pub struct PollCounter {
+    pub authority: Pubkey, // Wallet the counter belongs to.
+    pub count: u64,        // Number of polls created so far (= next seq).
+    pub bump: u8,          // PDA bump for counter account.
+}
+impl PollCounter {
+    /// Size calculation for the PollCounter account (without discriminator).
+    pub const SIZE: usize = 32 + 8 + 1; // authority + count + bump
+}
+
+/// Program-wide poll index, PDA `[b"registry"]`: hands out sequential `poll_id`s and keeps the
+/// newest `REGISTRY_RECENT` poll addresses, so clients can find polls without scanning.
+#[account]
+#[derive(InitSpace)]
+pub struct Registry {
+    pub poll_count: u64,                   // Polls registered so far (= next `poll_id`).
+    pub recent: [Pubkey; REGISTRY_RECENT], // Ring buffer: poll `id` sits at `id % REGISTRY_RECENT`.
+    pub bump: u8,                          // PDA bump for registry account.
+}
+impl Registry {
+    /// Assigns the next id to `poll` and records its address in the ring buffer.
+    fn register(&mut self, poll: Pubkey) -> Result<u64> {
+        let id = self.poll_count;
+        self.recent[(id % REGISTRY_RECENT as u64) as usize] = poll; // Overwrites the oldest entry.
+        self.poll_count = id.checked_add(1).ok_or(VotingError::Overflow)?;
+        Ok(id)
+    }
+
+    /// Address of poll `id` while it is among the newest `REGISTRY_RECENT`.
+    pub fn recent_poll(&self, id: u64) -> Option<Pubkey> {
+        (id < self.poll_count && self.poll_count - id <= REGISTRY_RECENT as u64)
+            .then(|| self.recent[(id % REGISTRY_RECENT as u64) as usize])
+    }
+}
+
+/// The polls one authority created with the index passed, oldest first, PDA
+/// `[b"index", authority]`. Sized for the current list and grown one entry per poll.
+#[account]
+pub struct AuthorityIndex {
+    pub authority: Pubkey,  // Creator these polls belong to.
+    pub bump: u8,           // PDA bump for index account.
+    pub polls: Vec<Pubkey>, // Poll addresses, at most `MAX_INDEXED_POLLS`.
+}
+impl AuthorityIndex {
+    /// authority + bump + polls (4 + n*32).
+    pub fn space(n: usize) -> usize {
+        32 + 1 + 4 + n * 32
+    }
+}
+
+/// Protocol-wide settings, PDA `[b"config"]`: the fee every poll creation pays, if deployed.
+#[account]
+#[derive(InitSpace)]
+pub struct Config {
+    pub admin: Pubkey,                  // Protocol admin allowed to `update_config` and `transfer_admin`.
+    pub fee_lamports: u64,              // Charged to the creator of each poll; 0 = free.
+    pub fee_recipient: Pubkey,          // Receives the fees and swept deposits.
+    pub creation_deposit_lamports: u64, // Locked in each `init_poll` poll until finalized; 0 = none.
+    pub deposit_min_ballots: u32,       // Ballots a finalized poll needs to get the deposit back.
+    pub bump: u8,                       // PDA bump for config account.
+    pub pending_admin: Option<Pubkey>,  // Set by `transfer_admin` until it signs `accept_admin`.
+    pub paused: bool,                   // Program-wide kill switch (`set_paused`).
+    pub creators_restricted: bool,      // Poll creation needs a `CreatorCredential` (`set_creators_restricted`).
+    pub max_polls_per_window: u32,      // Polls one authority may create per window; 0 = unlimited.
+    pub creation_window_secs: u32,      // Length of that rolling window.
+}
+
+/// How many polls an authority created in the current rate-limit window, PDA
+/// `[b"creator_stats", authority]`.
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorStats {
+    pub authority: Pubkey,            // Poll creator being counted.
+    pub polls_created_in_window: u32, // Creations since `window_start_ts`.
+    pub window_start_ts: i64,         // Unix time of the window's first creation.
+    pub bump: u8,                     // PDA bump for creator stats account.
+}
+
+/// Marks a wallet allowed to create polls while creators are restricted, PDA `[b"creator", wallet]`.
+#[account]
+#[derive(InitSpace)]
+pub struct CreatorCredential {
+    pub wallet: Pubkey, // Vetted poll creator.
+    pub bump: u8,       // PDA bump for creator credential account.
+}
+
+/// Optional settings passed to `init_poll`. Defaults keep the classic behavior.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct PollOptions {
+    pub finalize_bounty_lamports: u64, // Deposited into the poll and paid to the finalize cranker.
+    pub allowlist_required: bool,      // Only wallets registered via `register_voter` may vote.
+    pub allowlist_root: Option<[u8; 32]>, // Only wallets with a merkle proof against this root may vote.
+    pub gate_mint: Option<Pubkey>,     // Only holders of this token may vote.
+    pub min_balance: u64,              // Minimum raw balance of `gate_mint` (base units).
+    pub gate_collection: Option<Pubkey>, // Only holders of a verified NFT from this collection may vote (`nft-gate` feature).
+    pub min_lamports: u64,             // Voters must keep at least this many lamports after paying voter rent; 0 disables.
+    pub weighting: VoteWeighting,      // Ballot weight; `Equal` keeps one wallet, one vote.
+    pub weight_root: Option<[u8; 32]>, // Snapshot balances root for `Snapshot` weighting (or `set_weight_root`).
+    pub max_voters: u32,               // Only the first N wallets may vote; 0 = unlimited.
+    pub authority_can_vote: bool,      // False bars the poll authority from voting (neutral organizer).
+    pub votes_per_wallet: u8,          // Ballots each wallet may cast, 1..=MAX_VOTES_PER_WALLET.
+    pub allow_repeat_candidate: bool,  // With several ballots, may a wallet back one candidate twice?
+    pub registrar: Option<Pubkey>,     // Only wallets attested by this key may vote, one per identity.
+    pub ballot_type: BallotType,       // `Plurality` = one candidate per ballot via `vote`.
+    pub allow_underspend: bool,        // Cumulative polls: accept allocations below the budget.
+    pub kind: PollKind,                // `Referendum` needs the options "Yes" and "No" (see `init_referendum`).
+    pub allow_abstain: bool,           // Let voters formally abstain via `vote_abstain`.
+    pub allow_downvotes: bool,         // Let voters vote against a candidate (`vote_against`); plurality only.
+    pub num_winners: u8,               // Seats to fill, 1..candidate count; >1 not for IRV/referendums.
+    pub majority_bps: u16,             // Plurality elections: leader share below which `create_runoff` works; 0 = never.
+    pub allow_write_ins: bool,         // Plurality elections: voters may add candidates via `vote_write_in`.
+    pub commit_reveal: bool,           // Secret plurality ballots via `commit_vote` / `reveal_vote`.
+    pub reveal_deadline_ts: i64,       // Commit-reveal only: end of the reveal phase (> end_ts).
+    pub hide_tallies: bool,            // Plurality: keep `votes` at zero until `tabulate` after the close.
+    pub quorum: u64,                   // Minimum turnout (ballots, or total weight when weighted); 0 = none.
+    pub tie_break: TieBreak,           // Tie for first: report it (`None`), `EarliestLead` or `Random`.
+    pub registration_end_ts: i64,      // >0: wallets may `register_candidate` until then (< end_ts); voting waits.
+    pub sharded: bool,                 // Open plurality: per-candidate Tally PDAs (in `remaining_accounts`).
+    pub tally_shards: u8,              // Sharded: counters per candidate, 1..=MAX_TALLY_SHARDS; else 1.
+    pub max_candidates: u8,            // List size the account reserves room for, candidates.len()..=8.
+    pub vote_fee_lamports: u64,        // Anti-spam fee each ballot pays into the poll's treasury PDA; 0 = free.
+    pub fee_beneficiary: Option<Pubkey>, // Where `withdraw_treasury` sends the fees; None = the authority.
+    pub receiptless_voters: u16,       // >0: no Voter PDAs; `vote_light` with leaves 0..N of `allowlist_root` (N <= MAX_RECEIPTLESS_VOTERS).
+    pub candidate_wallets: Vec<Pubkey>, // Wallet of each seeded candidate (prize recipient); empty = the authority.
+    pub reward_mint: Option<Pubkey>,   // Token every voter may claim from the poll's reward vault; None = no rewards.
+    pub reward_amount: u64,            // Raw amount of `reward_mint` per voter (> 0 with a mint).
+    pub voter_retention_secs: u32,     // Delay after voting ends before `sweep_voter` may close Voter records.
+    pub candidate_fee_lamports: u64,   // Anti-spam fee `register_candidate` pays into the treasury; 0 = free registration.
+    pub fee_mint: Option<Pubkey>,      // Charge each `vote` ballot `fee_amount` of this token instead of lamports.
+    pub fee_amount: u64,               // Raw amount of `fee_mint` per ballot (> 0 with a mint; needs `vote_fee_lamports = 0`).
+    pub burn_mint: Option<Pubkey>,     // Burn-to-vote: each voter burns `burn_amount` of this token with their first ballot.
+    pub burn_amount: u64,              // Raw amount of `burn_mint` burned per voter (> 0 with a mint).
+    pub certifier: Option<Pubkey>,     // Observer who must `certify_results` before payout or runoff; None = not needed.
+    pub certification_deadline_ts: i64, // With a certifier: results count as certified after this (> end_ts and any reveal deadline).
+}
+
+impl PollOptions {
+    /// Candidates `init_poll` reserves room for: `max_candidates`, or only the `initial` list
+    /// on write-in polls, whose later entries grow the account at the adder's expense.
+    pub fn reserved_candidates(&self, initial: usize) -> u8 {
+        if self.allow_write_ins {
+            initial as u8
+        } else {
+            self.max_candidates
+        }
+    }
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        Self {
+            finalize_bounty_lamports: 0,
+            allowlist_required: false,
+            allowlist_root: None,
+            gate_mint: None,
+            min_balance: 0,
+            gate_collection: None,
+            min_lamports: 0,
+            weighting: VoteWeighting::Equal,
+            weight_root: None,
+            max_voters: 0,
+            authority_can_vote: true, // Organizers could always vote; keep that by default.
+            votes_per_wallet: 1,
+            allow_repeat_candidate: false,
+            registrar: None,
+            ballot_type: BallotType::Plurality,
+            allow_underspend: false,
+            kind: PollKind::Election,
+            allow_abstain: false,
+            allow_downvotes: false,
+            num_winners: 1,
+            majority_bps: 0,
+            allow_write_ins: false,
+            commit_reveal: false,
+            reveal_deadline_ts: 0,
+            hide_tallies: false,
+            quorum: 0,
+            tie_break: TieBreak::None,
+            registration_end_ts: 0,
+            sharded: false,
+            tally_shards: 1,
+            max_candidates: 8,
+            vote_fee_lamports: 0,
+            fee_beneficiary: None,
+            receiptless_voters: 0,
+            candidate_wallets: Vec::new(),
+            reward_mint: None,
+            reward_amount: 0,
+            voter_retention_secs: VOTER_RETENTION_SECS,
+            candidate_fee_lamports: 0,
+            fee_mint: None,
+            fee_amount: 0,
+            burn_mint: None,
+            burn_amount: 0,
+            certifier: None,
+            certification_deadline_ts: 0,
+        }
+    }
+}
+
+/// How much a ballot adds to its candidate's tally.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug, InitSpace)]
+pub enum VoteWeighting {
+    /// Every ballot counts 1.
+    #[default]
+    Equal,
+    /// A ballot counts the voter's raw balance of `mint` at vote time.
+    TokenBalance { mint: Pubkey },
+    /// A ballot counts `floor(sqrt(balance))` of `mint`, damping large holders.
+    QuadraticToken { mint: Pubkey },
+    /// A ballot counts the voter's balance at `slot`, proven against `Poll::weight_root`.
+    Snapshot { slot: u64 },
+    /// Voting locks the voter's whole balance of `mint` in the poll's escrow vault until
+    /// `unlock_tokens`; the ballot counts the locked amount.
+    Escrow { mint: Pubkey },
+    /// A ballot counts the lamports delegated by an active native stake account that the voter
+    /// controls (staker or withdrawer).
+    Stake,
+    /// A ballot counts the weight on the voter's credential (`register_voter`), e.g. co-op shares.
+    Credential,
+}
+
+/// What a poll decides.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug, InitSpace)]
+pub enum PollKind {
+    /// Pick a winner among the candidates.
+    #[default]
+    Election,
+    /// Yes (index 0) / No (index 1); passes if yes-votes are at least `pass_bps` of the total.
+    Referendum { pass_bps: u16 },
+}
+
+/// How `finalize_poll` settles a tie for first place (single-winner, non-IRV polls).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug, InitSpace)]
+pub enum TieBreak {
+    /// Report the tie: `is_tie` set, no winner.
+    #[default]
+    None,
+    /// The tied candidate whose count last changed earliest (plurality only).
+    EarliestLead,
+    /// A leader picked from `sha256(poll || latest SlotHashes entry)` at finalize. Not a VRF:
+    /// the finalizer sees the recent hash and chooses when to land the transaction, and the
+    /// slot leader influences the hash, so the pick is biasable. Low-stakes polls only.
+    Random,
+}
+
+/// What a ballot looks like and which instruction casts it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq, Default, Debug, InitSpace)]
+pub enum BallotType {
+    /// One candidate per ballot (`vote`).
+    #[default]
+    Plurality,
+    /// Any non-empty set of candidates, each getting the full weight (`vote_approval`).
+    Approval,
+    /// Candidates in order of preference (`vote_ranked`), finalized by instant runoff
+    /// (`tally_irv`). `votes` shows first preferences.
+    RankedChoice,
+    /// A full ranking (`vote_borda`) worth `n-1, ..., 0` points; `votes` holds points.
+    Borda,
+    /// A 0..=10 score for every candidate (`vote_score`); `votes` holds summed scores.
+    Score,
+    /// Each voter spreads `budget` points over the candidates as they like
+    /// (`vote_cumulative`); `votes` holds points.
+    Cumulative { budget: u16 },
+    /// Each voter splits 100% (10_000 bps) over the candidates (`vote_split`); `votes` holds
+    /// basis points.
+    Split,
+}
+
+/// A voter's claimed snapshot balance plus its merkle proof against `Poll::weight_root`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct BalanceProof {
+    pub balance: u64,           // Raw balance at the snapshot slot.
+    pub proof: Vec<[u8; 32]>,   // Sibling hashes from the leaf up to the root.
+}
+
+/// `delegator` lets `to` cast its ballot in `poll` via `vote_as_delegate`.
+#[account]
+pub struct Delegation {
+    pub poll: Pubkey,      // Poll this delegation is for.
+    pub delegator: Pubkey, // Wallet handing over its vote.
+    pub to: Pubkey,        // Wallet allowed to vote for it.
+    pub bump: u8,          // PDA bump for delegation account.
+    pub expires_ts: i64,   // Unix time after which `vote_as_delegate` fails.
+}
+impl Delegation {
+    /// Size calculation for the Delegation account (without discriminator).
+    pub const SIZE: usize = 32 + 32 + 32 + 1 + 8; // poll + delegator + to + bump + expires_ts
+}
+
+/// The poll's registrar vouches that `wallet` belongs to the person behind `identity_hash`.
+#[account]
+pub struct Attestation {
+    pub poll: Pubkey,             // Poll this attestation is for.
+    pub wallet: Pubkey,           // Attested wallet.
+    pub identity_hash: [u8; 32],  // Registrar's opaque identifier of the person.
+    pub bump: u8,                 // PDA bump for attestation account.
+}
+impl Attestation {
+    /// Size calculation for the Attestation account (without discriminator).
+    pub const SIZE: usize = 32 + 32 + 32 + 1; // poll + wallet + identity_hash + bump
+}
+
+/// Marks an attested identity as used in `poll`, by `wallet`.
+#[account]
+pub struct IdentityClaim {
+    pub poll: Pubkey,             // Poll the claim is for.
+    pub identity_hash: [u8; 32],  // Claimed identity.
+    pub wallet: Pubkey,           // Wallet that voted with it.
+    pub bump: u8,                 // PDA bump for identity account.
+}
+impl IdentityClaim {
+    /// Size calculation for the IdentityClaim account (without discriminator).
+    pub const SIZE: usize = 32 + 32 + 32 + 1; // poll + identity_hash + wallet + bump
+}
+
+/// Marks `wallet` as barred from voting in `poll`.
+#[account]
+pub struct Banned {
+    pub poll: Pubkey,   // Poll the ban applies to.
+    pub wallet: Pubkey, // Barred wallet.
+    pub bump: u8,       // PDA bump for ban account.
+}
+impl Banned {
+    /// Size calculation for the Banned account (without discriminator).
+    pub const SIZE: usize = 32 + 32 + 1; // poll + wallet + bump
+}
+
+/// Proof that the authority allowed `wallet` to vote in `poll`. Credentials from before
+/// `index` existed won't deserialize (a breaking redeploy, like older Voter records).
+#[account]
+pub struct VoterCredential {
+    pub poll: Pubkey,   // Poll this credential is valid for.
+    pub wallet: Pubkey, // Eligible wallet.
+    pub bump: u8,       // PDA bump for credential account.
+    pub weight: u64,    // Ballot weight in `Credential` weighting.
+    pub index: Option<u32>, // Slot in the poll's `ParticipationBitmap` (None if issued without one).
+}
+impl VoterCredential {
+    /// Size calculation for the VoterCredential account (without discriminator).
+    pub const SIZE: usize = 32 + 32 + 1 + 8 + 5; // poll + wallet + bump + weight + index
+}
+
+/// One bit per credential slot of a poll, set once that credential votes, PDA
+/// `[b"participation", poll]`. Replaces Voter PDAs for the poll (see `init_participation`).
+#[account]
+pub struct ParticipationBitmap {
+    pub poll: Pubkey,     // Poll whose ballots this tracks.
+    pub bump: u8,         // PDA bump for bitmap account.
+    pub capacity: u32,    // Slots available, at most `MAX_PARTICIPANTS`.
+    pub next_index: u32,  // Slot the next credential gets.
+    pub bits: Vec<u8>,    // Bit i (byte i / 8, bit i % 8) set once slot i has voted.
+}
+impl ParticipationBitmap {
+    /// poll + bump + capacity + next_index + bits (4 + capacity / 8 rounded up).
+    pub fn space(capacity: u32) -> usize {
+        32 + 1 + 4 + 4 + 4 + (capacity as usize).div_ceil(8)
+    }
+
+    /// Hands out the next slot, failing with `ParticipationFull` once all are taken.
+    fn assign(&mut self) -> Result<u32> {
+        require!(self.next_index < self.capacity, VotingError::ParticipationFull);
+        self.next_index += 1;
+        Ok(self.next_index - 1)
+    }
+
+    /// True once slot `index` has voted.
+    pub fn is_set(&self, index: u32) -> bool {
+        self.bits
+            .get(index as usize / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Sets slot `index`, failing with `AlreadyVoted` if it is set or `NotIndexed` if it is
+    /// outside the bitmap (past `capacity` or the stored bits).
+    fn mark(&mut self, index: u32) -> Result<()> {
+        require!(
+            index < self.capacity && (index as usize / 8) < self.bits.len(),
+            VotingError::NotIndexed
+        ); // Never index past the account.
+        require!(!self.is_set(index), VotingError::AlreadyVoted);
+        self.bits[index as usize / 8] |= 1 << (index % 8);
+        Ok(())
+    }
 }
 
-/// On-chain poll configuration and results.
+/// Escrow for a poll's prize, PDA `[b"prize", poll]`; its lamports above rent are `Poll.prize_total`.
 #[account]
-pub struct Poll {
-    pub authority: Pubkey,      // Poll admin.
-    pub title: String,          // Poll title.
-    pub candidates: Vec<String>,// Candidate names.
-    pub votes: Vec<u64>,        // Vote counts aligned with candidates.
-    pub start_ts: i64,          // Start time (unix).
-    pub end_ts: i64,            // End time (unix).
-    pub bump: u8,               // PDA bump for poll account.
+#[derive(InitSpace)]
+pub struct PrizePool {
+    pub poll: Pubkey, // Poll whose winner gets the prize.
+    pub bump: u8,     // PDA bump for prize pool account.
 }
-impl Poll {
-    /// Rough sizing: authority (32) + title (4 + 64) + candidates (4 + n*(4+32))
-    /// + votes (4 + n*8) + timestamps (8+8) + bump (1).
-    /// Adjust upward if you allow more/longer candidates.
-    pub const MAX_SIZE: usize = 32 + 4 + 64 + 4 + (8 * (4 + 32)) + 4 + (8 * 8) + 8 + 8 + 1;
+
+/// What one wallet put into a poll's prize pool, PDA `[b"contribution", poll, funder]`.
+#[account]
+#[derive(InitSpace)]
+pub struct Contribution {
+    pub poll: Pubkey,   // Poll funded.
+    pub funder: Pubkey, // Wallet refunded if nobody can win.
+    pub amount: u64,    // Lamports contributed so far.
+    pub bump: u8,       // PDA bump for contribution account.
 }
 
-/// Marks that a wallet has already voted in a poll.
+/// Registration fee one wallet paid to enter a poll, PDA `[b"candidate_fee", poll, wallet]`.
 #[account]
+#[derive(InitSpace)]
+pub struct CandidateFee {
+    pub poll: Pubkey,   // Poll entered.
+    pub wallet: Pubkey, // Registrant refunded if rejected.
+    pub amount: u64,    // Lamports paid into the treasury.
+    pub rejected: bool, // Set by `reject_candidate`; makes the fee refundable.
+    pub bump: u8,       // PDA bump for candidate fee account.
+}
+
+/// Marks that a wallet has already voted in a poll. Fields after `bump` were added over time;
+/// Voter records from older deployments won't deserialize, so those were breaking redeploys.
+#[account]
+#[derive(InitSpace, Default)]
 pub struct Voter {
+    pub version: u8,     // Layout version (`VOTER_VERSION`).
     pub poll: Pubkey,    // Poll this record belongs to.
     pub wallet: Pubkey,  // Wallet that cast the vote.
     pub has_voted: bool, // Marker flag (always true once created).
     pub bump: u8,        // PDA bump for voter account.
+    pub candidate_idx: u8, // Candidate this wallet voted for.
+    pub voted_at: i64,   // Unix time the current choice was recorded.
+    pub weight: u64,     // Weight applied to the tally (1 in `Equal` mode).
+    pub observed_balance: u64, // Token balance read at vote time (0 in `Equal` mode).
+    pub locked_amount: u64, // Tokens in escrow for this ballot, until `unlock_tokens`.
+    pub votes_used: u8,     // Ballots cast so far (`Poll::votes_per_wallet` max).
+    pub choices: [u8; MAX_VOTES_PER_WALLET as usize], // Candidate of each ballot; first `votes_used` valid.
+    pub abstained: bool,    // Record made by `vote_abstain`; no candidate ballot.
+    pub against: bool,      // Ballot made by `vote_against` (counted in `downvotes`).
+    pub commitment: [u8; 32], // Commit-reveal polls: hash set by `commit_vote`.
+    pub revealed: bool,     // Commit-reveal polls: `reveal_vote` counted this ballot.
+    pub counted: bool,      // Hidden-tally polls: `tabulate` added this record.
+    #[max_len(8)]
+    pub ballot: Vec<u16>,   // Approval: 0/1 per candidate; score/cumulative/split: points (bps) per candidate; ranked/Borda: candidate indices, best first; plurality: empty.
+    pub reward_claimed: bool, // Reward polls: `claim_reward` paid this record.
+    pub receipt_claimed: bool, // Receipt polls: `claim_receipt` minted for this record.
+    pub burned: u64,        // Burn-to-vote polls: tokens this record's first ballot burned.
+}
+
+/// Instant-runoff counters of a ranked-choice poll, PDA `[b"ranked", poll]`. For every subset
+/// of candidates still standing and every member of it, the total weight of ballots that rank
+/// that member highest within the subset. Subsets are stored in increasing bitmask order
+/// (8 candidates = 256 subsets), one counter per member, so any elimination round can be read
+/// off without the ballots.
+#[account]
+pub struct RankedTally {
+    pub poll: Pubkey,     // Poll these counters belong to.
+    pub bump: u8,         // PDA bump for ranked tally account.
+    pub counts: Vec<u64>, // `RANKED_COUNTERS` counters, see `ranked_slot`.
+}
+impl RankedTally {
+    /// poll + bump + counts (4 + RANKED_COUNTERS*8); fits a single `create_account`.
+    pub const SIZE: usize = 32 + 1 + 4 + RANKED_COUNTERS * 8;
+}
+
+/// A counter of a `sharded` poll: `[b"tally", poll, candidate_idx]` with one shard per
+/// candidate, `[b"shard", poll, candidate_idx, shard]` with more.
+#[account]
+#[derive(Default)]
+pub struct Tally {
+    pub poll: Pubkey,       // Poll this shard belongs to.
+    pub candidate_idx: u8,  // Candidate counted here.
+    pub bump: u8,           // PDA bump for tally account.
+    pub votes: u64,         // Ballots for the candidate.
+    pub voters: u32,        // Ballots recorded here (equals `votes` with equal weights).
+    pub shard: u8,          // Shard number, 0..tally_shards.
+    pub folded: bool,       // Added to the poll by `collect_tallies`.
+}
+impl Tally {
+    /// poll + candidate_idx + bump + votes + voters + shard + folded.
+    pub const SIZE: usize = 32 + 1 + 1 + 8 + 4 + 1 + 1;
+}
+
+/// Plurality poll with up to `MAX_LARGE_CANDIDATES` candidates, stored as fixed arrays and
+/// accessed in place (`AccountLoader`) instead of being Borsh-decoded on every instruction.
+/// Fields are ordered so `repr(C)` needs no implicit padding.
+#[account(zero_copy)]
+pub struct PollLarge {
+    pub authority: Pubkey,                         // Poll admin.
+    pub seq: u64,                                  // `PollCounter` value in the PDA seeds.
+    pub start_ts: i64,                             // Unix start timestamp.
+    pub end_ts: i64,                               // Unix end timestamp.
+    pub voter_count: u64,                          // Ballots cast.
+    pub votes: [u64; MAX_LARGE_CANDIDATES],        // Tallies; entries past `candidate_count` stay 0.
+    pub names: [[u8; 32]; MAX_LARGE_CANDIDATES],   // UTF-8 names, zero padded.
+    pub title: [u8; 64],                           // UTF-8 title, zero padded.
+    pub candidate_count: u8,                       // Entries in use in `names`/`votes`.
+    pub bump: u8,                                  // PDA bump for poll account.
+    pub _reserved: [u8; 6],                        // Keeps the size a multiple of 8.
+}
+impl PollLarge {
+    /// authority + seq + start_ts + end_ts + voter_count + votes (64*8) + names (64*32)
+    /// + title (64) + candidate_count + bump + reserved (6).
+    pub const SIZE: usize = 32 + 8 + 8 + 8 + 8 + MAX_LARGE_CANDIDATES * 8 + MAX_LARGE_CANDIDATES * 32 + 64 + 1 + 1 + 6;
+
+    /// Validates and appends names, failing with `TooManyCandidates` past the cap.
+    fn push_candidates(&mut self, candidates: &[String]) -> Result<()> {
+        let count = self.candidate_count as usize; // Entries already in use.
+        require!(
+            count + candidates.len() <= MAX_LARGE_CANDIDATES,
+            VotingError::TooManyCandidates
+        ); // Fixed arrays.
+        for (i, name) in candidates.iter().enumerate() {
+            validate_candidate_name(name)?; // Same rules as init_poll.
+            require!(name.len() <= 32, VotingError::CandidateNameTooLong); // Fixed-size name slot.
+            require_new_candidate(self.names[..count + i].iter().map(padded_str), name)?; // Distinct names.
+            self.names[count + i][..name.len()].copy_from_slice(name.as_bytes());
+        }
+        self.candidate_count = (count + candidates.len()) as u8;
+        Ok(())
+    }
+
+    /// Adds one ballot for `candidate_idx` at `now` and returns its new count: the window must
+    /// be open (`TooEarly`, `Closed`), with at least two candidates and a valid index.
+    fn count_vote(&mut self, candidate_idx: u8, now: i64) -> Result<u64> {
+        require!(now >= self.start_ts, VotingError::TooEarly); // Window not open yet.
+        require!(now <= self.end_ts, VotingError::Closed); // Window already over.
+        require!(self.candidate_count >= 2, VotingError::NotEnoughCandidates); // Something to choose from.
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < self.candidate_count as usize, VotingError::BadCandidate); // Validate index in range.
+        self.votes[idx] = self.votes[idx].checked_add(1).ok_or(VotingError::Overflow)?;
+        self.voter_count = self.voter_count.checked_add(1).ok_or(VotingError::Overflow)?;
+        Ok(self.votes[idx])
+    }
+}
+
+/// Immutable half of a split poll (`init_poll_v2`): written once at creation.
+#[account]
+#[derive(InitSpace)]
+pub struct PollConfig {
+    pub authority: Pubkey,       // Poll admin.
+    pub seq: u64,                // `PollCounter` value in the PDA seeds.
+    #[max_len(MAX_TITLE_BYTES)]
+    pub title: String,           // Poll title.
+    #[max_len(256)]
+    pub description: String,     // What is being decided (may be empty).
+    #[max_len(8, MAX_NAME_BYTES)]
+    pub candidates: Vec<String>, // Candidate names.
+    pub start_ts: i64,           // Start time (unix).
+    pub end_ts: i64,             // End time (unix).
+    pub bump: u8,                // PDA bump for config account.
+}
+
+/// Mutable half of a split poll, PDA `[b"poll_tally", config]`: the only account
+/// `vote_v2` write-locks. Zero-copy, with its own copy of the schedule and candidate count,
+/// so a ballot never decodes the config. Fields are ordered so `repr(C)` needs no implicit
+/// padding.
+#[account(zero_copy)]
+pub struct PollTally {
+    pub config: Pubkey,      // PollConfig these counts belong to.
+    pub votes: [u64; 8],     // Vote counts aligned with `PollConfig::candidates`; the rest stay 0.
+    pub start_ts: i64,       // Copy of `PollConfig::start_ts`.
+    pub end_ts: i64,         // Copy of `PollConfig::end_ts`.
+    pub voter_count: u32,    // Ballots cast.
+    pub candidate_count: u8, // Entries of `votes` in use.
+    pub bump: u8,            // PDA bump for tally account.
+    pub _reserved: [u8; 2],  // Keeps the size a multiple of 8.
+}
+impl PollTally {
+    /// config + votes (8*8) + start_ts + end_ts + voter_count + candidate_count + bump
+    /// + reserved (2).
+    pub const SIZE: usize = 32 + 8 * 8 + 8 + 8 + 4 + 1 + 1 + 2;
+
+    /// Adds one ballot for `candidate_idx` at `now` and returns its new count: the window must
+    /// be open (`TooEarly`, `Closed`) and the index valid (`BadCandidate`).
+    fn count_vote(&mut self, candidate_idx: u8, now: i64) -> Result<u64> {
+        require!(now >= self.start_ts, VotingError::TooEarly); // Window not open yet.
+        require!(now <= self.end_ts, VotingError::Closed); // Window already over.
+        let idx = candidate_idx as usize; // Cast to usize for indexing.
+        require!(idx < self.candidate_count as usize, VotingError::BadCandidate); // Validate index in range.
+        self.votes[idx] = self.votes[idx].checked_add(1).ok_or(VotingError::Overflow)?;
+        self.voter_count = self.voter_count.checked_add(1).ok_or(VotingError::Overflow)?;
+        Ok(self.votes[idx])
+    }
+}
+
+/// Emitted when a poll is created. Every event carries the poll pubkey so one log
+/// subscription can demultiplex across polls.
+#[event]
+pub struct PollCreated {
+    pub poll: Pubkey,        // New poll.
+    pub authority: Pubkey,   // Poll admin.
+    pub title: String,       // Poll title.
+    pub description: String, // Poll description.
+    pub start_ts: i64,       // Start time (unix).
+    pub end_ts: i64,         // End time (unix).
+    pub candidate_count: u8, // Number of candidates at creation.
+    pub poll_id: Option<u64>, // Registry id, if the poll was registered.
+}
+
+/// Emitted for every ballot cast through `vote`.
+#[event]
+pub struct VoteCast {
+    pub poll: Pubkey,      // Poll voted on.
+    pub wallet: Pubkey,    // Voter wallet.
+    pub candidate_idx: u8, // Chosen candidate.
+    pub weight: u64,       // Amount added to the tally.
+    pub new_count: u64,    // Candidate tally after this vote.
+    pub against: bool,     // Vote against (`new_count` is then the candidate's downvotes).
+}
+
+/// Emitted when a poll's results are frozen.
+#[event]
+pub struct PollFinalized {
+    pub poll: Pubkey,           // Finalized poll.
+    pub winner_idx: Option<u8>, // Winner (None on tie / no votes).
+    pub is_tie: bool,           // Tie for first place.
+    pub totals: Vec<u64>,       // Final tallies aligned with candidates.
+    pub ballot_type: BallotType, // What `totals` count: votes (plurality/approval), points (Borda/score/cumulative), bps (split), last IRV round.
+    pub passed: bool,           // Referendums: threshold met (always false for elections).
+    pub downvotes: Vec<u64>,    // Votes against, aligned with candidates (all zero unless enabled).
+    pub winners: Vec<u8>,       // Seated candidates, best first (all tied ones on a seat tie).
+    pub seat_tie: bool,         // Tie for the last of `num_winners` seats.
+    pub voter_count: u32,       // Wallets with a ballot recorded, abstentions included.
+    pub turnout: u64,           // Weight of all ballots cast, abstentions included.
+    pub quorum_met: bool,       // False: the results above are recorded but not valid.
+    pub tie_break: TieBreak,    // Policy behind `winner_idx` when first place was tied.
+    pub results_hash: [u8; 32], // Same as `Poll::results_hash`.
+}
+
+/// Emitted when a poll's end time is pushed out.
+#[event]
+pub struct PollExtended {
+    pub poll: Pubkey,     // Extended poll.
+    pub old_end_ts: i64,  // Previous end time (unix).
+    pub new_end_ts: i64,  // New end time (unix).
+}
+
+/// Emitted when the certifier co-signs a result.
+#[event]
+pub struct ResultsCertified {
+    pub poll: Pubkey,       // Certified poll.
+    pub certifier: Pubkey,  // Who signed.
+}
+
+/// Emitted when a finalized result is contested.
+#[event]
+pub struct DisputeFlagged {
+    pub poll: Pubkey,     // Contested poll.
+    pub by: Pubkey,       // Poll authority or protocol admin who flagged it.
+    pub reason_code: u8,  // Free-form reason for off-chain tooling.
+}
+
+/// Emitted when the protocol admin rules on a dispute.
+#[event]
+pub struct DisputeResolved {
+    pub poll: Pubkey,  // Poll ruled on.
+    pub upheld: bool,  // True: result stands; false: voided.
 }
-impl Voter {
-    /// Size calculation for the Voter account (without discriminator).
-    pub const SIZE: usize = 32 + 32 + 1 + 1; // poll + wallet + has_voted + bump
+
+/// Emitted when the candidate list changes before voting opens.
+#[event]
+pub struct CandidatesChanged {
+    pub poll: Pubkey,           // Edited poll.
+    pub candidates: Vec<String>, // Full candidate list after the change.
 }
 
 /// Custom errors for clearer client UX.
@@ -164,4 +6984,1294 @@ pub enum VotingError {
     EmptyCandidateName,
     #[msg("Arithmetic overflow")]
     Overflow,
+    #[msg("Poll is still open")]
+    PollStillOpen,
+    #[msg("Poll has been cancelled")]
+    PollCancelled,
+    #[msg("Votes have already been cast")]
+    VotesAlreadyCast,
+    #[msg("Poll has already been finalized")]
+    AlreadyFinalized,
+    #[msg("Poll ended too long ago to be extended")]
+    ExtensionTooLate,
+    #[msg("Poll is paused")]
+    PollPaused,
+    #[msg("Poll is not paused")]
+    NotPaused,
+    #[msg("Poll has already started")]
+    PollStarted,
+    #[msg("New candidate is the same as the current vote")]
+    SameCandidate,
+    #[msg("Wallet has already voted in this poll")]
+    AlreadyVoted,
+    #[msg("Description too long")]
+    DescriptionTooLong,
+    #[msg("Wallet is not eligible to vote in this poll")]
+    NotEligible,
+    #[msg("Merkle proof is missing or invalid")]
+    InvalidProof,
+    #[msg("Not enough of the required token to vote")]
+    InsufficientTokens,
+    #[msg("Account is not a valid token account")]
+    InvalidTokenAccount,
+    #[msg("NFT collection is not verified")]
+    CollectionNotVerified,
+    #[msg("Account is not valid token metadata")]
+    InvalidMetadata,
+    #[msg("Feature not enabled in this build")]
+    FeatureNotEnabled,
+    #[msg("Wallet balance is below the poll's minimum")]
+    WalletTooNew,
+    #[msg("Too many entries in one batch")]
+    BatchTooLarge,
+    #[msg("Remaining accounts do not match the instruction arguments")]
+    AccountMismatch,
+    #[msg("Wallet has no voting power in this poll")]
+    NoVotingPower,
+    #[msg("Snapshot weight root has not been published")]
+    WeightRootNotSet,
+    #[msg("Tokens are still locked in escrow")]
+    TokensLocked,
+    #[msg("No escrowed tokens to unlock")]
+    NothingToUnlock,
+    #[msg("Account is not a delegated stake account of this wallet")]
+    InvalidStakeAccount,
+    #[msg("Stake is not active")]
+    StakeNotActive,
+    #[msg("Weight must be greater than zero")]
+    InvalidWeight,
+    #[msg("Cannot delegate to yourself")]
+    InvalidDelegate,
+    #[msg("Signer is not the delegate of this delegation")]
+    NotDelegate,
+    #[msg("This poll does not accept delegated votes")]
+    DelegationUnsupported,
+    #[msg("Delegation has expired")]
+    DelegationExpired,
+    #[msg("Expiry must be in the future and later than the current one")]
+    InvalidExpiry,
+    #[msg("Delegation chain is too long")]
+    DelegationTooDeep,
+    #[msg("Delegation chain contains a cycle")]
+    DelegationCycle,
+    #[msg("Wallet is banned from this poll")]
+    VoterBanned,
+    #[msg("Poll has reached its voter cap")]
+    PollFull,
+    #[msg("The poll authority may not vote in this poll")]
+    AuthorityCannotVote,
+    #[msg("votes_per_wallet must be between 1 and MAX_VOTES_PER_WALLET")]
+    InvalidVotesPerWallet,
+    #[msg("Wallet has used all of its votes")]
+    VoteLimitReached,
+    #[msg("Wallet already voted for this candidate")]
+    DuplicateChoice,
+    #[msg("Wallet cast several ballots; retract and vote again instead")]
+    MultipleBallots,
+    #[msg("Wallet has no attestation from the poll's registrar")]
+    NotAttested,
+    #[msg("Signer is not the poll's registrar")]
+    NotRegistrar,
+    #[msg("This identity already voted with another wallet")]
+    IdentityAlreadyVoted,
+    #[msg("Wrong instruction for this poll's ballot type")]
+    WrongBallotType,
+    #[msg("Ballot selects no candidate")]
+    EmptyBallot,
+    #[msg("Ranked-choice polls are finalized with tally_irv")]
+    UseTallyIrv,
+    #[msg("Ballot must have one entry per candidate")]
+    BadBallotLength,
+    #[msg("Scores must be between 0 and 10")]
+    ScoreOutOfRange,
+    #[msg("Cumulative budget must be positive")]
+    InvalidBudget,
+    #[msg("Allocation exceeds the point budget")]
+    OverBudget,
+    #[msg("Allocation must spend the whole point budget")]
+    BudgetNotSpent,
+    #[msg("Pass threshold must be between 1 and 10000 bps")]
+    InvalidThreshold,
+    #[msg("Referendums have exactly the options Yes and No")]
+    ReferendumOptions,
+    #[msg("This poll doesn't accept abstentions")]
+    AbstainNotAllowed,
+    #[msg("Wallet abstained; retract the abstention to vote")]
+    Abstained,
+    #[msg("This poll doesn't accept votes against candidates")]
+    DownvotesNotAllowed,
+    #[msg("num_winners must be at least 1 and below the candidate count")]
+    InvalidNumWinners,
+    #[msg("Poll is not finalized yet")]
+    NotFinalized,
+    #[msg("Leader already has the required majority, or runoffs are disabled")]
+    RunoffNotNeeded,
+    #[msg("Tie for second place; finalists are ambiguous")]
+    RunoffTie,
+    #[msg("A runoff was already created for this poll")]
+    RunoffExists,
+    #[msg("This poll doesn't accept write-in candidates")]
+    WriteInsNotAllowed,
+    #[msg("Allocation must add up to exactly 10000 bps")]
+    BadAllocation,
+    #[msg("Commit-reveal poll: use commit_vote and reveal_vote")]
+    CommitRevealPoll,
+    #[msg("Poll doesn't use commit-reveal ballots")]
+    NotCommitReveal,
+    #[msg("Reveals are only accepted between end_ts and reveal_deadline_ts")]
+    RevealNotOpen,
+    #[msg("Ballot already revealed")]
+    AlreadyRevealed,
+    #[msg("Candidate and salt don't match the commitment")]
+    CommitmentMismatch,
+    #[msg("Poll doesn't hide its tallies")]
+    TalliesNotHidden,
+    #[msg("Voter record already tabulated")]
+    AlreadyCounted,
+    #[msg("Not every Voter record has been tabulated yet")]
+    TabulationIncomplete,
+    #[msg("Candidate registration is closed")]
+    RegistrationClosed,
+    #[msg("Wallet already registered a candidate")]
+    AlreadyRegistered,
+    #[msg("Candidate registration is still open")]
+    RegistrationOpen,
+    #[msg("Candidate has not been approved")]
+    CandidateNotApproved,
+    #[msg("Option not supported on sharded polls")]
+    ShardedOptions,
+    #[msg("Sharded poll: use vote_sharded")]
+    ShardedPoll,
+    #[msg("Poll is not sharded")]
+    NotSharded,
+    #[msg("Run collect_tallies first")]
+    TalliesNotCollected,
+    #[msg("Shard count must be 1..=MAX_TALLY_SHARDS (1 unless sharded)")]
+    InvalidShardCount,
+    #[msg("Not every candidate has its tally shards yet")]
+    ShardsNotReady,
+    #[msg("Candidate already has its tally shards")]
+    ShardsExist,
+    #[msg("Poll data would exceed Poll::INIT_SPACE")]
+    PollTooLarge,
+    #[msg("Account layout version is not supported; migrate it first")]
+    UnsupportedVersion,
+    #[msg("Poll tallies are out of step with its candidate list")]
+    CorruptPoll,
+    #[msg("Another candidate already has this name (ignoring case and surrounding spaces)")]
+    DuplicateCandidate,
+    #[msg("Title must not be empty")]
+    EmptyTitle,
+    #[msg("Text contains control or invisible characters")]
+    InvalidCharacters,
+    #[msg("max_candidates must be between the initial candidate count and 8")]
+    InvalidMaxCandidates,
+    #[msg("Authority index already lists MAX_INDEXED_POLLS polls")]
+    IndexFull,
+    #[msg("Receiptless polls need an allowlist root, at most MAX_RECEIPTLESS_VOTERS leaves and plain equal-weight ballots")]
+    ReceiptlessOptions,
+    #[msg("Receiptless poll: use vote_light")]
+    ReceiptlessPoll,
+    #[msg("Poll is not receiptless")]
+    NotReceiptless,
+    #[msg("Participation bitmaps need 1..=MAX_PARTICIPANTS slots, a credential allowlist and plain ballots")]
+    ParticipationOptions,
+    #[msg("Poll tracks ballots in a participation bitmap: use vote_indexed")]
+    ParticipationPoll,
+    #[msg("Participation bitmap has no free slots")]
+    ParticipationFull,
+    #[msg("Credential has no slot in the participation bitmap")]
+    NotIndexed,
+    #[msg("Credential already has a bitmap slot")]
+    AlreadyIndexed,
+    #[msg("This poll charges a vote fee: pass its treasury account")]
+    TreasuryRequired,
+    #[msg("Withdraw the collected fees to the beneficiary first")]
+    TreasuryNotWithdrawn,
+    #[msg("Poll has no treasury (it charges no vote fee)")]
+    NoTreasury,
+    #[msg("Amount exceeds the fees held above the treasury's rent")]
+    InsufficientTreasury,
+    #[msg("Only the protocol admin may do this")]
+    NotProtocolAdmin,
+    #[msg("The protocol fee needs the config's fee_recipient account")]
+    FeeRecipientRequired,
+    #[msg("Poll holds no creation deposit")]
+    NoDeposit,
+    #[msg("Creation deposit is still locked; finalize the poll or wait for sweep_abandoned")]
+    DepositHeld,
+    #[msg("Candidate wallets must match the candidate list")]
+    CandidateWalletsMismatch,
+    #[msg("Tied polls can't pay out a prize")]
+    PrizeTied,
+    #[msg("Poll has no winner")]
+    NoWinner,
+    #[msg("Prize can only be reclaimed when nobody can win it")]
+    PrizeNotRefundable,
+    #[msg("Pay out or refund the prize pool first")]
+    PrizeUnpaid,
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount,
+    #[msg("Poll did not reach its quorum")]
+    QuorumNotMet,
+    #[msg("Poll has no voter rewards")]
+    NoRewards,
+    #[msg("Reward already claimed for this voter")]
+    RewardAlreadyClaimed,
+    #[msg("Reward vault has too few tokens left")]
+    RewardsExhausted,
+    #[msg("Ballot was never revealed")]
+    NotRevealed,
+    #[msg("Poll already has a receipt mint")]
+    ReceiptMintExists,
+    #[msg("Poll has no receipt mint")]
+    NoReceipts,
+    #[msg("Receipt already claimed for this voter")]
+    ReceiptAlreadyClaimed,
+    #[msg("Receipt URI exceeds the metadata limit")]
+    ReceiptUriTooLong,
+    #[msg("Candidate fee record account required")]
+    CandidateFeeRecordRequired,
+    #[msg("Only fees of rejected candidates are refundable")]
+    FeeNotRefundable,
+    #[msg("Rejected candidates' fees must be refunded first")]
+    CandidateRefundsOwed,
+    #[msg("Configure either a lamport or a token vote fee, not both")]
+    FeeConflict,
+    #[msg("Token fee vault accounts required")]
+    FeeVaultRequired,
+    #[msg("This ballot instruction can't pay token fees; use the Vote instructions")]
+    TokenFeeUnsupported,
+    #[msg("Voter has no token account for the fee mint")]
+    NoFeeTokenAccount,
+    #[msg("Not enough tokens to pay the vote fee")]
+    InsufficientFeeTokens,
+    #[msg("This ballot instruction can't burn tokens; use the Vote instructions")]
+    BurnUnsupported,
+    #[msg("Signer is not the pending protocol admin")]
+    NotPendingAdmin,
+    #[msg("Protocol is paused")]
+    ProtocolPaused,
+    #[msg("Wallet is not an allowed poll creator")]
+    CreatorNotAllowed,
+    #[msg("Voting window is shorter than MIN_DURATION_SECS")]
+    DurationTooShort,
+    #[msg("Voting window is longer than MAX_DURATION_SECS")]
+    DurationTooLong,
+    #[msg("End time is already in the past")]
+    EndInPast,
+    #[msg("Start time is further in the past than START_SKEW_SECS")]
+    StartInPast,
+    #[msg("Start time is more than MAX_START_DELAY_SECS away")]
+    StartTooFar,
+    #[msg("Creator stats account required")]
+    CreatorStatsRequired,
+    #[msg("Too many polls created in this window")]
+    CreationRateLimited,
+    #[msg("Result is disputed")]
+    ResultDisputed,
+    #[msg("Result was voided")]
+    ResultVoided,
+    #[msg("Poll is not disputed")]
+    NotDisputed,
+    #[msg("Dispute window has closed")]
+    DisputeWindowClosed,
+    #[msg("Only the poll authority or protocol admin may dispute")]
+    NotDisputeParty,
+    #[msg("Poll has no certifier")]
+    NoCertifier,
+    #[msg("Signer is not the poll's certifier")]
+    NotCertifier,
+    #[msg("Results are already certified")]
+    AlreadyCertified,
+    #[msg("Results are not certified yet")]
+    NotCertified,
+    #[msg("Signer is not the pending poll authority")]
+    NotPendingAuthority,
+    #[msg("No authority transfer is pending")]
+    NoPendingAuthority,
+    #[msg("Random tie-breaks need the SlotHashes sysvar")]
+    SlotHashesRequired,
+    #[msg("Reward tokens are still in the vault; withdraw them first")]
+    RewardsNotWithdrawn,
+    #[msg("The result can still be disputed; wait out the dispute window or certify it")]
+    DisputeWindowOpen,
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_winner_picks_unique_leader() {
+        assert_eq!(compute_winner(&[3, 7, 2]), (Some(1), false));
+        assert_eq!(compute_winner(&[5, 5, 1]), (None, true)); // Shared top count.
+        assert_eq!(compute_winner(&[0, 0]), (None, false)); // Nobody voted.
+        assert_eq!(compute_winner(&[]), (None, false));
+    }
+
+    #[test]
+    fn compute_net_winner_subtracts_downvotes() {
+        assert_eq!(compute_net_winner(&[5, 4], &[3, 0]), (Some(1), false)); // 2 vs 4.
+        assert_eq!(compute_net_winner(&[0, 0], &[2, 1]), (Some(1), false)); // Best below zero.
+        assert_eq!(compute_net_winner(&[3, 4], &[0, 1]), (None, true)); // 3 vs 3.
+        assert_eq!(compute_net_winner(&[0, 0], &[0, 0]), (None, false)); // No ballots.
+    }
+
+    #[test]
+    fn top_candidates_orders_and_flags_seat_ties() {
+        assert_eq!(top_candidates(&[1, 9, 5], 2), (vec![1, 2], false));
+        assert_eq!(top_candidates(&[4, 9, 4], 2), (vec![1, 0, 2], true)); // Last seat shared.
+        assert_eq!(top_candidates(&[-2, -1, -3], 1), (vec![1], false)); // Net scores may be negative.
+    }
+
+    #[test]
+    fn ballot_points_scores_borda_rankings() {
+        assert_eq!(ballot_points(BallotType::Borda, &[2, 0, 1]), vec![1, 0, 2]);
+        assert_eq!(ballot_points(BallotType::Score, &[7, 0, 10]), vec![7, 0, 10]); // As stored.
+        assert_eq!(ballot_points(BallotType::Approval, &[1, 0, 1]), vec![1, 0, 1]);
+    }
+
+    /// `RankedTally::counts` holding `ballots` of weight 1 over `candidates`.
+    fn ranked_counts(ballots: &[&[u16]], candidates: usize) -> Vec<u64> {
+        let len: usize = (0u16..1 << candidates).map(|s| s.count_ones() as usize).sum();
+        let mut counts = vec![0; len];
+        for ranking in ballots {
+            apply_ranking(&mut counts, ranking, candidates, 1, true).unwrap();
+        }
+        counts
+    }
+
+    #[test]
+    fn instant_runoff_transfers_eliminated_ballots() {
+        // First preferences 2/2/1: candidate 2 goes, its ballot moves to 1, who wins 3-2.
+        let counts = ranked_counts(&[&[0], &[0], &[1], &[1], &[2, 1]], 3);
+        let (winner, tie, last_round) = instant_runoff(&counts, 3);
+        assert_eq!((winner, tie), (Some(1), false));
+        assert_eq!(last_round, vec![2, 3, 0]);
+    }
+
+    #[test]
+    fn instant_runoff_majority_tie_and_empty() {
+        assert_eq!(instant_runoff(&ranked_counts(&[&[1, 0], &[1], &[0]], 2), 2).0, Some(1));
+        let (winner, tie, _) = instant_runoff(&ranked_counts(&[&[0], &[1]], 2), 2);
+        assert_eq!((winner, tie), (None, true)); // Both eliminated together.
+        let (winner, tie, _) = instant_runoff(&ranked_counts(&[], 3), 3);
+        assert_eq!((winner, tie), (None, false)); // No ballots.
+    }
+
+    #[test]
+    fn isqrt_edge_values() {
+        assert_eq!(isqrt(0), 0);
+        assert_eq!(isqrt(1), 1);
+        assert_eq!(isqrt(2), 1);
+        assert_eq!(isqrt(15), 3);
+        assert_eq!(isqrt(16), 4);
+        assert_eq!(isqrt(u64::MAX), u32::MAX as u64); // floor(sqrt(2^64 - 1)) = 2^32 - 1.
+        for n in [99u64, 10_000, 1 << 40, (1 << 62) + 12_345] {
+            let r = isqrt(n);
+            assert!(r * r <= n && (r + 1) as u128 * (r + 1) as u128 > n as u128);
+        }
+    }
+
+    /// `vote_weight` for a wallet holding `amount` of the weighting mint.
+    fn token_weight(weighting: fn(Pubkey) -> VoteWeighting, amount: u64) -> u64 {
+        let (key, wallet, mint, owner) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), token::spl_token::ID);
+        let mut data = vec![0u8; 165]; // Base token account layout.
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(wallet.as_ref());
+        data[64..72].copy_from_slice(&amount.to_le_bytes());
+        data[108] = 1; // Initialized.
+        let mut lamports = 0;
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        let account = UncheckedAccount::try_from(&info);
+        let poll = Poll { weighting: weighting(mint), ..Poll::default() };
+        vote_weight(&poll, &wallet, Some(&account), None, None, None, 0).unwrap().1
+    }
+
+    #[test]
+    fn quadratic_weighting_dampens_large_holders() {
+        let linear = |mint| VoteWeighting::TokenBalance { mint };
+        let quadratic = |mint| VoteWeighting::QuadraticToken { mint };
+        assert_eq!((token_weight(linear, 100), token_weight(linear, 10_000)), (100, 10_000)); // 100x the say.
+        assert_eq!((token_weight(quadratic, 100), token_weight(quadratic, 10_000)), (10, 100)); // Only 10x.
+        assert_eq!(token_weight(quadratic, 1), 1); // Smallest holding still counts.
+    }
+
+    #[test]
+    fn write_in_joins_matching_candidate() {
+        let poll = Poll { candidates: vec!["Alice".into(), "Bob".into()], max_candidates: 3, ..Poll::default() };
+        assert_eq!(poll.write_in_candidate("alice").unwrap(), Some(0));
+        assert_eq!(poll.write_in_candidate("  BOB ").unwrap(), Some(1));
+        assert_eq!(poll.write_in_candidate("Carol").unwrap(), None); // New entry.
+        assert!(poll.write_in_candidate("   ").is_err()); // Same name rules as init.
+    }
+
+    #[test]
+    fn write_in_respects_max_candidates() {
+        let poll = Poll { candidates: vec!["Alice".into(), "Bob".into()], max_candidates: 2, ..Poll::default() };
+        assert_eq!(poll.write_in_candidate("Carol").unwrap_err(), VotingError::TooManyCandidates.into());
+        assert_eq!(poll.write_in_candidate("alice").unwrap(), Some(0)); // Existing names still count.
+    }
+
+    #[test]
+    fn write_in_polls_reserve_no_room() {
+        let write_ins = PollOptions { allow_write_ins: true, max_candidates: 8, ..PollOptions::default() };
+        assert_eq!(write_ins.reserved_candidates(2), 2);
+        assert_eq!(PollOptions::default().reserved_candidates(2), 8);
+        let names = ["Alice", "Bob"];
+        let bare = Poll::space("t", "", &names, 2, 0);
+        assert_eq!(Poll::space("t", "", &["Alice", "Bob", "Carol"], 3, 0), bare + Poll::PER_CANDIDATE - MAX_NAME_BYTES + 5);
+    }
+
+    /// A commit-reveal poll voting until 100 with reveals until 200, and `wallet`'s
+    /// commitment to candidate 1 with `salt`.
+    fn committed(wallet: Pubkey, salt: [u8; 32]) -> (Poll, Voter) {
+        let poll = Poll {
+            candidates: vec!["A".into(), "B".into()],
+            approved: vec![true; 2],
+            commit_reveal: true,
+            end_ts: 100,
+            reveal_deadline_ts: 200,
+            ..Poll::default()
+        };
+        let voter = Voter { wallet, commitment: vote_commitment(1, &salt, &wallet), ..Voter::default() };
+        (poll, voter)
+    }
+
+    #[test]
+    fn reveal_accepts_matching_commitment() {
+        let (poll, voter) = committed(Pubkey::new_unique(), [7; 32]);
+        assert!(check_reveal(&poll, &voter, 1, &[7; 32], 150).is_ok());
+        assert_eq!(check_reveal(&poll, &voter, 0, &[7; 32], 150).unwrap_err(), VotingError::CommitmentMismatch.into()); // Other candidate.
+    }
+
+    #[test]
+    fn reveal_rejects_wrong_salt() {
+        let (poll, voter) = committed(Pubkey::new_unique(), [7; 32]);
+        assert_eq!(check_reveal(&poll, &voter, 1, &[8; 32], 150).unwrap_err(), VotingError::CommitmentMismatch.into());
+    }
+
+    #[test]
+    fn reveal_rejects_copied_commitment() {
+        let (poll, original) = committed(Pubkey::new_unique(), [7; 32]);
+        let copier = Voter { wallet: Pubkey::new_unique(), commitment: original.commitment, ..Voter::default() };
+        assert_eq!(check_reveal(&poll, &copier, 1, &[7; 32], 150).unwrap_err(), VotingError::CommitmentMismatch.into()); // Bound to the committer's wallet.
+    }
+
+    #[test]
+    fn reveal_only_within_phase_and_once() {
+        let (poll, mut voter) = committed(Pubkey::new_unique(), [7; 32]);
+        assert_eq!(check_reveal(&poll, &voter, 1, &[7; 32], 100).unwrap_err(), VotingError::RevealNotOpen.into()); // Still voting.
+        assert_eq!(check_reveal(&poll, &voter, 1, &[7; 32], 201).unwrap_err(), VotingError::RevealNotOpen.into()); // Too late.
+        assert!(check_reveal(&poll, &voter, 1, &[7; 32], 200).is_ok()); // Deadline itself counts.
+        voter.revealed = true;
+        assert_eq!(check_reveal(&poll, &voter, 1, &[7; 32], 150).unwrap_err(), VotingError::AlreadyRevealed.into());
+    }
+
+    /// A plurality poll tied 2-2 between A and B (C trails), settled at the given times.
+    fn tied(tie_break: TieBreak, settled: [i64; 3]) -> Poll {
+        Poll {
+            candidates: vec!["A".into(), "B".into(), "C".into()],
+            votes: vec![2, 2, 1],
+            downvotes: vec![0; 3],
+            last_increment_ts: settled.to_vec(),
+            tie_break,
+            ..Poll::default()
+        }
+    }
+
+    #[test]
+    fn tie_break_none_keeps_the_tie() {
+        let poll = tied(TieBreak::None, [30, 40, 10]);
+        assert_eq!(break_tie(&poll, Pubkey::new_unique(), Some(&[1; 32])).unwrap(), None);
+    }
+
+    #[test]
+    fn tie_break_earliest_lead_orders_by_settle_time() {
+        // A voted at t=10 and t=30, B at t=20 and t=40: A settled first. C is not a leader.
+        let poll = tied(TieBreak::EarliestLead, [30, 40, 5]);
+        assert_eq!(break_tie(&poll, Pubkey::new_unique(), None).unwrap(), Some(0));
+        let poll = tied(TieBreak::EarliestLead, [40, 30, 5]);
+        assert_eq!(break_tie(&poll, Pubkey::new_unique(), None).unwrap(), Some(1));
+        let poll = tied(TieBreak::EarliestLead, [30, 30, 5]);
+        assert_eq!(break_tie(&poll, Pubkey::new_unique(), None).unwrap(), None); // Same second.
+    }
+
+    #[test]
+    fn tie_break_random_picks_a_leader_from_the_slot_hash() {
+        let (poll, key) = (tied(TieBreak::Random, [30, 40, 10]), Pubkey::new_unique());
+        let mut picks = std::collections::BTreeSet::new();
+        for b in 0..32u8 {
+            let pick = break_tie(&poll, key, Some(&[b; 32])).unwrap().unwrap();
+            assert!(pick < 2); // Never the trailing candidate.
+            assert_eq!(break_tie(&poll, key, Some(&[b; 32])).unwrap(), Some(pick)); // Deterministic.
+            picks.insert(pick);
+        }
+        assert_eq!(picks.len(), 2); // The hash decides, not the candidate order.
+        assert_eq!(break_tie(&poll, key, None).unwrap_err(), VotingError::SlotHashesRequired.into());
+    }
+
+    #[test]
+    fn names_limit_bytes_and_chars() {
+        assert!(validate_candidate_name(&"a".repeat(MAX_NAME_CHARS)).is_ok());
+        assert!(validate_candidate_name(&"é".repeat(MAX_NAME_CHARS)).is_ok()); // 2 bytes each: exactly MAX_NAME_BYTES.
+        assert!(validate_candidate_name(&"🗳".repeat(MAX_NAME_BYTES / 4)).is_ok());
+        let too_long = VotingError::CandidateNameTooLong.into();
+        assert!(validate_candidate_name(&"a".repeat(MAX_NAME_CHARS + 1)).unwrap_err() == too_long);
+        assert!(validate_candidate_name(&"é".repeat(MAX_NAME_CHARS + 1)).unwrap_err() == too_long);
+        assert!(validate_candidate_name(&"🗳".repeat(MAX_NAME_BYTES / 4 + 1)).unwrap_err() == too_long); // Few chars, too many bytes.
+    }
+
+    #[test]
+    fn titles_limit_bytes_and_chars() {
+        assert!(validate_title(&"a".repeat(MAX_TITLE_CHARS)).is_ok());
+        assert!(validate_title(&"é".repeat(MAX_TITLE_CHARS)).is_ok());
+        assert!(validate_title(&"🗳".repeat(MAX_TITLE_BYTES / 4)).is_ok());
+        let too_long = VotingError::TitleTooLong.into();
+        assert!(validate_title(&"a".repeat(MAX_TITLE_CHARS + 1)).unwrap_err() == too_long);
+        assert!(validate_title(&"é".repeat(MAX_TITLE_CHARS + 1)).unwrap_err() == too_long);
+        assert!(validate_title(&"🗳".repeat(MAX_TITLE_BYTES / 4 + 1)).unwrap_err() == too_long);
+    }
+
+    #[test]
+    fn blank_text_rejected() {
+        for blank in ["", " ", "\t\n", "\u{3000}"] {
+            assert!(validate_candidate_name(blank).unwrap_err() == VotingError::EmptyCandidateName.into());
+            assert!(validate_title(blank).unwrap_err() == VotingError::EmptyTitle.into());
+        }
+    }
+
+    #[test]
+    fn hidden_characters_rejected() {
+        for text in ["Ali\nce", "Ali\tce", "Ali\u{7f}ce", "Ali\u{200B}ce", "Ali\u{200D}ce", "\u{202E}ecilA", "\u{FEFF}Alice", "Ali\u{2066}ce"] {
+            assert!(validate_candidate_name(text).unwrap_err() == VotingError::InvalidCharacters.into());
+            assert!(validate_title(text).unwrap_err() == VotingError::InvalidCharacters.into());
+        }
+        for text in ["Zoë", "José María", "李小龙", "Ana-Lu O'Neil", "🗳 Party"] {
+            assert!(validate_candidate_name(text).is_ok());
+            assert!(validate_title(text).is_ok());
+        }
+    }
+
+    #[test]
+    fn outcome_hash_matches_documented_encoding() {
+        let key = Pubkey::new_unique();
+        let poll = Poll {
+            candidates: vec!["Alice".into(), "Zoë".into(), String::new()],
+            votes: vec![7, u64::MAX, 0],
+            voter_count: 42,
+            end_ts: -1,
+            ..Poll::default()
+        };
+        let stored = poll.outcome_hash(&key); // What finalize writes.
+        assert_eq!(stored, results_hash(&key, &poll.candidates, &poll.votes, poll.voter_count, poll.end_ts));
+        let votes: Vec<u8> = poll.votes.iter().flat_map(|v| v.to_le_bytes()).collect(); // No length prefix.
+        let expected = anchor_lang::solana_program::hash::hashv(&[
+            key.as_ref(),
+            &poll.candidates.try_to_vec().unwrap(), // Borsh Vec<String>.
+            &votes,
+            &poll.voter_count.to_le_bytes(),
+            &poll.end_ts.to_le_bytes(),
+        ]);
+        assert_eq!(stored, expected.to_bytes());
+        assert_ne!(stored, poll.outcome_hash(&Pubkey::new_unique())); // Bound to the account.
+    }
+
+    /// A poll with every variable-size field at its largest: longest texts, `n` full-length
+    /// names with their aligned vectors, the full spent-leaf bitmap, every `Option` set and
+    /// the largest enum variants.
+    fn full_poll(n: usize) -> Poll {
+        let key = Some(Pubkey::new_unique());
+        Poll {
+            title: "t".repeat(MAX_TITLE_BYTES),
+            description: "d".repeat(256),
+            candidates: vec!["n".repeat(MAX_NAME_BYTES); n],
+            votes: vec![u64::MAX; n],
+            downvotes: vec![u64::MAX; n],
+            winners: vec![0; n],
+            last_increment_ts: vec![i64::MAX; n],
+            candidate_wallets: vec![Pubkey::new_unique(); n],
+            approved: vec![true; n],
+            spent_leaves: vec![0xff; RECEIPTLESS_BITMAP_BYTES],
+            winner_idx: Some(0),
+            allowlist_root: Some([1; 32]),
+            gate_mint: key,
+            gate_collection: key,
+            weight_root: Some([2; 32]),
+            registrar: key,
+            runoff_of: key,
+            runoff: key,
+            poll_id: Some(u64::MAX),
+            participation: key,
+            reward_mint: key,
+            receipt_mint: key,
+            fee_mint: key,
+            burn_mint: key,
+            certifier: key,
+            pending_authority: key,
+            weighting: VoteWeighting::TokenBalance { mint: Pubkey::new_unique() },
+            kind: PollKind::Referendum { pass_bps: 10_000 },
+            ballot_type: BallotType::Cumulative { budget: u16::MAX },
+            ..Poll::default()
+        }
+    }
+
+    #[test]
+    fn full_poll_fits_init_space() {
+        assert_eq!(full_poll(8).try_to_vec().unwrap().len(), Poll::INIT_SPACE); // Bounds are tight.
+    }
+
+    #[test]
+    fn space_matches_init_space_at_the_limits() {
+        let names = vec!["n".repeat(MAX_NAME_BYTES); 8];
+        let title = "t".repeat(MAX_TITLE_BYTES);
+        let description = "d".repeat(256);
+        assert_eq!(Poll::space(&title, &description, &names, 8, MAX_RECEIPTLESS_VOTERS), Poll::INIT_SPACE);
+    }
+
+    #[test]
+    fn space_covers_the_actual_poll_and_reserved_room() {
+        let mut poll = Poll {
+            title: "Board".into(),
+            candidates: vec!["Alice".into(), "Bob".into()],
+            votes: vec![0; 2],
+            downvotes: vec![0; 2],
+            last_increment_ts: vec![0; 2],
+            candidate_wallets: vec![Pubkey::new_unique(); 2],
+            approved: vec![true; 2],
+            ..full_poll(0) // Every fixed-size `Option` set.
+        };
+        poll.description.clear();
+        poll.spent_leaves.clear();
+        let space = Poll::space(&poll.title, &poll.description, &poll.candidates, 4, 0);
+        assert!(poll.try_to_vec().unwrap().len() <= space);
+        for _ in 0..2 {
+            poll.candidates.push("n".repeat(MAX_NAME_BYTES)); // Full-length additions fit the room.
+            poll.votes.push(u64::MAX);
+            poll.downvotes.push(u64::MAX);
+            poll.winners.push(0);
+            poll.last_increment_ts.push(0);
+            poll.candidate_wallets.push(Pubkey::new_unique());
+            poll.approved.push(true);
+        }
+        assert!(poll.try_to_vec().unwrap().len() <= space);
+        assert!(space < Poll::space(&poll.title, &poll.description, &poll.candidates, 5, 0)); // One more slot costs more.
+    }
+
+    #[test]
+    fn max_space_grows_past_eight_candidates() {
+        assert_eq!(Poll::max_space(2), Poll::INIT_SPACE);
+        assert_eq!(Poll::max_space(8), Poll::INIT_SPACE);
+        assert_eq!(Poll::max_space(MAX_EXPANDED_CANDIDATES), Poll::INIT_SPACE + 8 * Poll::PER_CANDIDATE);
+        let poll = full_poll(MAX_EXPANDED_CANDIDATES as usize);
+        assert_eq!(poll.try_to_vec().unwrap().len(), Poll::max_space(MAX_EXPANDED_CANDIDATES));
+    }
+
+    #[test]
+    fn move_end_shifts_later_deadlines() {
+        let mut poll = Poll {
+            start_ts: 1_000,
+            end_ts: 2_000,
+            commit_reveal: true,
+            reveal_deadline_ts: 3_000,
+            certifier: Some(Pubkey::new_unique()),
+            certification_deadline_ts: 5_000,
+            ..Poll::default()
+        };
+        poll.move_end(2_500).unwrap();
+        assert_eq!((poll.end_ts, poll.reveal_deadline_ts, poll.certification_deadline_ts), (2_500, 3_500, 5_500));
+        assert!(poll.move_end(1_000 + MAX_DURATION_SECS + 1).is_err()); // Over the duration cap.
+        assert_eq!(poll.end_ts, 2_500); // Unchanged on failure.
+    }
+
+    #[test]
+    fn move_end_refused_after_tabulation_started() {
+        let mut poll = Poll { hide_tallies: true, start_ts: 0, end_ts: 100, tabulated: 1, ..Poll::default() };
+        assert_eq!(poll.move_end(200).unwrap_err(), VotingError::AlreadyCounted.into());
+        assert_eq!(poll.end_ts, 100);
+    }
+
+    #[test]
+    fn move_end_refused_after_shards_folded() {
+        let mut poll = Poll { sharded: true, start_ts: 0, end_ts: 100, folded_shards: 1, ..Poll::default() };
+        assert_eq!(poll.move_end(200).unwrap_err(), VotingError::AlreadyCounted.into()); // Partly collected.
+        poll.folded_shards = 0;
+        poll.collected = true;
+        assert_eq!(poll.move_end(200).unwrap_err(), VotingError::AlreadyCounted.into());
+    }
+
+    #[test]
+    fn cancel_before_any_ballot() {
+        let poll = Poll { candidates: vec!["A".into(), "B".into()], votes: vec![0; 2], start_ts: 100, end_ts: 200, ..Poll::default() };
+        assert!(poll.require_cancellable(50).is_ok()); // Before start.
+        assert!(poll.require_cancellable(150).is_ok()); // Open but still empty.
+        let sharded = Poll { sharded: true, ..poll };
+        assert!(sharded.require_cancellable(50).is_ok());
+        assert_eq!(sharded.require_cancellable(100).unwrap_err(), VotingError::VotesAlreadyCast.into());
+    }
+
+    #[test]
+    fn cancel_refused_after_first_vote_or_finalize() {
+        let poll = |votes: Vec<u64>, voter_count| Poll { candidates: vec!["A".into(), "B".into()], votes, voter_count, ..Poll::default() };
+        let voted = poll(vec![0, 1], 1);
+        assert_eq!(voted.require_cancellable(150).unwrap_err(), VotingError::VotesAlreadyCast.into());
+        let retracted = poll(vec![0; 2], 1); // Tally empty, record kept.
+        assert_eq!(retracted.require_cancellable(150).unwrap_err(), VotingError::VotesAlreadyCast.into());
+        let finalized = Poll { finalized: true, ..poll(vec![0; 2], 0) };
+        assert_eq!(finalized.require_cancellable(150).unwrap_err(), VotingError::AlreadyFinalized.into());
+        let cancelled = Poll { cancelled: true, ..poll(vec![0; 2], 0) };
+        assert_eq!(cancelled.require_cancellable(150).unwrap_err(), VotingError::PollCancelled.into());
+    }
+
+    /// Tallies of a points-based poll after `ballots` of weight 1, as `record_points_ballot`
+    /// adds them.
+    fn points_tally(ballot_type: BallotType, ballots: &[Vec<u16>]) -> Vec<u64> {
+        let mut votes = vec![0u64; ballots[0].len()];
+        for ballot in ballots {
+            for (v, p) in votes.iter_mut().zip(ballot_points(ballot_type, ballot)) {
+                *v += p as u64;
+            }
+        }
+        votes
+    }
+
+    #[test]
+    fn score_winner_can_differ_from_plurality() {
+        // Two voters each back A and C; a fifth backs A. Everyone rates B a close second.
+        let ballots: Vec<Vec<u16>> = [[10, 9, 0], [10, 9, 0], [0, 9, 10], [0, 9, 10], [10, 8, 0]]
+            .iter()
+            .map(|s| score_ballot(s, 3).unwrap())
+            .collect();
+        let favourites = [0usize, 0, 2, 2, 0];
+        let mut plurality = vec![0u64; 3];
+        favourites.iter().for_each(|&c| plurality[c] += 1);
+        assert_eq!(compute_winner(&plurality), (Some(0), false)); // A leads 3-0-2 on first choices.
+        let scores = points_tally(BallotType::Score, &ballots);
+        assert_eq!(scores, vec![30, 44, 20]);
+        assert_eq!(compute_winner(&scores), (Some(1), false)); // B wins on scores.
+    }
+
+    #[test]
+    fn score_ballot_checks_length_and_range() {
+        assert_eq!(score_ballot(&[0, MAX_SCORE, 5], 3).unwrap(), vec![0, 10, 5]);
+        assert_eq!(score_ballot(&[1, 2], 3).unwrap_err(), VotingError::BadBallotLength.into());
+        assert_eq!(score_ballot(&[1, 2, 3, 4], 3).unwrap_err(), VotingError::BadBallotLength.into());
+        assert_eq!(score_ballot(&[1, MAX_SCORE + 1, 3], 3).unwrap_err(), VotingError::ScoreOutOfRange.into());
+    }
+
+    #[test]
+    fn cumulative_budget_concentrated_or_spread() {
+        assert!(cumulative_ballot(&[10, 0, 0], 3, 10, false).is_ok()); // All on one candidate.
+        assert!(cumulative_ballot(&[4, 3, 3], 3, 10, false).is_ok()); // Spread out.
+        let ballot_type = BallotType::Cumulative { budget: 10 };
+        assert_eq!(points_tally(ballot_type, &[vec![10, 0, 0], vec![4, 3, 3]]), vec![14, 3, 3]);
+        assert_eq!(points_tally(ballot_type, &[vec![0, 5, 5], vec![0, 5, 5], vec![10, 0, 0]]), vec![10, 10, 10]);
+    }
+
+    #[test]
+    fn cumulative_allocation_limits() {
+        assert_eq!(cumulative_ballot(&[6, 5, 0], 3, 10, true).unwrap_err(), VotingError::OverBudget.into());
+        assert_eq!(cumulative_ballot(&[9, 0, 0], 3, 10, false).unwrap_err(), VotingError::BudgetNotSpent.into());
+        assert!(cumulative_ballot(&[9, 0, 0], 3, 10, true).is_ok()); // Under-spending allowed.
+        assert_eq!(cumulative_ballot(&[0, 0, 0], 3, 10, true).unwrap_err(), VotingError::EmptyBallot.into());
+        assert_eq!(cumulative_ballot(&[10, 0], 3, 10, false).unwrap_err(), VotingError::BadBallotLength.into());
+        assert_eq!(cumulative_ballot(&[u16::MAX; 8], 8, u16::MAX, false).unwrap_err(), VotingError::OverBudget.into()); // No overflow.
+    }
+
+    #[test]
+    fn split_ballots_combine_into_bps_totals() {
+        let (first, second) = (vec![7_000, 3_000, 0], vec![2_500, 2_500, 5_000]);
+        assert!(split_ballot(&first, 3).is_ok() && split_ballot(&second, 3).is_ok());
+        assert_eq!(points_tally(BallotType::Split, &[first, second]), vec![9_500, 5_500, 5_000]); // 20_000 bps in all.
+    }
+
+    #[test]
+    fn split_ballot_must_total_exactly_100_percent() {
+        assert_eq!(split_ballot(&[5_000, 4_999, 0], 3).unwrap_err(), VotingError::BadAllocation.into());
+        assert_eq!(split_ballot(&[5_000, 5_001, 0], 3).unwrap_err(), VotingError::BadAllocation.into());
+        assert_eq!(split_ballot(&[5_000, 5_000], 3).unwrap_err(), VotingError::BadBallotLength.into());
+        assert!(split_ballot(&[10_000, 0, 0], 3).is_ok());
+    }
+
+    #[test]
+    fn referendum_supermajority_threshold() {
+        assert!(referendum_passed(6_667, 3_333, 6_667)); // Exactly 66.67%.
+        assert!(!referendum_passed(6_666, 3_334, 6_667)); // One vote below.
+        assert!(!referendum_passed(2, 1, 6_667)); // Two thirds is 66.66...%, short of 6667 bps.
+    }
+
+    #[test]
+    fn referendum_simple_majority_threshold() {
+        assert!(referendum_passed(50, 50, 5_000)); // Exactly half passes.
+        assert!(!referendum_passed(49, 51, 5_000)); // One vote below.
+        assert!(referendum_passed(u64::MAX, u64::MAX, 5_000)); // No overflow.
+    }
+
+    #[test]
+    fn referendum_without_votes_fails() {
+        assert!(!referendum_passed(0, 0, 5_000));
+        assert!(!referendum_passed(0, 0, 0)); // Even with no threshold.
+        assert!(referendum_passed(0, 1, 0)); // Any turnout passes 0 bps.
+    }
+
+    #[test]
+    fn tie_at_the_last_seat_seats_everyone_tied() {
+        assert_eq!(top_candidates(&[9, 7, 5, 5, 1], 3), (vec![0, 1, 2, 3], true)); // Third seat shared.
+        assert_eq!(top_candidates(&[5, 5, 5, 1], 2), (vec![0, 1, 2], true)); // Tie spans the boundary.
+        assert_eq!(top_candidates(&[9, 7, 5, 4, 1], 3), (vec![0, 1, 2], false)); // Clear cut.
+        assert_eq!(top_candidates(&[9, 9, 5, 1], 2), (vec![0, 1], false)); // Tie inside the seats is fine.
+        assert_eq!(top_candidates(&[1, 5, 5, 7], 3), (vec![3, 1, 2], false)); // Best first.
+    }
+
+    #[test]
+    fn runoff_when_leader_below_majority() {
+        assert_eq!(runoff_finalists(&[30, 40, 20, 10], 5_000).unwrap(), [1, 0]); // Leader at 40%.
+        assert_eq!(runoff_finalists(&[40, 35, 25], 5_000).unwrap(), [0, 1]);
+        assert_eq!(runoff_finalists(&[55, 45], 6_000).unwrap(), [0, 1]); // Higher bar.
+    }
+
+    #[test]
+    fn runoff_refused_when_leader_has_majority() {
+        let not_needed = VotingError::RunoffNotNeeded.into();
+        assert_eq!(runoff_finalists(&[55, 25, 20], 5_000).unwrap_err(), not_needed); // Leader at 55%.
+        assert_eq!(runoff_finalists(&[50, 30, 20], 5_000).unwrap_err(), not_needed); // Exactly the bar.
+        assert_eq!(runoff_finalists(&[0, 0, 0], 5_000).unwrap_err(), not_needed); // Nobody voted.
+        assert_eq!(runoff_finalists(&[40, 35, 25], 0).unwrap_err(), not_needed); // Runoffs off.
+        assert_eq!(runoff_finalists(&[40, 30, 30], 5_000).unwrap_err(), VotingError::RunoffTie.into());
+    }
+
+    #[test]
+    fn close_waits_for_the_reveal_phase() {
+        let (poll, _) = committed(Pubkey::new_unique(), [7; 32]); // Ends at 100, reveals until 200.
+        assert_eq!(poll.require_closable(100).unwrap_err(), VotingError::PollStillOpen.into());
+        assert_eq!(poll.require_closable(150).unwrap_err(), VotingError::PollStillOpen.into()); // Mid-reveal.
+        assert_eq!(poll.require_closable(200).unwrap_err(), VotingError::PollStillOpen.into());
+        assert!(poll.require_closable(201).is_ok());
+        let plain = Poll { commit_reveal: false, ..poll };
+        assert!(plain.require_closable(101).is_ok());
+        let cancelled = Poll { cancelled: true, ..plain };
+        assert!(cancelled.require_closable(0).is_ok());
+    }
+
+    #[test]
+    fn hidden_tally_poll_closes_only_once_finalized() {
+        let poll = Poll { hide_tallies: true, end_ts: 100, voter_count: 10, tabulated: 5, ..Poll::default() };
+        assert_eq!(poll.require_closable(50).unwrap_err(), VotingError::PollStillOpen.into());
+        assert_eq!(poll.require_closable(101).unwrap_err(), VotingError::NotFinalized.into()); // Half tabulated.
+        let tabulated = Poll { tabulated: 10, ..poll };
+        assert_eq!(tabulated.require_closable(101).unwrap_err(), VotingError::NotFinalized.into()); // Not published yet.
+        let finalized = Poll { finalized: true, ..tabulated };
+        assert!(finalized.require_closable(101).is_ok());
+        let cancelled = Poll { hide_tallies: true, cancelled: true, ..Poll::default() };
+        assert!(cancelled.require_closable(0).is_ok());
+    }
+
+    #[test]
+    fn quorum_met_at_exactly_the_threshold() {
+        let poll = |turnout, quorum| Poll { turnout, quorum, ..tied(TieBreak::None, [0; 3]) };
+        assert!(poll(10, 10).meets_quorum()); // Exactly quorum.
+        assert!(poll(11, 10).meets_quorum());
+        assert!(!poll(9, 10).meets_quorum()); // One below.
+        assert!(!poll(0, 1).meets_quorum());
+    }
+
+    #[test]
+    fn quorum_zero_means_no_requirement() {
+        assert!(Poll { turnout: 0, quorum: 0, ..Poll::default() }.meets_quorum()); // Even with no ballots.
+        assert!(Poll { turnout: 5, quorum: 0, ..tied(TieBreak::None, [0; 3]) }.meets_quorum());
+    }
+
+    #[test]
+    fn poll_large_layout_matches_size() {
+        assert_eq!(std::mem::size_of::<PollLarge>(), PollLarge::SIZE); // No hidden padding.
+    }
+
+    #[test]
+    fn poll_large_takes_fifty_candidates_and_votes_at_the_top() {
+        let mut poll = PollLarge { start_ts: 0, end_ts: 100, ..bytemuck::Zeroable::zeroed() };
+        let names: Vec<String> = (0..50).map(|i| format!("Candidate {i}")).collect();
+        poll.push_candidates(&names[..20]).unwrap(); // Created with a first batch...
+        poll.push_candidates(&names[20..]).unwrap(); // ...and topped up with `add_candidates_large`.
+        assert_eq!(poll.candidate_count, 50);
+        assert_eq!(padded_str(&poll.names[49]), "Candidate 49");
+        assert_eq!(poll.count_vote(49, 50).unwrap(), 1);
+        assert_eq!(poll.count_vote(48, 50).unwrap(), 1);
+        assert_eq!(poll.count_vote(49, 50).unwrap(), 2);
+        assert_eq!(poll.voter_count, 3);
+        assert_eq!(poll.count_vote(50, 50).unwrap_err(), VotingError::BadCandidate.into()); // Past the last name.
+        assert_eq!(poll.count_vote(0, 101).unwrap_err(), VotingError::Closed.into());
+        let more: Vec<String> = (50..65).map(|i| format!("Candidate {i}")).collect();
+        assert_eq!(poll.push_candidates(&more).unwrap_err(), VotingError::TooManyCandidates.into()); // 65 > 64.
+    }
+
+    #[test]
+    fn poll_tally_counts_in_place() {
+        assert_eq!(std::mem::size_of::<PollTally>(), PollTally::SIZE); // No hidden padding.
+        let mut tally = PollTally { start_ts: 0, end_ts: 100, candidate_count: 8, ..bytemuck::Zeroable::zeroed() };
+        assert_eq!(tally.count_vote(7, 0).unwrap(), 1); // Last slot, first second.
+        assert_eq!(tally.count_vote(7, 100).unwrap(), 2); // Last second.
+        assert_eq!((tally.votes, tally.voter_count), ([0, 0, 0, 0, 0, 0, 0, 2], 2));
+        assert_eq!(tally.count_vote(8, 50).unwrap_err(), VotingError::BadCandidate.into());
+        assert_eq!(tally.count_vote(0, -1).unwrap_err(), VotingError::TooEarly.into());
+        assert_eq!(tally.count_vote(0, 101).unwrap_err(), VotingError::Closed.into());
+    }
+
+    #[test]
+    fn sharded_polls_refuse_an_allowlist_root() {
+        let sharded = Poll { sharded: true, ..Poll::default() };
+        assert_eq!(sharded.require_allowlist_root(&Some([1; 32])).unwrap_err(), VotingError::ShardedOptions.into());
+        assert!(sharded.require_allowlist_root(&None).is_ok());
+        assert!(Poll::default().require_allowlist_root(&Some([1; 32])).is_ok());
+    }
+
+    /// A receiptless poll over `leaves` allowlist leaves with root `[1; 32]`.
+    fn receiptless(leaves: u16) -> Poll {
+        Poll {
+            receiptless_voters: leaves,
+            allowlist_root: Some([1; 32]),
+            spent_leaves: vec![0; (leaves as usize).div_ceil(8)],
+            ..Poll::default()
+        }
+    }
+
+    #[test]
+    fn leaf_index_spent_only_once() {
+        let mut poll = receiptless(1_000);
+        for leaf in [0, 7, 8, 999] {
+            poll.spend_leaf(leaf).unwrap();
+            assert_eq!(poll.spend_leaf(leaf).unwrap_err(), VotingError::AlreadyVoted.into());
+        }
+        poll.spend_leaf(1).unwrap(); // Neighbours stay free.
+        assert_eq!(poll.spent_leaves.iter().map(|b| b.count_ones()).sum::<u32>(), 5);
+        assert_eq!(poll.spend_leaf(1_000).unwrap_err(), VotingError::InvalidProof.into()); // Past the tree.
+    }
+
+    #[test]
+    fn receiptless_root_required_and_pinned_once_spent() {
+        let mut poll = receiptless(16);
+        assert_eq!(poll.require_allowlist_root(&None).unwrap_err(), VotingError::ReceiptlessOptions.into());
+        assert!(poll.require_allowlist_root(&Some([2; 32])).is_ok()); // No leaf spent yet.
+        poll.spend_leaf(3).unwrap();
+        assert_eq!(poll.require_allowlist_root(&Some([2; 32])).unwrap_err(), VotingError::ReceiptlessOptions.into());
+        assert!(poll.require_allowlist_root(&Some([1; 32])).is_ok()); // Same root.
+    }
+
+    #[test]
+    fn participation_polls_refuse_an_allowlist_root() {
+        let poll = Poll { allowlist_required: true, participation: Some(Pubkey::new_unique()), ..Poll::default() };
+        assert_eq!(poll.require_allowlist_root(&Some([1; 32])).unwrap_err(), VotingError::ParticipationOptions.into());
+        assert!(poll.require_allowlist_root(&None).is_ok());
+    }
+
+    /// An empty `ParticipationBitmap` with `capacity` slots, as `init_participation` makes it.
+    fn participation(capacity: u32) -> ParticipationBitmap {
+        ParticipationBitmap {
+            poll: Pubkey::new_unique(),
+            bump: 0,
+            capacity,
+            next_index: 0,
+            bits: vec![0; (capacity as usize).div_ceil(8)],
+        }
+    }
+
+    #[test]
+    fn participation_bitmap_of_1000_slots() {
+        let mut bitmap = participation(1_000);
+        assert_eq!(ParticipationBitmap::space(1_000), 45 + 125);
+        for expected in 0..1_000 {
+            assert_eq!(bitmap.assign().unwrap(), expected); // Sequential slots.
+        }
+        assert_eq!(bitmap.assign().unwrap_err(), VotingError::ParticipationFull.into());
+        for index in (0..1_000).step_by(3) {
+            bitmap.mark(index).unwrap();
+        }
+        assert!((0..1_000).all(|i| bitmap.is_set(i) == (i % 3 == 0))); // Only the voters' bits.
+        assert_eq!(bitmap.mark(999).unwrap_err(), VotingError::AlreadyVoted.into()); // 999 = 3 * 333.
+        bitmap.mark(998).unwrap(); // Last byte, other bit.
+        assert_eq!(bitmap.mark(1_000).unwrap_err(), VotingError::NotIndexed.into()); // Out of range.
+        assert!(!bitmap.is_set(1_000) && !bitmap.is_set(u32::MAX));
+    }
+
+    #[test]
+    fn participation_bitmap_smaller_than_capacity() {
+        let mut bitmap = ParticipationBitmap { bits: vec![0; 2], ..participation(1_000) }; // 16 bits only.
+        assert!(!bitmap.is_set(500)); // Reads past the bits are "not voted", not a panic.
+        assert_eq!(bitmap.mark(500).unwrap_err(), VotingError::NotIndexed.into()); // Nor are writes.
+        bitmap.mark(15).unwrap();
+        assert!(bitmap.is_set(15));
+    }
+
+    /// A v1 poll account as the old program wrote it: discriminator, then the v1 fields.
+    fn v1_account(authority: Pubkey, votes: Vec<u64>, finalized: bool) -> Vec<u8> {
+        let mut data = <Poll as Discriminator>::DISCRIMINATOR.to_vec();
+        data.extend(authority.try_to_vec().unwrap());
+        data.extend("Board".to_string().try_to_vec().unwrap());
+        data.extend(vec!["Alice".to_string(), "Bob".to_string()].try_to_vec().unwrap());
+        data.extend(votes.try_to_vec().unwrap());
+        data.extend(100i64.to_le_bytes()); // start_ts
+        data.extend(200i64.to_le_bytes()); // end_ts
+        data.extend([254, 0]); // bump, cancelled
+        data.extend([u8::from(finalized)]);
+        data.extend(Some(1u8).try_to_vec().unwrap()); // winner_idx
+        data.extend([0, 0]); // is_tie, paused
+        data.extend(5_000u64.to_le_bytes()); // finalize_bounty
+        data
+    }
+
+    #[test]
+    fn v1_account_migrates_to_the_current_layout() {
+        let (authority, key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let old = PollV1::read(&v1_account(authority, vec![2, 3], true)).unwrap();
+        let mut account = vec![0u8; 8 + Poll::INIT_SPACE]; // Grown by `migrate_poll`.
+        old.upgrade(&key).unwrap().try_serialize(&mut &mut account[..]).unwrap();
+        let poll = Poll::try_deserialize(&mut &account[..]).unwrap();
+        assert_eq!(poll.version, POLL_VERSION);
+        assert_eq!((poll.authority, poll.title.as_str(), poll.bump), (authority, "Board", 254));
+        assert_eq!(poll.candidates, vec!["Alice".to_string(), "Bob".to_string()]);
+        assert_eq!((poll.votes.clone(), poll.voter_count, poll.turnout), (vec![2, 3], 5, 5));
+        assert_eq!((poll.start_ts, poll.end_ts, poll.finalize_bounty), (100, 200, 5_000));
+        assert!(poll.require_consistent().is_ok()); // Aligned vectors filled in.
+        assert_eq!(poll.candidate_wallets, vec![authority; 2]);
+        assert_eq!((poll.winner_idx, poll.winners.clone(), poll.quorum_met), (Some(1), vec![1], true));
+        assert_eq!(poll.results_hash, poll.outcome_hash(&key));
+    }
+
+    #[test]
+    fn v1_read_rejects_other_accounts() {
+        let mut data = v1_account(Pubkey::new_unique(), vec![0, 0], false);
+        assert!(PollV1::read(&data).is_ok());
+        assert_eq!(PollV1::read(&data[..8]).err(), Some(VotingError::UnsupportedVersion.into()));
+        assert_eq!(PollV1::read(&data[..40]).err(), Some(VotingError::UnsupportedVersion.into())); // Truncated.
+        data[0] ^= 1;
+        assert_eq!(PollV1::read(&data).err(), Some(VotingError::UnsupportedVersion.into())); // Not a Poll.
+        let unfinalized = PollV1::read(&v1_account(Pubkey::new_unique(), vec![0, 0], false)).unwrap();
+        assert_eq!(unfinalized.upgrade(&Pubkey::new_unique()).unwrap().results_hash, [0; 32]); // Nothing to commit to.
+    }
+
+    /// `poll` written to and read back from account data, as a later instruction would see it.
+    fn stored(poll: &Poll) -> Poll {
+        let mut data = vec![0u8; 8 + Poll::INIT_SPACE];
+        poll.try_serialize(&mut &mut data[..]).unwrap();
+        Poll::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    #[test]
+    fn mismatched_tallies_fail_cleanly() {
+        let poll = Poll { start_ts: 0, end_ts: 100, ..tied(TieBreak::None, [0; 3]) };
+        let poll = Poll { candidate_wallets: vec![Pubkey::default(); 3], approved: vec![true; 3], ..poll };
+        assert!(stored(&poll).require_consistent().is_ok());
+        assert!(stored(&poll).require_voting_open(50).is_ok());
+        let short = stored(&Poll { votes: vec![2, 2], ..poll });
+        assert_eq!(short.require_consistent().unwrap_err(), VotingError::CorruptPoll.into());
+        assert_eq!(short.require_voting_open(50).unwrap_err(), VotingError::CorruptPoll.into()); // Before any indexing.
+        assert_eq!(short.require_setup_phase(-1).unwrap_err(), VotingError::CorruptPoll.into());
+        let long = stored(&Poll { approved: vec![true; 4], ..tied(TieBreak::None, [0; 3]) });
+        assert_eq!(long.require_consistent().unwrap_err(), VotingError::CorruptPoll.into());
+    }
+
+    #[test]
+    fn duplicate_names_exact_case_and_whitespace() {
+        let names = ["Alice", "Bob"];
+        let fresh = |name| require_new_candidate(names.iter().copied(), name);
+        assert_eq!(fresh("Alice").unwrap_err(), VotingError::DuplicateCandidate.into()); // Exact.
+        assert_eq!(fresh("ALICE").unwrap_err(), VotingError::DuplicateCandidate.into()); // Case only.
+        assert_eq!(fresh("bOb").unwrap_err(), VotingError::DuplicateCandidate.into());
+        assert_eq!(fresh("  Alice\t").unwrap_err(), VotingError::DuplicateCandidate.into()); // Whitespace only.
+        assert_eq!(fresh(" alice ").unwrap_err(), VotingError::DuplicateCandidate.into()); // Both.
+        assert!(fresh("Alicia").is_ok());
+        assert!(fresh("Al ice").is_ok()); // Inner whitespace is part of the name.
+        assert!(require_new_candidate(std::iter::empty(), "Alice").is_ok());
+    }
+
+    #[test]
+    fn same_candidate_folds_ascii_case_only() {
+        assert!(same_candidate("Zoë", " zoë "));
+        assert!(!same_candidate("Zoë", "ZOË")); // Non-ASCII case is kept apart.
+        assert!(!same_candidate("Alice", "Alice2"));
+    }
+
+    #[test]
+    fn close_waits_for_reward_tokens() {
+        let funded = Poll { reward_mint: Some(Pubkey::new_unique()), reward_amount: 10, rewards_outstanding: 25, end_ts: 100, ..Poll::default() };
+        assert_eq!(funded.require_closable(101).unwrap_err(), VotingError::RewardsNotWithdrawn.into());
+        let cancelled = Poll { cancelled: true, ..funded };
+        assert_eq!(cancelled.require_closable(0).unwrap_err(), VotingError::RewardsNotWithdrawn.into()); // Even when cancelled.
+        let empty = Poll { rewards_outstanding: 0, ..cancelled };
+        assert!(empty.require_closable(0).is_ok());
+        let unfunded = Poll { reward_mint: Some(Pubkey::new_unique()), reward_amount: 10, end_ts: 100, ..Poll::default() };
+        assert!(unfunded.require_closable(101).is_ok());
+    }
+
+    #[test]
+    fn reward_claimed_once_per_voter() {
+        let poll = Poll { reward_mint: Some(Pubkey::new_unique()), reward_amount: 10, end_ts: 100, ..Poll::default() };
+        let mut voter = Voter { has_voted: true, ..Voter::default() };
+        assert!(check_claim(&poll, &voter, 10, 101).is_ok()); // Vault holds exactly one reward.
+        assert_eq!(check_claim(&poll, &voter, 10, 100).unwrap_err(), VotingError::PollStillOpen.into());
+        voter.reward_claimed = true;
+        assert_eq!(check_claim(&poll, &voter, 1_000, 101).unwrap_err(), VotingError::RewardAlreadyClaimed.into());
+    }
+
+    #[test]
+    fn reward_claim_fails_on_an_empty_vault() {
+        let poll = Poll { reward_mint: Some(Pubkey::new_unique()), reward_amount: 10, end_ts: 100, ..Poll::default() };
+        let voter = Voter { has_voted: true, ..Voter::default() };
+        assert_eq!(check_claim(&poll, &voter, 0, 101).unwrap_err(), VotingError::RewardsExhausted.into());
+        assert_eq!(check_claim(&poll, &voter, 9, 101).unwrap_err(), VotingError::RewardsExhausted.into()); // Short by one.
+        let (secret, committed) = committed(Pubkey::new_unique(), [7; 32]);
+        let secret = Poll { reward_mint: poll.reward_mint, reward_amount: 10, ..secret };
+        assert_eq!(check_claim(&secret, &committed, 10, 300).unwrap_err(), VotingError::NotRevealed.into());
+    }
+
+    /// Runs `require_not_paused` against a config account owned by `owner` with `paused` set.
+    fn pause_check(owner: Pubkey, paused: bool) -> Result<()> {
+        let config = Config {
+            admin: Pubkey::new_unique(),
+            fee_lamports: 0,
+            fee_recipient: Pubkey::new_unique(),
+            creation_deposit_lamports: 0,
+            deposit_min_ballots: 0,
+            bump: 255,
+            pending_admin: None,
+            paused,
+            creators_restricted: false,
+            max_polls_per_window: 0,
+            creation_window_secs: 0,
+        };
+        let mut data = vec![0u8; 8 + Config::INIT_SPACE];
+        config.try_serialize(&mut &mut data[..]).unwrap();
+        let (key, mut lamports) = (Pubkey::new_unique(), 0);
+        let info = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, 0);
+        require_not_paused(&UncheckedAccount::try_from(&info))
+    }
+
+    #[test]
+    fn pause_switch_blocks_while_set() {
+        assert_eq!(pause_check(crate::ID, true).unwrap_err(), VotingError::ProtocolPaused.into());
+        assert!(pause_check(crate::ID, false).is_ok()); // Flipped back.
+    }
+
+    #[test]
+    fn pause_ignores_a_missing_or_foreign_config() {
+        assert!(pause_check(Pubkey::default(), true).is_ok()); // System-owned: `init_config` never ran.
+        assert!(pause_check(Pubkey::new_unique(), true).is_ok()); // Not ours, so not trusted.
+    }
+
+    const NOW: i64 = 1_700_000_000;
+
+    #[test]
+    fn schedule_duration_bounds() {
+        assert!(validate_schedule(NOW, NOW + MIN_DURATION_SECS, NOW).is_ok()); // Exactly the minimum.
+        assert!(validate_schedule(NOW, NOW + MIN_DURATION_SECS + 1, NOW).is_ok());
+        assert_eq!(validate_schedule(NOW, NOW + MIN_DURATION_SECS - 1, NOW).unwrap_err(), VotingError::DurationTooShort.into());
+        assert!(validate_schedule(NOW, NOW + MAX_DURATION_SECS, NOW).is_ok()); // Exactly the maximum.
+        assert!(validate_schedule(NOW, NOW + MAX_DURATION_SECS - 1, NOW).is_ok());
+        assert_eq!(validate_schedule(NOW, NOW + MAX_DURATION_SECS + 1, NOW).unwrap_err(), VotingError::DurationTooLong.into());
+    }
+
+    #[test]
+    fn schedule_must_end_in_the_future() {
+        let start = NOW - START_SKEW_SECS;
+        assert_eq!(validate_schedule(start, NOW, NOW).unwrap_err(), VotingError::EndInPast.into()); // Ends right now.
+        assert_eq!(validate_schedule(start, NOW - 1, NOW).unwrap_err(), VotingError::EndInPast.into());
+        assert!(validate_schedule(start, start + MIN_DURATION_SECS, NOW).is_ok()); // Started within the skew.
+        assert_eq!(validate_schedule(NOW + 10, NOW + 10, NOW).unwrap_err(), VotingError::BadSchedule.into()); // Empty window.
+    }
+
+    #[test]
+    fn schedule_start_bounds() {
+        let long = MIN_DURATION_SECS * 2;
+        assert!(validate_schedule(NOW - START_SKEW_SECS, NOW + long, NOW).is_ok());
+        assert_eq!(validate_schedule(NOW - START_SKEW_SECS - 1, NOW + long, NOW).unwrap_err(), VotingError::StartInPast.into());
+        let far = NOW + MAX_START_DELAY_SECS;
+        assert!(validate_schedule(far, far + long, NOW).is_ok());
+        assert_eq!(validate_schedule(far + 1, far + 1 + long, NOW).unwrap_err(), VotingError::StartTooFar.into());
+    }
+
+    fn rate_limited(max_polls_per_window: u32) -> Config {
+        Config {
+            admin: Pubkey::new_unique(),
+            fee_lamports: 0,
+            fee_recipient: Pubkey::new_unique(),
+            creation_deposit_lamports: 0,
+            deposit_min_ballots: 0,
+            bump: 255,
+            pending_admin: None,
+            paused: false,
+            creators_restricted: false,
+            max_polls_per_window,
+            creation_window_secs: 86_400,
+        }
+    }
+
+    #[test]
+    fn creation_rate_limit_caps_each_window() {
+        let config = rate_limited(3);
+        let authority = Pubkey::new_unique();
+        let mut stats = CreatorStats { authority, polls_created_in_window: 0, window_start_ts: 0, bump: 0 };
+        let create = |stats: &mut CreatorStats, now| count_creation(Some(&config), Some(stats), authority, Some(254), now);
+        for i in 0..3 {
+            create(&mut stats, NOW + i).unwrap(); // Up to the cap.
+        }
+        assert_eq!(stats.polls_created_in_window, 3);
+        assert_eq!(stats.window_start_ts, NOW);
+        assert_eq!(stats.bump, 254);
+        assert_eq!(create(&mut stats, NOW + 10).unwrap_err(), VotingError::CreationRateLimited.into()); // N+1.
+        assert_eq!(create(&mut stats, NOW + 86_399).unwrap_err(), VotingError::CreationRateLimited.into());
+        assert_eq!(stats.polls_created_in_window, 3); // Rejected creations don't count.
+        create(&mut stats, NOW + 86_400).unwrap(); // The window rolled over.
+        assert_eq!(stats.polls_created_in_window, 1);
+        assert_eq!(stats.window_start_ts, NOW + 86_400);
+    }
+
+    #[test]
+    fn creation_rate_limit_off_or_missing_stats() {
+        let authority = Pubkey::new_unique();
+        assert!(count_creation(None, None, authority, None, NOW).is_ok()); // No config on this cluster.
+        assert!(count_creation(Some(&rate_limited(0)), None, authority, None, NOW).is_ok()); // 0 = unlimited.
+        assert_eq!(
+            count_creation(Some(&rate_limited(3)), None, authority, None, NOW).unwrap_err(),
+            VotingError::CreatorStatsRequired.into()
+        );
+    }
+
+    fn won(finalized_at: i64) -> Poll {
+        Poll { finalized: true, finalized_at, quorum_met: true, winner_idx: Some(1), ..Poll::default() }
+    }
+
+    #[test]
+    fn payout_waits_for_the_dispute_window() {
+        let after = NOW + DISPUTE_WINDOW_SECS + 1;
+        let poll = won(NOW);
+        assert_eq!(poll.require_payable(NOW).unwrap_err(), VotingError::DisputeWindowOpen.into());
+        assert_eq!(poll.require_payable(NOW + DISPUTE_WINDOW_SECS).unwrap_err(), VotingError::DisputeWindowOpen.into()); // Last contestable second.
+        assert_eq!(poll.require_payable(after).unwrap(), 1);
+        let certified = Poll { certifier: Some(Pubkey::new_unique()), certified: true, ..won(NOW) };
+        assert_eq!(certified.require_payable(NOW).unwrap(), 1); // The certifier signed off.
+        let unsigned = Poll { certifier: Some(Pubkey::new_unique()), certification_deadline_ts: after + 10, ..won(NOW) };
+        assert_eq!(unsigned.require_payable(after).unwrap_err(), VotingError::NotCertified.into());
+    }
+
+    #[test]
+    fn payout_follows_dispute_rulings() {
+        let after = NOW + DISPUTE_WINDOW_SECS + 1;
+        let disputed = Poll { disputed: true, ..won(NOW) };
+        assert_eq!(disputed.require_payable(after).unwrap_err(), VotingError::ResultDisputed.into()); // Blocked while open.
+        let upheld = Poll { disputed: false, ..disputed }; // `resolve_dispute(true)`.
+        assert_eq!(upheld.require_payable(after).unwrap(), 1);
+        let voided = Poll { voided: true, ..upheld }; // `resolve_dispute(false)`.
+        assert_eq!(voided.require_payable(after).unwrap_err(), VotingError::ResultVoided.into());
+        assert_eq!(voided.require_payable(i64::MAX).unwrap_err(), VotingError::ResultVoided.into()); // For good.
+        assert!(voided.prize_refundable()); // Funders get their money back instead.
+    }
+
+    #[test]
+    fn apply_ranking_removal_undoes_addition() {
+        let mut counts = ranked_counts(&[&[2, 0]], 3);
+        apply_ranking(&mut counts, &[2, 0], 3, 1, false).unwrap();
+        assert!(counts.iter().all(|&c| c == 0));
+        assert!(apply_ranking(&mut counts, &[1], 3, 1, false).is_err()); // Nothing to remove.
+    }
 }
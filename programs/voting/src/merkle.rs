@@ -0,0 +1,165 @@
+//! Merkle allowlist helpers shared by the program and off-chain tooling.
+//!
+//! Leaves are `sha256(wallet)`; inner nodes are `sha256(min(a, b) || max(a, b))`, so proofs
+//! are plain lists of sibling hashes with no left/right flags. Leaves (32-byte input) and
+//! inner nodes (64-byte input) can't collide. Snapshot-weight trees use
+//! `sha256(wallet || balance_le)` leaves (40-byte input) and receiptless polls
+//! `sha256(wallet || index_le)` leaves (34-byte input), so they can't collide either.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::hash::hashv;
+
+/// Leaf hash for an eligible wallet.
+pub fn leaf_hash(wallet: &Pubkey) -> [u8; 32] {
+    hashv(&[wallet.as_ref()]).to_bytes()
+}
+
+/// Leaf hash for a wallet's snapshot balance.
+pub fn weight_leaf_hash(wallet: &Pubkey, balance: u64) -> [u8; 32] {
+    hashv(&[wallet.as_ref(), &balance.to_le_bytes()]).to_bytes()
+}
+
+/// Leaf hash for the wallet at `index` of a receiptless poll's allowlist.
+pub fn indexed_leaf_hash(wallet: &Pubkey, index: u16) -> [u8; 32] {
+    hashv(&[wallet.as_ref(), &index.to_le_bytes()]).to_bytes()
+}
+
+/// Parent hash of two nodes, order-independent.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[a, b]).to_bytes()
+    } else {
+        hashv(&[b, a]).to_bytes()
+    }
+}
+
+/// Folds `proof` over `leaf` and compares the result with `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling)); // Walk up to the root.
+    &computed == root
+}
+
+/// Off-chain tree builder used to publish roots and hand out proofs.
+#[cfg(feature = "client")]
+pub struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>, // layers[0] = leaves, last = [root].
+}
+
+#[cfg(feature = "client")]
+impl MerkleTree {
+    /// Builds a tree over already-hashed leaves (see `leaf_hash`). An unpaired node at the end
+    /// of a layer is carried up unchanged.
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut layers = vec![leaves];
+        while layers.last().map_or(0, |l| l.len()) > 1 {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+        Self { layers }
+    }
+
+    /// Builds a tree whose leaves are `leaf_hash(wallet)` for each wallet, in order.
+    pub fn from_wallets(wallets: &[Pubkey]) -> Self {
+        Self::new(wallets.iter().map(leaf_hash).collect())
+    }
+
+    /// Builds a snapshot-weight tree whose leaves are `weight_leaf_hash(wallet, balance)`.
+    pub fn from_balances(balances: &[(Pubkey, u64)]) -> Self {
+        Self::new(
+            balances
+                .iter()
+                .map(|(wallet, balance)| weight_leaf_hash(wallet, *balance))
+                .collect(),
+        )
+    }
+
+    /// Builds a receiptless-poll tree whose leaves are `indexed_leaf_hash(wallet, i)` for the
+    /// wallet at position `i`.
+    pub fn from_indexed_wallets(wallets: &[Pubkey]) -> Self {
+        Self::new(
+            wallets
+                .iter()
+                .enumerate()
+                .map(|(i, wallet)| indexed_leaf_hash(wallet, i as u16))
+                .collect(),
+        )
+    }
+
+    /// Root to store on the poll; all zeros for an empty tree.
+    pub fn root(&self) -> [u8; 32] {
+        self.layers
+            .last()
+            .and_then(|l| l.first())
+            .copied()
+            .unwrap_or([0; 32])
+    }
+
+    /// Sibling hashes from leaf `index` up to the root, or `None` if out of range.
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.layers[0].len() {
+            return None;
+        }
+        let mut proof = Vec::new();
+        let mut idx = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(idx ^ 1) {
+                proof.push(*sibling); // Unpaired nodes have no sibling at this level.
+            }
+            idx /= 2;
+        }
+        Some(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_hand_built_proofs() {
+        let wallets: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = wallets.iter().map(leaf_hash).collect();
+        let ab = hash_pair(&leaves[0], &leaves[1]);
+        let root = hash_pair(&ab, &leaves[2]); // Odd leaf carried up.
+        assert!(verify(&[leaves[1], leaves[2]], &root, leaves[0]));
+        assert!(verify(&[ab], &root, leaves[2]));
+        assert!(!verify(&[leaves[0], leaves[2]], &root, leaves[0])); // Wrong sibling.
+        assert!(!verify(&[ab], &root, leaf_hash(&Pubkey::new_unique()))); // Not in the tree.
+    }
+
+    #[test]
+    fn hash_pair_is_order_independent() {
+        let (a, b) = ([1u8; 32], [2u8; 32]);
+        assert_eq!(hash_pair(&a, &b), hash_pair(&b, &a));
+    }
+
+    #[test]
+    fn leaf_kinds_differ() {
+        let wallet = Pubkey::new_unique();
+        assert_ne!(leaf_hash(&wallet), weight_leaf_hash(&wallet, 0));
+        assert_ne!(leaf_hash(&wallet), indexed_leaf_hash(&wallet, 0));
+    }
+
+    #[cfg(feature = "client")]
+    #[test]
+    fn tree_proofs_verify() {
+        let wallets: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let tree = MerkleTree::from_wallets(&wallets);
+        for (i, wallet) in wallets.iter().enumerate() {
+            assert!(verify(&tree.proof(i).unwrap(), &tree.root(), leaf_hash(wallet)));
+        }
+        assert!(tree.proof(wallets.len()).is_none());
+        assert_eq!(MerkleTree::new(Vec::new()).root(), [0; 32]);
+    }
+}
@@ -0,0 +1,88 @@
+//! Just enough of the Metaplex Token Metadata layout to read an NFT's collection, so
+//! collection-gated polls don't depend on the `mpl-token-metadata` crate.
+//!
+//! Compiled only with the `nft-gate` feature.
+
+use anchor_lang::prelude::*;
+
+use crate::VotingError;
+
+/// Metaplex Token Metadata program.
+pub mod mpl_token_metadata {
+    anchor_lang::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+const KEY_METADATA_V1: u8 = 4; // `Key::MetadataV1` discriminant.
+
+/// Fields of a metadata account the program cares about.
+pub struct Metadata {
+    pub mint: Pubkey,                      // NFT mint this metadata describes.
+    pub collection: Option<(Pubkey, bool)>, // (collection mint, verified).
+}
+
+/// Little cursor over Borsh-encoded bytes that fails with `InvalidMetadata` on short data.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let end = self.pos.checked_add(n).ok_or(VotingError::InvalidMetadata)?;
+        let bytes = self
+            .data
+            .get(self.pos..end)
+            .ok_or(VotingError::InvalidMetadata)?;
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn pubkey(&mut self) -> Result<Pubkey> {
+        Ok(Pubkey::try_from(self.take(32)?).unwrap())
+    }
+
+    fn skip_string(&mut self) -> Result<()> {
+        let len = self.u32()? as usize;
+        self.take(len).map(|_| ())
+    }
+}
+
+/// Reads a metadata account owned by the Token Metadata program.
+pub fn unpack_metadata(info: &AccountInfo) -> Result<Metadata> {
+    require_keys_eq!(*info.owner, mpl_token_metadata::ID, VotingError::InvalidMetadata);
+    let data = info.try_borrow_data()?;
+    let mut r = Reader { data: &data, pos: 0 };
+    require!(r.u8()? == KEY_METADATA_V1, VotingError::InvalidMetadata); // Metadata account kind.
+    r.take(32)?; // update_authority
+    let mint = r.pubkey()?;
+    r.skip_string()?; // name
+    r.skip_string()?; // symbol
+    r.skip_string()?; // uri
+    r.take(2)?; // seller_fee_basis_points
+    if r.u8()? == 1 {
+        let creators = r.u32()? as usize;
+        r.take(creators.checked_mul(34).ok_or(VotingError::InvalidMetadata)?)?; // address + verified + share
+    }
+    r.take(2)?; // primary_sale_happened + is_mutable
+    if r.u8()? == 1 {
+        r.take(1)?; // edition_nonce
+    }
+    if r.u8()? == 1 {
+        r.take(1)?; // token_standard
+    }
+    let collection = if r.u8()? == 1 {
+        let verified = r.u8()? == 1;
+        Some((r.pubkey()?, verified))
+    } else {
+        None
+    };
+    Ok(Metadata { mint, collection })
+}
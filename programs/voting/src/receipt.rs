@@ -0,0 +1,137 @@
+//! Hand-built CPIs for participation receipts: a 0-decimal mint with Metaplex metadata, and
+//! one token minted to each voter's associated token account (created with
+//! `token::create_associated_token_account`). Keeps `anchor-spl` and `mpl-token-metadata` out
+//! of the build.
+//!
+//! Compiled only with the `receipt-nft` feature.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+/// Metaplex Token Metadata program.
+pub mod mpl_token_metadata {
+    anchor_lang::declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+/// Size of an SPL Token mint account.
+pub const MINT_LEN: usize = 82;
+/// Token Metadata limits for `name` and `uri`.
+pub const MAX_NAME_BYTES: usize = 32;
+pub const MAX_URI_BYTES: usize = 200;
+/// Symbol of every receipt mint.
+pub const SYMBOL: &str = "VOTE";
+
+const INITIALIZE_MINT2: u8 = 20; // Token program `InitializeMint2`.
+const MINT_TO: u8 = 7; // Token program `MintTo`.
+const CREATE_METADATA_V3: u8 = 33; // Token Metadata `CreateMetadataAccountV3`.
+
+/// Metadata PDA of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+        &mpl_token_metadata::ID,
+    )
+    .0
+}
+
+/// `InitializeMint2` with 0 decimals, `authority` as mint authority and no freeze authority.
+pub fn initialize_mint<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &Pubkey,
+) -> Result<()> {
+    let mut data = vec![INITIALIZE_MINT2, 0]; // Discriminator + decimals.
+    data.extend_from_slice(authority.as_ref());
+    data.push(0); // COption::None freeze authority.
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![AccountMeta::new(*mint.key, false)],
+        data,
+    };
+    invoke_signed(&ix, &[mint.clone(), token_program.clone()], &[])?;
+    Ok(())
+}
+
+/// Accounts of a `CreateMetadataAccountV3` call.
+pub struct MetadataAccounts<'a, 'info> {
+    pub metadata_program: &'a AccountInfo<'info>, // Token Metadata program.
+    pub metadata: &'a AccountInfo<'info>,         // Metadata PDA of `mint` (see `metadata_address`).
+    pub mint: &'a AccountInfo<'info>,             // Mint being described.
+    pub authority: &'a AccountInfo<'info>,        // Program PDA: mint and update authority.
+    pub payer: &'a AccountInfo<'info>,            // Funds the metadata account.
+    pub system_program: &'a AccountInfo<'info>,   // For account creation.
+}
+
+/// `CreateMetadataAccountV3` for `a.mint`, with `a.authority` (a program PDA signing with
+/// `signer_seeds`) as mint and update authority. No creators, collection or uses; mutable.
+pub fn create_metadata(
+    a: MetadataAccounts,
+    name: &str,
+    symbol: &str,
+    uri: &str,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![CREATE_METADATA_V3];
+    for text in [name, symbol, uri] {
+        data.extend_from_slice(&(text.len() as u32).to_le_bytes()); // Borsh string.
+        data.extend_from_slice(text.as_bytes());
+    }
+    data.extend_from_slice(&0u16.to_le_bytes()); // seller_fee_basis_points
+    data.extend_from_slice(&[0, 0, 0]); // creators, collection, uses: None
+    data.push(1); // is_mutable
+    data.push(0); // collection_details: None
+    let ix = Instruction {
+        program_id: mpl_token_metadata::ID,
+        accounts: vec![
+            AccountMeta::new(*a.metadata.key, false),
+            AccountMeta::new_readonly(*a.mint.key, false),
+            AccountMeta::new_readonly(*a.authority.key, true), // Mint authority.
+            AccountMeta::new(*a.payer.key, true),
+            AccountMeta::new_readonly(*a.authority.key, true), // Update authority.
+            AccountMeta::new_readonly(*a.system_program.key, false),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[
+            a.metadata.clone(),
+            a.mint.clone(),
+            a.authority.clone(),
+            a.payer.clone(),
+            a.system_program.clone(),
+            a.metadata_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// `MintTo` of `amount` tokens of `mint` into `to`, signed by the PDA `authority`.
+pub fn mint_to<'info>(
+    token_program: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let mut data = vec![MINT_TO];
+    data.extend_from_slice(&amount.to_le_bytes());
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*mint.key, false),
+            AccountMeta::new(*to.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[mint.clone(), to.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Minimal read-only view of native stake accounts for stake-weighted polls.
+//!
+//! Reads the bincode `StakeStateV2` layout directly (authorities and delegation only), so the
+//! program doesn't need the stake program crates.
+
+use anchor_lang::prelude::*;
+
+use crate::VotingError;
+
+/// Native stake program.
+pub mod stake_program {
+    anchor_lang::declare_id!("Stake11111111111111111111111111111111111111");
+}
+
+const STATE_LEN: usize = 200; // `StakeStateV2::size_of()`.
+const TAG_STAKE: u32 = 2; // `StakeStateV2::Stake` discriminant (delegated).
+
+/// Fields of a delegated stake account the program cares about.
+pub struct StakeAccount {
+    pub staker: Pubkey,           // May delegate/deactivate.
+    pub withdrawer: Pubkey,       // May withdraw / change authorities.
+    pub stake: u64,               // Delegated lamports.
+    pub activation_epoch: u64,    // Epoch the delegation started.
+    pub deactivation_epoch: u64,  // `u64::MAX` unless deactivating/deactivated.
+}
+
+impl StakeAccount {
+    /// Delegated, activated before `epoch` and not deactivating. Warmup isn't modelled: the
+    /// whole delegation counts from the epoch after activation.
+    pub fn is_active(&self, epoch: u64) -> bool {
+        self.activation_epoch < epoch && self.deactivation_epoch == u64::MAX
+    }
+}
+
+fn read_u64(data: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(data[at..at + 8].try_into().unwrap())
+}
+
+/// Reads a delegated stake account, failing with `InvalidStakeAccount` for anything else
+/// (uninitialized, initialized-but-undelegated, or not owned by the stake program).
+pub fn unpack_stake_account(info: &AccountInfo) -> Result<StakeAccount> {
+    require_keys_eq!(*info.owner, stake_program::ID, VotingError::InvalidStakeAccount);
+    let data = info.try_borrow_data()?;
+    require!(data.len() >= STATE_LEN, VotingError::InvalidStakeAccount); // Full state present.
+    let tag = u32::from_le_bytes(data[0..4].try_into().unwrap());
+    require!(tag == TAG_STAKE, VotingError::InvalidStakeAccount); // Must be delegated.
+    Ok(StakeAccount {
+        staker: Pubkey::try_from(&data[12..44]).unwrap(), // After tag + rent_exempt_reserve.
+        withdrawer: Pubkey::try_from(&data[44..76]).unwrap(),
+        stake: read_u64(&data, 156), // Delegation: voter_pubkey (124..156), then stake.
+        activation_epoch: read_u64(&data, 164),
+        deactivation_epoch: read_u64(&data, 172),
+    })
+}
@@ -0,0 +1,193 @@
+//! Minimal view of SPL Token accounts, so gating does not pull in `anchor-spl`: account
+//! parsing, associated token addresses, and the few CPIs escrow weighting, voter rewards and
+//! token vote fees need (`Transfer`, `Burn`, `CloseAccount`, associated account creation).
+//!
+//! Only the fixed base layout shared by Token and Token-2022 is read (first 165 bytes of an
+//! account, first 82 bytes of a mint); extensions are ignored.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+use crate::VotingError;
+
+/// SPL Token program.
+pub mod spl_token {
+    anchor_lang::declare_id!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+}
+
+/// SPL Token-2022 program (same base layout).
+pub mod spl_token_2022 {
+    anchor_lang::declare_id!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+}
+
+/// Associated Token Account program.
+pub mod associated_token {
+    anchor_lang::declare_id!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+}
+
+const ACCOUNT_LEN: usize = 165; // Base token account size.
+const BURN: u8 = 8; // Token program `Burn`.
+const CLOSE_ACCOUNT: u8 = 9; // Token program `CloseAccount`.
+const CREATE_IDEMPOTENT: u8 = 1; // Associated Token Account `CreateIdempotent`.
+
+/// Fields of a token account the program cares about.
+pub struct TokenAccount {
+    pub mint: Pubkey,   // Mint of the held tokens.
+    pub owner: Pubkey,  // Wallet that owns the tokens.
+    pub amount: u64,    // Raw balance (base units).
+}
+
+/// True when `program` is one of the supported token programs.
+pub fn is_token_program(program: &Pubkey) -> bool {
+    *program == spl_token::ID || *program == spl_token_2022::ID
+}
+
+/// Address of `wallet`'s associated token account for `mint` under `token_program`.
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[wallet.as_ref(), token_program.as_ref(), mint.as_ref()],
+        &associated_token::ID,
+    )
+    .0
+}
+
+/// Reads an initialized token account, failing with `InvalidTokenAccount` otherwise.
+pub fn unpack_token_account(info: &AccountInfo) -> Result<TokenAccount> {
+    require!(is_token_program(info.owner), VotingError::InvalidTokenAccount); // Owned by a token program.
+    let data = info.try_borrow_data()?;
+    require!(data.len() >= ACCOUNT_LEN, VotingError::InvalidTokenAccount); // Base layout present.
+    let state = data[108]; // 0 = uninitialized, 1 = initialized, 2 = frozen.
+    require!(state != 0, VotingError::InvalidTokenAccount);
+    Ok(TokenAccount {
+        mint: Pubkey::try_from(&data[0..32]).unwrap(),
+        owner: Pubkey::try_from(&data[32..64]).unwrap(),
+        amount: u64::from_le_bytes(data[64..72].try_into().unwrap()),
+    })
+}
+
+/// CPI to the token program's `Transfer` (instruction 3). `authority` is the owner of `from`;
+/// pass signer seeds when it is a program PDA. Works for Token-2022 accounts whose mint has no
+/// extensions that require `TransferChecked` (e.g. transfer fees).
+pub fn transfer<'info>(
+    token_program: &AccountInfo<'info>,
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(is_token_program(token_program.key), VotingError::InvalidTokenAccount); // Known program only.
+    let mut data = vec![3u8]; // `Transfer` discriminator.
+    data.extend_from_slice(&amount.to_le_bytes());
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*from.key, false),
+            AccountMeta::new(*to.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[from.clone(), to.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// CPI to the token program's `Burn`: destroys `amount` tokens of `mint` held in `account`,
+/// lowering the mint's supply. `authority` is the owner (or an approved delegate) of `account`.
+pub fn burn<'info>(
+    token_program: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(is_token_program(token_program.key), VotingError::InvalidTokenAccount); // Known program only.
+    let mut data = vec![BURN];
+    data.extend_from_slice(&amount.to_le_bytes());
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*account.key, false),
+            AccountMeta::new(*mint.key, false),
+            AccountMeta::new_readonly(*authority.key, true),
+        ],
+        data,
+    };
+    invoke_signed(
+        &ix,
+        &[account.clone(), mint.clone(), authority.clone(), token_program.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// CPI to the token program's `CloseAccount`: sends `account`'s rent to `destination`.
+/// The token balance must be zero; `owner` signs, with seeds when it is a program PDA.
+pub fn close_account<'info>(
+    token_program: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+    owner: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    require!(is_token_program(token_program.key), VotingError::InvalidTokenAccount); // Known program only.
+    let ix = Instruction {
+        program_id: *token_program.key,
+        accounts: vec![
+            AccountMeta::new(*account.key, false),
+            AccountMeta::new(*destination.key, false),
+            AccountMeta::new_readonly(*owner.key, true),
+        ],
+        data: vec![CLOSE_ACCOUNT],
+    };
+    invoke_signed(
+        &ix,
+        &[account.clone(), destination.clone(), owner.clone(), token_program.clone()],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Creates `wallet`'s associated token account for `mint` if it doesn't exist yet.
+pub fn create_associated_token_account<'info>(
+    ata_program: &AccountInfo<'info>,
+    payer: &AccountInfo<'info>,
+    ata: &AccountInfo<'info>,
+    wallet: &AccountInfo<'info>,
+    mint: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: *ata_program.key,
+        accounts: vec![
+            AccountMeta::new(*payer.key, true),
+            AccountMeta::new(*ata.key, false),
+            AccountMeta::new_readonly(*wallet.key, false),
+            AccountMeta::new_readonly(*mint.key, false),
+            AccountMeta::new_readonly(*system_program.key, false),
+            AccountMeta::new_readonly(*token_program.key, false),
+        ],
+        data: vec![CREATE_IDEMPOTENT],
+    };
+    invoke_signed(
+        &ix,
+        &[
+            payer.clone(),
+            ata.clone(),
+            wallet.clone(),
+            mint.clone(),
+            system_program.clone(),
+            token_program.clone(),
+            ata_program.clone(),
+        ],
+        &[],
+    )?;
+    Ok(())
+}
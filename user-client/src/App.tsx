@@ -19,8 +19,21 @@ type PollAccount = {
   votes: number[];
   startTs: number;
   endTs: number;
+  allowlistRequired: boolean;
+  gateMint: PublicKey | null;
+  weightMint: PublicKey | null; // Set when ballots are weighted by token balance.
+  escrow: boolean; // Voting locks the weight tokens until `unlock_tokens`.
+  voteFee: number; // Lamports each ballot pays into the poll treasury (0 = free).
+  feeMint: PublicKey | null; // Token each ballot pays `feeAmount` of instead, if set.
+  feeVault: PublicKey; // Poll's token account collecting those fees.
+  burnMint: PublicKey | null; // Burn-to-vote token, if set.
 };
 
+// Mint whose balance weighs ballots, for the token-weighted `VoteWeighting` variants.
+function weightMintOf(weighting: any): PublicKey | null {
+  return weighting.tokenBalance?.mint ?? weighting.quadraticToken?.mint ?? weighting.escrow?.mint ?? null;
+}
+
 const parsedIdl = idl as unknown as VotingIdl;
 const PROGRAM_ID = new PublicKey(
   import.meta.env.VITE_PROGRAM_ID || parsedIdl.address || "DddwKhB21GsneUinJyEN7Uax3BoePhCgqcU68FTWX7bi"
@@ -45,6 +58,44 @@ function deriveVoterPda(poll: PublicKey, wallet: PublicKey): PublicKey {
   return pda;
 }
 
+function deriveBanPda(poll: PublicKey, wallet: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("ban"), poll.toBuffer(), wallet.toBuffer()],
+    PROGRAM_ID
+  );
+  return pda;
+}
+
+function deriveEscrowPda(poll: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync([Buffer.from("escrow"), poll.toBuffer()], PROGRAM_ID);
+  return pda;
+}
+
+const [CONFIG_PDA] = PublicKey.findProgramAddressSync([Buffer.from("config")], PROGRAM_ID);
+
+function deriveTreasuryPda(poll: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync([Buffer.from("treasury"), poll.toBuffer()], PROGRAM_ID);
+  return pda;
+}
+
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+
+function deriveAssociatedTokenAddress(owner: PublicKey, mint: PublicKey, tokenProgram: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [owner.toBuffer(), tokenProgram.toBuffer(), mint.toBuffer()],
+    ASSOCIATED_TOKEN_PROGRAM_ID
+  );
+  return pda;
+}
+
+function deriveCredentialPda(poll: PublicKey, wallet: PublicKey): PublicKey {
+  const [pda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("cred"), poll.toBuffer(), wallet.toBuffer()],
+    PROGRAM_ID
+  );
+  return pda;
+}
+
 async function formatError(err: unknown, connection?: Parameters<SendTransactionError["getLogs"]>[0]) {
   if (err instanceof SendTransactionError) {
     try {
@@ -122,6 +173,14 @@ function App() {
         votes: account.votes.map((v: any) => Number(v)),
         startTs: Number(account.startTs),
         endTs: Number(account.endTs),
+        allowlistRequired: account.allowlistRequired,
+        gateMint: account.gateMint ?? null,
+        weightMint: weightMintOf(account.weighting),
+        escrow: !!account.weighting.escrow,
+        voteFee: Number(account.voteFee),
+        feeMint: account.feeMint ?? null,
+        feeVault: account.feeVault,
+        burnMint: account.burnMint ?? null,
       };
       setPoll(parsed);
       setStatus("Poll loaded.");
@@ -159,6 +218,14 @@ function App() {
           votes: (item.account.votes as any[]).map((v) => Number(v)),
           startTs: Number(item.account.startTs),
           endTs: Number(item.account.endTs),
+          allowlistRequired: item.account.allowlistRequired as boolean,
+          gateMint: (item.account.gateMint as PublicKey | null) ?? null,
+          weightMint: weightMintOf(item.account.weighting),
+          escrow: !!item.account.weighting.escrow,
+          voteFee: Number(item.account.voteFee),
+          feeMint: (item.account.feeMint as PublicKey | null) ?? null,
+          feeVault: item.account.feeVault as PublicKey,
+          burnMint: (item.account.burnMint as PublicKey | null) ?? null,
         }))
         .sort((a: PollAccount, b: PollAccount) => b.startTs - a.startTs);
       const latest = sorted[0];
@@ -204,13 +271,56 @@ function App() {
       setStatus("Sending vote...");
       const pollPk = new PublicKey(pollAddress);
       const voterPda = deriveVoterPda(pollPk, wallet.publicKey);
+      // Token-gated / token-weighted polls need one of the voter's token accounts for the mint.
+      const findTokenAccount = async (owner: PublicKey, mint: PublicKey | null) => {
+        if (!mint) return null;
+        const held = await program.provider.connection.getTokenAccountsByOwner(owner, { mint });
+        return held.value[0] ?? null;
+      };
+      const gateTokenAccount = (await findTokenAccount(wallet.publicKey, poll.gateMint))?.pubkey ?? null;
+      const weightHolding = await findTokenAccount(wallet.publicKey, poll.weightMint);
+      // Escrow polls move the tokens into a vault owned by the poll's escrow PDA.
+      const escrowVault = poll.escrow
+        ? (await findTokenAccount(deriveEscrowPda(pollPk), poll.weightMint))?.pubkey ?? null
+        : null;
+      // Token-fee polls debit the voter's associated token account for the fee mint.
+      const feeTokenProgram = poll.feeMint
+        ? (await program.provider.connection.getAccountInfo(poll.feeMint))?.owner ?? null
+        : null;
+      const feeTokenAccount =
+        poll.feeMint && feeTokenProgram
+          ? deriveAssociatedTokenAddress(wallet.publicKey, poll.feeMint, feeTokenProgram)
+          : null;
+      // Burn-to-vote polls burn from one of the voter's token accounts for the burn mint.
+      const burnHolding = await findTokenAccount(wallet.publicKey, poll.burnMint);
       await program.methods
-        .vote(selectedIdx)
+        .vote(selectedIdx, [], null) // No merkle/snapshot proofs; those polls need them from the organizer.
         .accounts({
           poll: pollPk,
           authority: poll.authority,
           voter: voterPda,
           wallet: wallet.publicKey,
+          // Allowlisted polls need the credential issued by the authority; others pass none.
+          credential: poll.allowlistRequired ? deriveCredentialPda(pollPk, wallet.publicKey) : null,
+          ban: deriveBanPda(pollPk, wallet.publicKey), // Empty unless the wallet is banned.
+          attestation: null, // Registrar polls need the attestation + identity PDAs.
+          identity: null,
+          gateTokenAccount,
+          nftTokenAccount: null, // Collection-gated polls need the NFT token + metadata accounts.
+          nftMetadata: null,
+          weightTokenAccount: weightHolding?.pubkey ?? null,
+          stakeAccount: null, // Stake-weighted polls need one of the voter's delegated stake accounts.
+          escrowVault,
+          tokenProgram: poll.escrow
+            ? weightHolding?.account.owner ?? null
+            : feeTokenProgram ?? burnHolding?.account.owner ?? null,
+          rankedTally: null, // Only `vote_ranked` needs it.
+          treasury: poll.voteFee > 0 ? deriveTreasuryPda(pollPk) : null, // Receives the vote fee.
+          feeTokenAccount, // Pays the token fee, if any.
+          feeVault: poll.feeMint ? poll.feeVault : null,
+          burnTokenAccount: burnHolding?.pubkey ?? null,
+          burnMint: poll.burnMint,
+          protocolConfig: CONFIG_PDA, // Checked for the protocol-wide pause.
           systemProgram: SystemProgram.programId,
         })
         .rpc();